@@ -0,0 +1,90 @@
+//! End-to-end `theshit fix` latency against every bundled native rule plus a varying number of
+//! Python rules. Run with `cargo bench`; set `THESHIT_BENCH_ITERATIONS` to change the sample
+//! size.
+use std::env;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+const PYTHON_RULE_COUNTS: &[usize] = &[0, 10, 50, 100];
+const DEFAULT_ITERATIONS: usize = 20;
+
+/// A home whose rules dir holds the bundled native rules and `python_rules` rules that never
+/// match, so every rule is evaluated on each run.
+fn bench_home(python_rules: usize) -> tempfile::TempDir {
+    let home = tempfile::tempdir().expect("Failed to create temp dir");
+    let active = home.path().join(".config/theshit/fix_rules/active");
+    fs::create_dir_all(&active).expect("Failed to create rules dir");
+    let bundled = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/rules/active");
+    for entry in fs::read_dir(bundled).expect("Failed to read bundled rules") {
+        let path = entry.expect("Failed to read bundled rule").path();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "native")
+        {
+            fs::copy(
+                &path,
+                active.join(path.file_name().expect("Rule has a name")),
+            )
+            .expect("Failed to copy rule");
+        }
+    }
+    for index in 0..python_rules {
+        let path = active.join(format!("bench_rule_{index}.py"));
+        fs::write(
+            &path,
+            "def match(command, stdout, stderr):\n    return 'never matches' in stderr\n\n\
+             def fix(command, stdout, stderr):\n    return command\n",
+        )
+        .expect("Failed to write rule");
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600))
+            .expect("Failed to set permissions");
+    }
+    home
+}
+
+fn run_fix(home: &Path) -> Duration {
+    let start = Instant::now();
+    let output = Command::new(env!("CARGO_BIN_EXE_theshit"))
+        .args(["--shell", "bash", "fix", "--mode", "auto"])
+        .args(["--command", "cs /tmp", "--stderr", ""])
+        .env("HOME", home)
+        .env("XDG_CONFIG_HOME", home.join(".config"))
+        .env("XDG_CACHE_HOME", home.join(".cache"))
+        .env_remove("SH_HISTORY")
+        .current_dir(home)
+        .output()
+        .expect("Failed to run theshit");
+    let elapsed = start.elapsed();
+    assert!(output.status.success(), "theshit fix failed: {output:?}");
+    elapsed
+}
+
+fn main() {
+    let iterations = env::var("THESHIT_BENCH_ITERATIONS")
+        .ok()
+        .and_then(|iterations| iterations.parse().ok())
+        .unwrap_or(DEFAULT_ITERATIONS);
+    println!(
+        "{:>12} {:>10} {:>10} {:>10}",
+        "python rules", "min", "median", "max"
+    );
+    for &python_rules in PYTHON_RULE_COUNTS {
+        let home = bench_home(python_rules);
+        // The first run pays for compiling the rules to bytecode.
+        run_fix(home.path());
+        let mut samples = (0..iterations)
+            .map(|_| run_fix(home.path()))
+            .collect::<Vec<_>>();
+        samples.sort();
+        println!(
+            "{:>12} {:>10.2?} {:>10.2?} {:>10.2?}",
+            python_rules,
+            samples[0],
+            samples[samples.len() / 2],
+            samples[samples.len() - 1]
+        );
+    }
+}
@@ -1,4 +1,11 @@
-use clap::{Parser, Subcommand};
+use crate::config::Mode;
+use crate::fix::OutputFormat;
+use crate::misc;
+use crate::shells::{self, CompletionSpec, Shell};
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use std::env;
+use std::path::Path;
+use std::str::FromStr;
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
@@ -7,6 +14,13 @@ pub struct Cli {
     pub command: Command,
     #[arg(long, short, help = "Specify the shell to use (e.g., bash, zsh)")]
     pub shell: Option<String>,
+    #[arg(
+        long,
+        short,
+        global = true,
+        help = "Only print fixed commands and hard errors (also THESHIT_QUIET=1)"
+    )]
+    pub quiet: bool,
 }
 
 #[derive(Subcommand)]
@@ -15,9 +29,362 @@ pub enum Command {
         #[arg(default_value_t = String::from("shit"))]
         name: String,
     },
-    Fix,
+    Fix {
+        #[arg(long, help = "Command to fix instead of SH_PREV_CMD")]
+        command: Option<String>,
+        #[arg(long, help = "Standard output of the command; skips running it")]
+        stdout: Option<String>,
+        #[arg(long, help = "Standard error of the command; skips running it")]
+        stderr: Option<String>,
+        #[arg(long, help = "Exit code of the command; skips running it")]
+        exit_code: Option<i32>,
+        #[arg(
+            long,
+            default_value_t = 1,
+            value_parser = clap::value_parser!(u32).range(1..),
+            help = "How many recent commands to try, stopping at the first fixable one"
+        )]
+        history_depth: u32,
+        #[arg(long, help = "Print details about skipped rules")]
+        verbose: bool,
+        #[arg(long, help = "Print how long each stage of fixing took to stderr")]
+        profile: bool,
+        #[arg(
+            long,
+            value_parser = Mode::from_str,
+            help = "What to do with the fixes: auto, confirm or suggest (overrides the config)"
+        )]
+        mode: Option<Mode>,
+        #[arg(
+            long,
+            value_parser = OutputFormat::from_str,
+            default_value = "shell",
+            help = "How to print the fix to run: shell, or argv for a JSON array of its arguments"
+        )]
+        format: OutputFormat,
+        #[arg(
+            long,
+            requires = "command",
+            conflicts_with_all = ["stdout", "stderr", "exit_code"],
+            help = "Fix the command being typed (given with --command) without running it"
+        )]
+        current: bool,
+    },
+    #[command(about = "Print the previous command prefixed with sudo, like `sudo !!`")]
+    Sudo,
+    #[command(about = "Inspect the built-in rules")]
+    Rules {
+        #[command(subcommand)]
+        action: RulesAction,
+    },
+    #[command(about = "Run a single native or Python rule on the given command and output")]
+    TestRule {
+        #[arg(help = "Rule name, as in the rules directory without the extension")]
+        name: String,
+        #[arg(long, help = "Command to test the rule on; it is not run")]
+        command: String,
+        #[arg(long, default_value = "", help = "Standard output to pass to the rule")]
+        stdout: String,
+        #[arg(long, default_value = "", help = "Standard error to pass to the rule")]
+        stderr: String,
+        #[arg(long, help = "Exit code to pass to the rule")]
+        exit_code: Option<i32>,
+    },
+    #[command(about = "List the supported shells")]
+    Shells,
+    #[command(about = "Print a completion script for the given shell")]
+    Completions {
+        #[arg(value_parser = Shell::from_str, help = "Shell to generate completions for (bash, zsh, fish)")]
+        shell: Shell,
+    },
     Setup {
         #[arg(default_value_t = String::from("shit"))]
         name: String,
+        #[arg(
+            long,
+            help = "Rewrite an existing theshit setup, e.g. to rename the function"
+        )]
+        replace: bool,
     },
 }
+
+#[derive(Subcommand)]
+pub enum RulesAction {
+    #[command(about = "Describe what a native rule matches and what it suggests")]
+    Explain {
+        #[arg(help = "Rule name, as in the rules directory without the extension")]
+        name: String,
+    },
+}
+
+impl Cli {
+    /// Parses the arguments, attaching build metadata to `--version` only when it is requested,
+    /// so regular runs don't pay for shell detection twice.
+    pub fn parse_with_build_info() -> Self {
+        let mut command = Cli::command();
+        if env::args_os().any(|arg| arg == "--version") {
+            let shell = shells::get_current_shell();
+            let rules_dir = misc::get_rules_dir();
+            // clap only takes `&'static str` here; the process exits right after printing it.
+            let info = version_info(&enabled_features(), shell.as_ref(), rules_dir.as_deref());
+            command = command.long_version(info.leak() as &'static str);
+        }
+        Cli::from_arg_matches(&command.get_matches()).unwrap_or_else(|e| e.exit())
+    }
+}
+
+/// Describes the subcommands and their long options for the completion scripts.
+pub fn completion_spec() -> CompletionSpec {
+    let mut command = Cli::command();
+    command.build();
+    let long_options = |command: &clap::Command| {
+        command
+            .get_arguments()
+            .filter_map(|arg| arg.get_long())
+            .map(|long| format!("--{long}"))
+            .collect::<Vec<_>>()
+    };
+    CompletionSpec {
+        program: command.get_name().to_string(),
+        options: long_options(&command),
+        subcommands: command
+            .get_subcommands()
+            .filter(|subcommand| subcommand.get_name() != "help")
+            .map(|subcommand| (subcommand.get_name().to_string(), long_options(subcommand)))
+            .collect(),
+    }
+}
+
+fn enabled_features() -> Vec<&'static str> {
+    let mut features = vec![];
+    if cfg!(feature = "standard_panic") {
+        features.push("standard_panic");
+    }
+    if cfg!(feature = "lua") {
+        features.push("lua");
+    }
+    features
+}
+
+fn version_info(features: &[&str], shell: Option<&Shell>, rules_dir: Option<&Path>) -> String {
+    format!(
+        "{}\nfeatures: {}\nshell: {}\nrules: {}",
+        env!("CARGO_PKG_VERSION"),
+        if features.is_empty() {
+            "none".to_string()
+        } else {
+            features.join(", ")
+        },
+        shell.map_or("unknown", Shell::as_str),
+        rules_dir.map_or("unknown".to_string(), |dir| dir.display().to_string())
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_version_info_with_everything_known() {
+        let rules_dir = PathBuf::from("/home/user/.config/theshit/fix_rules");
+        let info = version_info(&["standard_panic"], Some(&Shell::Zsh), Some(&rules_dir));
+        assert_eq!(
+            info,
+            format!(
+                "{}\nfeatures: standard_panic\nshell: zsh\nrules: /home/user/.config/theshit/fix_rules",
+                env!("CARGO_PKG_VERSION")
+            )
+        );
+    }
+
+    #[test]
+    fn test_version_info_with_nothing_known() {
+        let info = version_info(&[], None, None);
+        assert!(info.starts_with(env!("CARGO_PKG_VERSION")));
+        assert!(info.contains("features: none"));
+        assert!(info.contains("shell: unknown"));
+        assert!(info.contains("rules: unknown"));
+    }
+
+    #[test]
+    fn test_fix_current_needs_command_and_no_output() {
+        let cli = Cli::try_parse_from(["theshit", "fix", "--current", "--command", "cs /tmp"])
+            .expect("Arguments should parse");
+        assert!(matches!(cli.command, Command::Fix { current: true, .. }));
+        assert!(Cli::try_parse_from(["theshit", "fix", "--current"]).is_err());
+        assert!(
+            Cli::try_parse_from([
+                "theshit",
+                "fix",
+                "--current",
+                "--command",
+                "x",
+                "--stderr",
+                "e"
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_fix_accepts_explicit_command_and_output() {
+        let cli = Cli::try_parse_from([
+            "theshit",
+            "fix",
+            "--command",
+            "git brnach",
+            "--stderr",
+            "git: 'brnach' is not a git command.",
+            "--exit-code",
+            "1",
+            "--history-depth",
+            "3",
+            "--verbose",
+            "--mode",
+            "suggest",
+        ])
+        .expect("Arguments should parse");
+        match cli.command {
+            Command::Fix {
+                command,
+                stdout,
+                stderr,
+                exit_code,
+                history_depth,
+                verbose,
+                profile,
+                mode,
+                format,
+                current,
+            } => {
+                assert!(!profile);
+                assert_eq!(command.as_deref(), Some("git brnach"));
+                assert_eq!(format, OutputFormat::Shell);
+                assert!(!current);
+                assert_eq!(history_depth, 3);
+                assert!(verbose);
+                assert_eq!(mode, Some(Mode::Suggest));
+                assert_eq!(stdout, None);
+                assert_eq!(
+                    stderr.as_deref(),
+                    Some("git: 'brnach' is not a git command.")
+                );
+                assert_eq!(exit_code, Some(1));
+            }
+            _ => panic!("Expected the fix subcommand"),
+        }
+    }
+
+    #[test]
+    fn test_rules_explain_takes_a_name() {
+        let cli = Cli::try_parse_from(["theshit", "rules", "explain", "sudo"])
+            .expect("Arguments should parse");
+        assert!(matches!(
+            cli.command,
+            Command::Rules {
+                action: RulesAction::Explain { name }
+            } if name == "sudo"
+        ));
+        assert!(Cli::try_parse_from(["theshit", "rules", "explain"]).is_err());
+    }
+
+    #[test]
+    fn test_fix_without_arguments() {
+        let cli = Cli::try_parse_from(["theshit", "fix"]).expect("Arguments should parse");
+        assert!(matches!(
+            cli.command,
+            Command::Fix {
+                command: None,
+                stdout: None,
+                stderr: None,
+                exit_code: None,
+                history_depth: 1,
+                verbose: false,
+                profile: false,
+                mode: None,
+                format: OutputFormat::Shell,
+                current: false
+            }
+        ));
+    }
+
+    #[test]
+    fn test_fix_rejects_zero_history_depth() {
+        assert!(Cli::try_parse_from(["theshit", "fix", "--history-depth", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_fix_format() {
+        let cli = Cli::try_parse_from(["theshit", "fix", "--format", "argv"])
+            .expect("Arguments should parse");
+        assert!(matches!(
+            cli.command,
+            Command::Fix {
+                format: OutputFormat::Argv,
+                ..
+            }
+        ));
+        assert!(Cli::try_parse_from(["theshit", "fix", "--format", "json"]).is_err());
+    }
+
+    #[test]
+    fn test_fix_rejects_unknown_mode() {
+        assert!(Cli::try_parse_from(["theshit", "fix", "--mode", "yolo"]).is_err());
+    }
+
+    #[test]
+    fn test_completion_spec() {
+        let spec = completion_spec();
+        assert_eq!(spec.program, "theshit");
+        assert!(spec.options.contains(&"--shell".to_string()));
+        let (_, fix_options) = spec
+            .subcommands
+            .iter()
+            .find(|(name, _)| name == "fix")
+            .expect("fix subcommand should be listed");
+        assert!(fix_options.contains(&"--history-depth".to_string()));
+        assert!(!spec.subcommands.iter().any(|(name, _)| name == "help"));
+    }
+
+    #[test]
+    fn test_completions_generate_for_each_shell() {
+        let spec = completion_spec();
+        for name in ["bash", "zsh", "fish"] {
+            let cli = Cli::try_parse_from(["theshit", "completions", name])
+                .expect("Arguments should parse");
+            let Command::Completions { shell } = cli.command else {
+                panic!("Expected the completions subcommand");
+            };
+            let script = shell.get_completions(&spec);
+            assert!(
+                script.contains("theshit"),
+                "{name} script names the program"
+            );
+            assert!(
+                script.contains("completions"),
+                "{name} script lists subcommands"
+            );
+            assert!(
+                script.contains("history-depth"),
+                "{name} script lists options"
+            );
+        }
+        assert!(Cli::try_parse_from(["theshit", "completions", "tcsh"]).is_err());
+    }
+
+    #[test]
+    fn test_setup_replace() {
+        let cli = Cli::try_parse_from(["theshit", "setup", "oops", "--replace"])
+            .expect("Arguments should parse");
+        assert!(matches!(
+            cli.command,
+            Command::Setup { name, replace: true } if name == "oops"
+        ));
+    }
+
+    #[test]
+    fn test_cli_definition_is_valid() {
+        Cli::command().debug_assert();
+    }
+}
@@ -0,0 +1,614 @@
+//! User configuration read from `~/.config/theshit/config.toml`, with a project's
+//! `.theshit.toml` merged on top.
+//!
+//! Only the subset of TOML the options need is understood: `[section]` headers, `key = value`
+//! pairs with string, integer, boolean or array values (arrays may span lines), and `#`
+//! comments.
+use crate::error::{AppError, AppResult};
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use strum::EnumString;
+
+/// What to do with the fixes once they are found.
+#[derive(EnumString, Debug, Clone, Copy, PartialEq, Default)]
+pub enum Mode {
+    /// Emit the top fix for the shell to run right away.
+    #[strum(serialize = "auto")]
+    Auto,
+
+    /// Let the user pick a fix interactively.
+    #[default]
+    #[strum(serialize = "confirm")]
+    Confirm,
+
+    /// Only list the fixes on stderr; nothing is run.
+    #[strum(serialize = "suggest")]
+    Suggest,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    String(String),
+    Integer(i64),
+    Bool(bool),
+    Array(Vec<Value>),
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Config {
+    pub mode: Option<Mode>,
+    /// Rules from `fix_rules/additional` to load as well, by file stem.
+    pub enabled_rules: Vec<String>,
+    /// Rules to skip even though they are active, by file stem.
+    pub disabled_rules: Vec<String>,
+    /// Confidence overrides, by file stem.
+    pub priorities: HashMap<String, u8>,
+    /// Extra commands `to_cd` treats as a mistyped `cd`, on top of its built-in ones.
+    pub cd_typos: Vec<String>,
+    /// KB kept from the start and from the end of each output stream passed to rules.
+    pub output_keep_kb: Option<usize>,
+    /// Seconds after which `confirm` mode runs the top fix unless the user declines.
+    pub auto_confirm_timeout: Option<u64>,
+}
+
+impl Config {
+    /// Loads the global config merged with the nearest `.theshit.toml`; a missing file is the
+    /// same as an empty one.
+    pub fn load() -> AppResult<Config> {
+        let global = match get_config_path() {
+            Some(path) => Config::load_from(&path)?,
+            None => Config::default(),
+        };
+        let project = match std::env::current_dir()
+            .ok()
+            .and_then(|cwd| find_project_config(&cwd))
+        {
+            Some(path) => Config::load_from(&path)?,
+            None => Config::default(),
+        };
+        Ok(global.merge(project))
+    }
+
+    pub fn load_from(path: &Path) -> AppResult<Config> {
+        match fs::read_to_string(path) {
+            Ok(raw) => Config::parse(&raw).map_err(|e| match e {
+                AppError::Config(message) => {
+                    AppError::Config(format!("{}: {}", path.display(), message))
+                }
+                other => other,
+            }),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Config::default()),
+            Err(e) => Err(AppError::Io(e)),
+        }
+    }
+
+    pub fn parse(raw: &str) -> AppResult<Config> {
+        let mut config = Config::default();
+        for (key, value) in parse_toml(raw)? {
+            match key.as_str() {
+                "mode" => {
+                    let mode = expect_string(&key, value)?;
+                    config.mode = Some(Mode::from_str(&mode).map_err(|_| {
+                        AppError::Config(format!(
+                            "invalid mode '{mode}', expected auto, confirm or suggest"
+                        ))
+                    })?);
+                }
+                "auto_confirm_timeout" => {
+                    config.auto_confirm_timeout = Some(expect_size(&key, value)? as u64)
+                }
+                "rules.enabled" => config.enabled_rules = expect_string_array(&key, value)?,
+                "rules.disabled" => config.disabled_rules = expect_string_array(&key, value)?,
+                "to_cd.typos" => config.cd_typos = expect_string_array(&key, value)?,
+                "output.keep_kb" => config.output_keep_kb = Some(expect_size(&key, value)?),
+                _ => {
+                    if let Some(rule) = key.strip_prefix("priority.") {
+                        let priority = expect_priority(&key, value)?;
+                        config.priorities.insert(rule.to_string(), priority);
+                    }
+                    // Other unknown keys are ignored so configs stay usable across versions.
+                }
+            }
+        }
+        Ok(config)
+    }
+
+    /// Layers `project` over `self`. A project only chooses rules: a rule it enables or disables
+    /// overrides the opposite choice made globally and its priorities win. Everything else,
+    /// notably `mode` and `auto_confirm_timeout`, stays global, so a cloned repository can't make
+    /// fixes run without asking.
+    pub fn merge(mut self, project: Config) -> Config {
+        self.enabled_rules
+            .retain(|rule| !project.disabled_rules.contains(rule));
+        self.disabled_rules
+            .retain(|rule| !project.enabled_rules.contains(rule));
+        self.enabled_rules.extend(project.enabled_rules);
+        self.disabled_rules.extend(project.disabled_rules);
+        self.priorities.extend(project.priorities);
+        self
+    }
+
+    pub fn is_enabled(&self, rule: &str) -> bool {
+        self.enabled_rules.iter().any(|enabled| enabled == rule)
+    }
+
+    pub fn is_disabled(&self, rule: &str) -> bool {
+        self.disabled_rules.iter().any(|disabled| disabled == rule)
+    }
+
+    pub fn priority(&self, rule: &str) -> Option<u8> {
+        self.priorities.get(rule).copied()
+    }
+}
+
+/// Looks for `.theshit.toml` from `start` upwards, stopping at the repository root.
+pub fn find_project_config(start: &Path) -> Option<PathBuf> {
+    for dir in start.ancestors() {
+        let path = dir.join(".theshit.toml");
+        if path.is_file() {
+            return Some(path);
+        }
+        if dir.join(".git").exists() {
+            break;
+        }
+    }
+    None
+}
+
+pub fn get_config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("theshit/config.toml"))
+}
+
+pub(crate) fn expect_string(key: &str, value: Value) -> AppResult<String> {
+    match value {
+        Value::String(value) => Ok(value),
+        other => Err(AppError::Config(format!(
+            "'{key}' must be a string, got {other:?}"
+        ))),
+    }
+}
+
+fn expect_string_array(key: &str, value: Value) -> AppResult<Vec<String>> {
+    match value {
+        Value::Array(values) => values
+            .into_iter()
+            .map(|value| expect_string(key, value))
+            .collect(),
+        other => Err(AppError::Config(format!(
+            "'{key}' must be an array of strings, got {other:?}"
+        ))),
+    }
+}
+
+fn expect_size(key: &str, value: Value) -> AppResult<usize> {
+    match value {
+        Value::Integer(size) if size > 0 => Ok(size as usize),
+        other => Err(AppError::Config(format!(
+            "'{key}' must be a positive integer, got {other:?}"
+        ))),
+    }
+}
+
+pub(crate) fn expect_priority(key: &str, value: Value) -> AppResult<u8> {
+    match value {
+        Value::Integer(priority @ 0..=100) => Ok(priority as u8),
+        other => Err(AppError::Config(format!(
+            "'{key}' must be an integer from 0 to 100, got {other:?}"
+        ))),
+    }
+}
+
+/// Flattens the document into `(section.key, value)` pairs in file order.
+pub(crate) fn parse_toml(raw: &str) -> AppResult<Vec<(String, Value)>> {
+    let mut section = String::new();
+    let mut entries = vec![];
+    let mut lines = raw.lines().enumerate();
+    while let Some((index, line)) = lines.next() {
+        let error = |message: &str| AppError::Config(format!("line {}: {}", index + 1, message));
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[') {
+            section = name
+                .strip_suffix(']')
+                .map(|name| name.trim().to_string())
+                .filter(|name| !name.is_empty())
+                .ok_or_else(|| error("invalid section header"))?;
+            continue;
+        }
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| error("expected 'key = value'"))?;
+        let key = unquote_key(key.trim()).ok_or_else(|| error("invalid key"))?;
+        let mut value = value.trim().to_string();
+        // An array may span lines: keep reading until it parses or the document ends.
+        if value.starts_with('[') {
+            while parse_value(&value).is_none() {
+                let Some((_, next)) = lines.next() else {
+                    break;
+                };
+                value.push(' ');
+                value.push_str(strip_comment(next).trim());
+            }
+        }
+        let (value, rest) = parse_value(&value).ok_or_else(|| error("invalid value"))?;
+        if !rest.trim().is_empty() {
+            return Err(error("unexpected trailing characters"));
+        }
+        let key = if section.is_empty() {
+            key
+        } else {
+            format!("{section}.{key}")
+        };
+        entries.push((key, value));
+    }
+    Ok(entries)
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut escaped = false;
+    for (index, c) in line.char_indices() {
+        match (quote, c) {
+            (Some('"'), '\\') if !escaped => {
+                escaped = true;
+                continue;
+            }
+            (Some(q), c) if c == q && !escaped => quote = None,
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '#') => return &line[..index],
+            _ => {}
+        }
+        escaped = false;
+    }
+    line
+}
+
+fn unquote_key(key: &str) -> Option<String> {
+    if let Some(key) = key.strip_prefix('"') {
+        return key.strip_suffix('"').map(str::to_string);
+    }
+    let bare = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '-';
+    (!key.is_empty() && key.chars().all(bare)).then(|| key.to_string())
+}
+
+/// Parses one value from the start of `raw`, returning it with the unparsed remainder.
+fn parse_value(raw: &str) -> Option<(Value, &str)> {
+    if let Some(rest) = raw.strip_prefix('"') {
+        let mut value = String::new();
+        let mut chars = rest.char_indices();
+        while let Some((index, c)) = chars.next() {
+            match c {
+                '"' => return Some((Value::String(value), &rest[index + 1..])),
+                '\\' => value.push(match chars.next()?.1 {
+                    'n' => '\n',
+                    't' => '\t',
+                    c @ ('"' | '\\') => c,
+                    _ => return None,
+                }),
+                c => value.push(c),
+            }
+        }
+        None
+    } else if let Some(rest) = raw.strip_prefix('\'') {
+        let end = rest.find('\'')?;
+        Some((Value::String(rest[..end].to_string()), &rest[end + 1..]))
+    } else if let Some(mut rest) = raw.strip_prefix('[') {
+        let mut values = vec![];
+        loop {
+            rest = rest.trim_start();
+            if let Some(rest) = rest.strip_prefix(']') {
+                return Some((Value::Array(values), rest));
+            }
+            let (value, remainder) = parse_value(rest)?;
+            values.push(value);
+            rest = remainder.trim_start();
+            match rest.strip_prefix(',') {
+                Some(remainder) => rest = remainder,
+                None if rest.starts_with(']') => {}
+                None => return None,
+            }
+        }
+    } else {
+        let end = raw
+            .find(|c: char| c.is_whitespace() || c == ',' || c == ']')
+            .unwrap_or(raw.len());
+        let (token, rest) = raw.split_at(end);
+        let value = match token {
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            token => Value::Integer(token.replace('_', "").parse().ok()?),
+        };
+        Some((value, rest))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_toml_values() {
+        let entries = parse_toml(
+            r#"
+# global options
+mode = "auto" # trailing comment
+count = 1_000
+enabled = false
+
+[rules]
+"quoted key" = 'C:\path'
+list = ["a", "b#c", 3]
+"#,
+        )
+        .expect("Document should parse");
+        assert_eq!(
+            entries,
+            vec![
+                ("mode".to_string(), Value::String("auto".to_string())),
+                ("count".to_string(), Value::Integer(1000)),
+                ("enabled".to_string(), Value::Bool(false)),
+                (
+                    "rules.quoted key".to_string(),
+                    Value::String("C:\\path".to_string())
+                ),
+                (
+                    "rules.list".to_string(),
+                    Value::Array(vec![
+                        Value::String("a".to_string()),
+                        Value::String("b#c".to_string()),
+                        Value::Integer(3)
+                    ])
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_toml_multiline_array() {
+        let entries = parse_toml(
+            r#"
+[rules]
+disabled = [
+  "sudo", # needs a password here
+  "git_push",
+]
+after = 1
+"#,
+        )
+        .expect("Document should parse");
+        assert_eq!(
+            entries,
+            vec![
+                (
+                    "rules.disabled".to_string(),
+                    Value::Array(vec![
+                        Value::String("sudo".to_string()),
+                        Value::String("git_push".to_string())
+                    ])
+                ),
+                ("rules.after".to_string(), Value::Integer(1)),
+            ]
+        );
+        let error = parse_toml("list = [\n  \"a\",\n").expect_err("Unclosed array should fail");
+        assert_eq!(
+            error.to_string(),
+            AppError::Config("line 1: invalid value".to_string()).to_string()
+        );
+    }
+
+    #[test]
+    fn test_load_from_reports_path_once() {
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let path = dir.path().join("config.toml");
+        fs::write(&path, "mode = auto\n").expect("Failed to write config");
+        let message = Config::load_from(&path)
+            .expect_err("Invalid config should fail")
+            .to_string();
+        assert_eq!(
+            message.matches("Configuration error").count(),
+            1,
+            "{message}"
+        );
+        assert!(message.contains(&format!("{}: line 1: invalid value", path.display())));
+    }
+
+    #[test]
+    fn test_parse_toml_errors() {
+        assert!(parse_toml("mode").is_err());
+        assert!(parse_toml("mode = \"auto").is_err());
+        assert!(parse_toml("mode = auto").is_err());
+        assert!(parse_toml("[rules").is_err());
+        assert!(parse_toml("mode = \"auto\" extra").is_err());
+    }
+
+    #[test]
+    fn test_config_mode() {
+        for (raw, mode) in [
+            ("mode = \"auto\"", Mode::Auto),
+            ("mode = \"confirm\"", Mode::Confirm),
+            ("mode = \"suggest\"", Mode::Suggest),
+        ] {
+            let config = Config::parse(raw).expect("Config should parse");
+            assert_eq!(config.mode, Some(mode));
+        }
+        assert_eq!(Config::parse("").expect("Config should parse").mode, None);
+        assert!(Config::parse("mode = \"yolo\"").is_err());
+        assert!(Config::parse("mode = 1").is_err());
+    }
+
+    #[test]
+    fn test_config_ignores_unknown_keys() {
+        let config =
+            Config::parse("future = true\n[section]\nkey = 1").expect("Config should parse");
+        assert_eq!(config, Config::default());
+    }
+
+    #[test]
+    fn test_config_rules() {
+        let config = Config::parse(
+            r#"
+[rules]
+enabled = ["python_command"]
+disabled = ["sudo", "to_cd"]
+
+[priority]
+git_push = 90
+"#,
+        )
+        .expect("Config should parse");
+        assert!(config.is_enabled("python_command"));
+        assert!(config.is_disabled("sudo"));
+        assert!(config.is_disabled("to_cd"));
+        assert!(!config.is_disabled("python_command"));
+        assert_eq!(config.priority("git_push"), Some(90));
+        assert_eq!(config.priority("sudo"), None);
+
+        assert!(Config::parse("[rules]\nenabled = \"sudo\"").is_err());
+        assert!(Config::parse("[rules]\nenabled = [1]").is_err());
+        assert!(Config::parse("[priority]\nsudo = 101").is_err());
+        assert!(Config::parse("[priority]\nsudo = \"high\"").is_err());
+    }
+
+    #[test]
+    fn test_config_output_keep_kb() {
+        let config = Config::parse("[output]\nkeep_kb = 16").expect("Config should parse");
+        assert_eq!(config.output_keep_kb, Some(16));
+        assert!(Config::parse("[output]\nkeep_kb = 0").is_err());
+        assert!(Config::parse("[output]\nkeep_kb = \"16\"").is_err());
+    }
+
+    #[test]
+    fn test_config_auto_confirm_timeout() {
+        let config = Config::parse("auto_confirm_timeout = 5").expect("Config should parse");
+        assert_eq!(config.auto_confirm_timeout, Some(5));
+        assert_eq!(Config::default().auto_confirm_timeout, None);
+        assert!(Config::parse("auto_confirm_timeout = 0").is_err());
+        assert!(Config::parse("auto_confirm_timeout = true").is_err());
+    }
+
+    #[test]
+    fn test_config_cd_typos() {
+        let global = Config::parse("[to_cd]\ntypos = [\"dc\"]").expect("Config should parse");
+        assert_eq!(global.cd_typos, vec!["dc".to_string()]);
+        let project = Config::parse("[to_cd]\ntypos = [\"vd\"]").expect("Config should parse");
+        assert_eq!(global.merge(project).cd_typos, vec!["dc".to_string()]);
+        assert!(Config::parse("[to_cd]\ntypos = \"dc\"").is_err());
+    }
+
+    #[test]
+    fn test_merge_project_over_global() {
+        let global = Config::parse(
+            r#"
+mode = "confirm"
+[rules]
+enabled = ["python_command", "pager_hint"]
+disabled = ["sudo"]
+[priority]
+sudo = 10
+to_cd = 20
+"#,
+        )
+        .expect("Config should parse");
+        let project = Config::parse(
+            r#"
+mode = "auto"
+[rules]
+enabled = ["sudo"]
+disabled = ["pager_hint"]
+[priority]
+to_cd = 80
+"#,
+        )
+        .expect("Config should parse");
+
+        let merged = global.merge(project);
+        assert_eq!(merged.mode, Some(Mode::Confirm));
+        assert!(merged.is_enabled("python_command"));
+        assert!(merged.is_enabled("sudo"));
+        assert!(!merged.is_disabled("sudo"));
+        assert!(!merged.is_enabled("pager_hint"));
+        assert!(merged.is_disabled("pager_hint"));
+        assert_eq!(merged.priority("sudo"), Some(10));
+        assert_eq!(merged.priority("to_cd"), Some(80));
+    }
+
+    #[test]
+    fn test_merge_keeps_behaviour_settings_global() {
+        let project = Config::parse(
+            r#"
+mode = "auto"
+auto_confirm_timeout = 1
+[output]
+keep_kb = 1
+[to_cd]
+typos = ["xd"]
+"#,
+        )
+        .expect("Config should parse");
+
+        let merged = Config::default().merge(project.clone());
+        assert_eq!(merged, Config::default());
+
+        let global = Config::parse("mode = \"suggest\"\nauto_confirm_timeout = 5")
+            .expect("Config should parse");
+        let merged = global.clone().merge(project);
+        assert_eq!(merged.mode, Some(Mode::Suggest));
+        assert_eq!(merged.auto_confirm_timeout, Some(5));
+        assert_eq!(merged, global);
+    }
+
+    #[test]
+    fn test_merge_keeps_global_when_project_is_empty() {
+        let global = Config::parse("mode = \"suggest\"\n[rules]\ndisabled = [\"sudo\"]")
+            .expect("Config should parse");
+        let merged = global.clone().merge(Config::default());
+        assert_eq!(merged, global);
+    }
+
+    #[test]
+    fn test_find_project_config_walks_up() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let repo = temp_dir.path().join("repo");
+        let nested = repo.join("src/deep");
+        fs::create_dir_all(&nested).expect("Failed to create dirs");
+        fs::create_dir(repo.join(".git")).expect("Failed to create .git");
+        fs::write(repo.join(".theshit.toml"), "").expect("Failed to write config");
+
+        assert_eq!(
+            find_project_config(&nested),
+            Some(repo.join(".theshit.toml"))
+        );
+    }
+
+    #[test]
+    fn test_find_project_config_stops_at_repo_root() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let repo = temp_dir.path().join("repo");
+        fs::create_dir_all(repo.join(".git")).expect("Failed to create .git");
+        fs::write(temp_dir.path().join(".theshit.toml"), "").expect("Failed to write config");
+
+        assert_eq!(find_project_config(&repo), None);
+    }
+
+    #[test]
+    fn test_load_missing_file() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let config = Config::load_from(&temp_dir.path().join("config.toml"))
+            .expect("Missing config should load");
+        assert_eq!(config, Config::default());
+    }
+
+    #[test]
+    fn test_load_reports_path() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let path = temp_dir.path().join("config.toml");
+        fs::write(&path, "mode = \"yolo\"").expect("Failed to write config");
+        let error = Config::load_from(&path).expect_err("Invalid mode should fail");
+        assert!(error.to_string().contains("config.toml"));
+    }
+}
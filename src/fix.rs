@@ -1,70 +1,197 @@
+#[cfg(feature = "lua")]
+mod lua;
 mod python;
 mod rust;
 mod structs;
+mod toml_rules;
 
+pub use crate::fix::structs::CommandOutput;
+
+use crate::config::{Config, Mode};
+use crate::error::{AppError, AppResult};
 use crate::fix::rust::NativeRule;
-use crate::fix::structs::CommandOutput;
-use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers, read};
-use crossterm::style::Stylize;
+use crate::misc;
+use crate::misc::log::{debug, diagnostic};
+use crate::misc::profile::Stage;
+use crate::misc::theme;
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers, poll, read};
+use crossterm::queue;
+use crossterm::terminal::{Clear, ClearType};
+use std::collections::{HashMap, HashSet};
 use std::io::{ErrorKind, Write};
-use std::path::PathBuf;
-use std::process::Command;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
 use std::str::FromStr;
-use std::sync::mpsc;
-use std::time::Duration;
-use std::{fs, io, thread};
+use std::time::{Duration, Instant};
+use std::{fs, io};
 use structs::RawModeGuard;
+use strum::EnumString;
 
-pub fn fix_command(command: String, expand_command: String) -> io::Result<String> {
-    let command_output = match get_command_output(expand_command) {
+pub fn get_fixed_commands(
+    command: String,
+    expand_command: String,
+    output: Option<CommandOutput>,
+    cwd: PathBuf,
+    config: &Config,
+    verbose: bool,
+) -> io::Result<Vec<String>> {
+    let command_output = match output.map_or_else(|| get_command_output(expand_command), Ok) {
         Ok(output) => output,
         Err(e) => match e.kind() {
+            // Same exit codes a shell would report, for rules matching on them.
             ErrorKind::NotFound => CommandOutput::new(
                 "command not found".to_string(),
                 "command not found".to_string(),
-            ),
+            )
+            .with_exit_code(127),
             ErrorKind::PermissionDenied => CommandOutput::new(
                 "permission denied".to_string(),
                 "permission denied".to_string(),
-            ),
+            )
+            .with_exit_code(126),
             _ => {
-                eprintln!("{}: {}", "Error executing command".red(), e);
+                eprintln!("{}: {}", theme::error("Error executing command"), e);
                 return Err(e);
             }
         },
     };
-    let command_struct = structs::Command::new(command, command_output);
-    let active_rules_dir = dirs::config_dir()
-        .ok_or(ErrorKind::NotFound)?
-        .join("theshit/fix_rules/active");
-    let mut fixed_commands: Vec<String> = vec![];
-    let mut python_rules: Vec<PathBuf> = vec![];
-    for rule in fs::read_dir(active_rules_dir)? {
-        let rule = rule?;
-        let path = rule.path();
+    let keep_kb = config
+        .output_keep_kb
+        .unwrap_or(structs::DEFAULT_OUTPUT_KEEP_KB);
+    let command_output = command_output.truncated(keep_kb.saturating_mul(1024));
+    let command_struct = structs::Command::new(command, command_output).with_cwd(cwd);
+    collect_fixed_commands(&command_struct, &misc::get_rules_dirs(), config, verbose)
+}
 
-        let file_name = match path.file_name() {
-            Some(name) => name,
-            None => {
-                eprintln!(
-                    "{}: {}",
-                    "Skipping rule without filename".yellow(),
-                    path.display()
-                );
-                continue;
-            }
-        };
-        if file_name.to_string_lossy() == "__pycache__" {
+/// Fixes a command that is still being typed: nothing is run, and only native rules that can
+/// tell from the command text alone take part.
+pub fn get_current_fixes(
+    command: String,
+    cwd: PathBuf,
+    config: &Config,
+) -> io::Result<Vec<String>> {
+    let command_struct =
+        structs::Command::new(command, CommandOutput::new(String::new(), String::new()))
+            .with_cwd(cwd);
+    collect_current_fixes(&command_struct, &misc::get_rules_dirs(), config)
+}
+
+fn collect_current_fixes(
+    command_struct: &structs::Command,
+    rules_dirs: &[PathBuf],
+    config: &Config,
+) -> io::Result<Vec<String>> {
+    let mut fixed_commands: Vec<(String, u8)> = vec![];
+    for path in select_rule_paths(rules_dirs, config)? {
+        if path
+            .extension()
+            .is_none_or(|extension| extension != "native")
+        {
             continue;
         }
+        let name = rule_name(&path);
+        if let Ok(rule) = NativeRule::from_str(&name) {
+            let confidence = config.priority(&name).unwrap_or(rule.confidence());
+            fixed_commands.extend(
+                rule.fix_current(command_struct)
+                    .into_iter()
+                    .map(|fixed| (fixed, confidence)),
+            );
+        }
+    }
+    Ok(rank_candidates(fixed_commands))
+}
 
+/// Runs only the rule called `name` on the given command and output, for rule authors: config,
+/// ranking and every other rule are left out, and the command is never executed.
+pub fn test_rule(
+    name: &str,
+    command: String,
+    output: CommandOutput,
+    cwd: PathBuf,
+    verbose: bool,
+) -> AppResult<Vec<String>> {
+    let command_struct = structs::Command::new(command, output).with_cwd(cwd);
+    run_single_rule(&command_struct, name, &misc::get_rules_dirs(), verbose)
+}
+
+/// A native rule wins over a Python rule of the same name, as it can't be shadowed on disk;
+/// among Python rules the one in the directory with the highest precedence is run.
+fn run_single_rule(
+    command_struct: &structs::Command,
+    name: &str,
+    rules_dirs: &[PathBuf],
+    verbose: bool,
+) -> AppResult<Vec<String>> {
+    if let Ok(rule) = NativeRule::from_str(name) {
+        return Ok(rule.fix_native(command_struct));
+    }
+    let file_name = format!("{name}.py");
+    let mut rule_path = None;
+    for rules_dir in rules_dirs.iter().filter(|dir| dir.is_dir()) {
+        rule_path = collect_rule_paths(rules_dir)?
+            .into_iter()
+            .find(|path| path.file_name().is_some_and(|found| *found == *file_name))
+            .or(rule_path);
+    }
+    let rule_path = rule_path.ok_or_else(|| {
+        AppError::Other(format!(
+            "'{name}' is neither a native rule nor a Python rule"
+        ))
+    })?;
+    let fixed =
+        python::process_python_rules(command_struct, vec![rule_path], &HashMap::new(), verbose)?;
+    Ok(fixed.into_iter().map(|(fixed, _)| fixed).collect())
+}
+
+/// Describes the native rule called `name`, or `None` if there is no such rule.
+pub fn describe_native_rule(name: &str) -> Option<&'static str> {
+    NativeRule::from_str(name)
+        .ok()
+        .map(|rule| rule.description())
+}
+
+/// Returns the first command that has any fixes along with them, trying the commands in order.
+pub fn find_first_fixable<T, E, F>(
+    commands: Vec<T>,
+    mut get_fixes: F,
+) -> Result<Option<(T, Vec<String>)>, E>
+where
+    F: FnMut(&T) -> Result<Vec<String>, E>,
+{
+    for command in commands {
+        let fixed_commands = get_fixes(&command)?;
+        if !fixed_commands.is_empty() {
+            return Ok(Some((command, fixed_commands)));
+        }
+    }
+    Ok(None)
+}
+
+fn collect_fixed_commands(
+    command_struct: &structs::Command,
+    rules_dirs: &[PathBuf],
+    config: &Config,
+    verbose: bool,
+) -> io::Result<Vec<String>> {
+    let mut fixed_commands: Vec<(String, u8)> = vec![];
+    let mut fallback_commands: Vec<(String, u8)> = vec![];
+    let mut python_rules: Vec<PathBuf> = vec![];
+    #[cfg(feature = "lua")]
+    let mut lua_rules: Vec<PathBuf> = vec![];
+    let mut toml_rules: Vec<PathBuf> = vec![];
+    for path in select_rule_paths(rules_dirs, config)? {
         match path.extension() {
             Some(extension) => match extension.to_string_lossy().as_ref() {
                 "native" => {
                     let native_rule_name = match path.file_stem() {
                         Some(name) => name,
                         None => {
-                            eprintln!("{}{}", "Failed to get stem for: ".yellow(), path.display());
+                            diagnostic!(
+                                "{}{}",
+                                theme::warning("Failed to get stem for: "),
+                                path.display()
+                            );
                             continue;
                         }
                     };
@@ -72,43 +199,189 @@ pub fn fix_command(command: String, expand_command: String) -> io::Result<String
                         NativeRule::from_str(native_rule_name.to_string_lossy().as_ref());
                     match native_rule {
                         Ok(rule) => {
-                            if let Some(fixed) = rule.fix_native(&command_struct) {
-                                fixed_commands.push(fixed)
+                            let confidence = config
+                                .priority(native_rule_name.to_string_lossy().as_ref())
+                                .unwrap_or(rule.confidence());
+                            let fixed = misc::profile::timed(Stage::NativeRules, || {
+                                rule.fix_native(command_struct)
+                            });
+                            debug!(
+                                "native rule {}: {}",
+                                native_rule_name.to_string_lossy(),
+                                if fixed.is_empty() {
+                                    "no match"
+                                } else {
+                                    "matched"
+                                }
+                            );
+                            let fixed = fixed.into_iter().map(|fixed| (fixed, confidence));
+                            if rule.is_fallback() {
+                                fallback_commands.extend(fixed);
+                            } else {
+                                fixed_commands.extend(fixed);
                             }
                         }
                         Err(_) => {
-                            eprintln!(
+                            diagnostic!(
                                 "{}{}{}",
-                                "Native rule '".yellow(),
+                                theme::warning("Native rule '"),
                                 native_rule_name.to_string_lossy(),
-                                "' isn't supported".yellow()
+                                theme::warning("' isn't supported")
                             );
                             continue;
                         }
                     }
                 }
                 "py" => python_rules.push(path),
+                #[cfg(feature = "lua")]
+                "lua" => lua_rules.push(path),
+                "toml" => toml_rules.push(path),
                 _ => {
-                    eprintln!(
+                    diagnostic!(
                         "{}{}{}",
-                        "Rule type '".yellow(),
+                        theme::warning("Rule type '"),
                         path.display(),
-                        "' isn't supported".yellow()
+                        theme::warning("' isn't supported")
                     )
                 }
             },
             None => {
-                eprintln!("{}{}", "Can't get extension for ".yellow(), path.display())
+                diagnostic!(
+                    "{}{}",
+                    theme::warning("Can't get extension for "),
+                    path.display()
+                )
             }
         }
     }
-    if !python_rules.is_empty() {
-        match python::process_python_rules(&command_struct, python_rules) {
+    // Rules are imported relative to their own rules directory, so each is processed apart.
+    for python_rules in group_by_rules_dir(python_rules, rules_dirs) {
+        match python::process_python_rules(
+            command_struct,
+            python_rules,
+            &config.priorities,
+            verbose,
+        ) {
+            Ok(commands) => fixed_commands.extend(commands),
+            Err(e) => diagnostic!("{}: {}", theme::error("Python rules processing failed"), e),
+        }
+    }
+    #[cfg(feature = "lua")]
+    for lua_rules in group_by_rules_dir(lua_rules, rules_dirs) {
+        match lua::process_lua_rules(command_struct, lua_rules, &config.priorities, verbose) {
             Ok(commands) => fixed_commands.extend(commands),
-            Err(e) => eprintln!("{}: {}", "Python rules processing failed".red(), e),
+            Err(e) => diagnostic!("{}: {}", theme::error("Lua rules processing failed"), e),
         }
     }
-    Ok(choose_fixed_command(fixed_commands))
+    if !toml_rules.is_empty() {
+        match toml_rules::process_toml_rules(
+            command_struct,
+            toml_rules,
+            &config.priorities,
+            verbose,
+        ) {
+            Ok(commands) => fixed_commands.extend(commands),
+            Err(e) => diagnostic!("{}: {}", theme::error("TOML rules processing failed"), e),
+        }
+    }
+    if fixed_commands.is_empty() {
+        fixed_commands = fallback_commands;
+    }
+    Ok(rank_candidates(fixed_commands))
+}
+
+/// Picks the rules to run from every rules directory, lowest precedence first. A rule file in
+/// a later directory replaces every earlier one with the same stem, whether or not it is active
+/// itself, so moving a packaged rule to the user's `additional/` disables it.
+fn select_rule_paths(rules_dirs: &[PathBuf], config: &Config) -> io::Result<Vec<PathBuf>> {
+    let mut paths: Vec<PathBuf> = vec![];
+    for rules_dir in rules_dirs.iter().filter(|dir| dir.is_dir()) {
+        let mut shadowing = HashSet::new();
+        for folder in ["active", "additional"] {
+            let dir = rules_dir.join(folder);
+            if dir.is_dir() {
+                shadowing.extend(collect_rule_paths(&dir)?.iter().map(|path| rule_name(path)));
+            }
+        }
+        paths.retain(|path| !shadowing.contains(&rule_name(path)));
+        paths.extend(select_dir_rule_paths(rules_dir, config)?);
+    }
+    Ok(paths)
+}
+
+/// Picks the rules to run from one directory: everything in `active/` plus the rules the config
+/// enables from `additional/`, minus the ones it disables.
+fn select_dir_rule_paths(rules_dir: &Path, config: &Config) -> io::Result<Vec<PathBuf>> {
+    let active_dir = rules_dir.join("active");
+    let mut paths = if active_dir.is_dir() {
+        collect_rule_paths(&active_dir)?
+    } else {
+        vec![]
+    };
+    let additional_dir = rules_dir.join("additional");
+    if !config.enabled_rules.is_empty() && additional_dir.is_dir() {
+        paths.extend(
+            collect_rule_paths(&additional_dir)?
+                .into_iter()
+                .filter(|path| config.is_enabled(&rule_name(path))),
+        );
+    }
+    paths.retain(|path| !config.is_disabled(&rule_name(path)));
+    Ok(paths)
+}
+
+/// Splits rule files by the rules directory they come from, keeping their order.
+fn group_by_rules_dir(paths: Vec<PathBuf>, rules_dirs: &[PathBuf]) -> Vec<Vec<PathBuf>> {
+    let mut groups: Vec<(Option<&PathBuf>, Vec<PathBuf>)> = vec![];
+    for path in paths {
+        let rules_dir = rules_dirs.iter().find(|dir| path.starts_with(dir));
+        match groups.iter_mut().find(|(dir, _)| *dir == rules_dir) {
+            Some((_, group)) => group.push(path),
+            None => groups.push((rules_dir, vec![path])),
+        }
+    }
+    groups.into_iter().map(|(_, group)| group).collect()
+}
+
+/// Rules are referred to in the config by their file stem.
+fn rule_name(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Walks the rules directory recursively, skipping `__pycache__`, and returns the rule files
+/// sorted by path so the processing order doesn't depend on the filesystem.
+fn collect_rule_paths(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut paths = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.file_name().is_some_and(|name| name == "__pycache__") {
+            continue;
+        }
+        if path.is_dir() {
+            paths.extend(collect_rule_paths(&path)?);
+        } else {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Orders candidates by descending confidence; equal scores keep their discovery order. A
+/// command suggested by several rules is kept once, where its highest confidence puts it.
+fn rank_candidates(mut candidates: Vec<(String, u8)>) -> Vec<String> {
+    candidates.sort_by(|(_, a), (_, b)| b.cmp(a));
+    let mut seen = HashSet::new();
+    candidates.retain(|(command, _)| seen.insert(command.clone()));
+    for (position, (command, confidence)) in candidates.iter().enumerate() {
+        debug!(
+            "candidate {}: {command} (confidence {confidence})",
+            position + 1
+        );
+    }
+    candidates.into_iter().map(|(command, _)| command).collect()
 }
 
 fn get_command_timeout(command_name: &str) -> Duration {
@@ -150,41 +423,311 @@ fn get_command_output(expand_command: String) -> io::Result<CommandOutput> {
     }
 
     let timeout = get_command_timeout(&split_command[0]);
+    let deadline = Instant::now() + timeout;
 
-    let child = Command::new(&split_command[0])
+    let mut child = Command::new(&split_command[0])
         .args(&split_command[1..])
         .env("LANG", "C")
         .env("LC_ALL", "C")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
         .spawn()?;
 
-    let (sender, receiver) = mpsc::channel();
-
-    let _handle = thread::spawn(move || {
-        let result = child.wait_with_output();
-        let _ = sender.send(result);
-    });
-
-    match receiver.recv_timeout(timeout) {
-        Ok(Ok(output)) => Ok(CommandOutput::from(output)),
-        Ok(Err(e)) => Err(e),
-        Err(mpsc::RecvTimeoutError::Timeout) => Err(io::Error::new(
-            ErrorKind::TimedOut,
-            format!("Command timed out after {:?}", timeout),
-        )),
-        Err(mpsc::RecvTimeoutError::Disconnected) => {
-            Err(io::Error::other("Command thread disconnected unexpectedly"))
+    // Read without a helper thread: a thread left waiting on a hung command would make
+    // forking the Python rule workers unsafe.
+    let mut stdout = child.stdout.take().expect("stdout is piped");
+    let mut stderr = child.stderr.take().expect("stderr is piped");
+    let read = misc::pipe::read_to_end_by(&mut [&mut stdout, &mut stderr], deadline);
+    let status = match read {
+        Ok(Some(_)) => misc::pipe::wait_by(deadline, || child.try_wait()),
+        _ => Ok(None),
+    };
+    match (read, status) {
+        (Ok(Some(output)), Ok(Some(status))) => {
+            let [stdout, stderr] =
+                <[Vec<u8>; 2]>::try_from(output).expect("Output is read from exactly two pipes");
+            Ok(CommandOutput::from(Output {
+                status,
+                stdout,
+                stderr,
+            }))
+        }
+        (read, status) => {
+            let _ = child.kill();
+            let _ = child.wait();
+            read?;
+            status?;
+            Err(io::Error::new(
+                ErrorKind::TimedOut,
+                format!("Command timed out after {:?}", timeout),
+            ))
         }
     }
 }
 
-fn choose_fixed_command(mut fixed_commands: Vec<String>) -> String {
+/// How the fix to run is written to stdout.
+#[derive(EnumString, Debug, Clone, Copy, PartialEq, Default)]
+pub enum OutputFormat {
+    /// The command as is, for the shell to `eval`.
+    #[default]
+    #[strum(serialize = "shell")]
+    Shell,
+
+    /// The command split into its arguments, as a JSON array of strings, for callers that exec
+    /// it directly. Fixes that need a shell to run are left out.
+    #[strum(serialize = "argv")]
+    Argv,
+}
+
+/// Hands the fixes to the shell according to `mode`: only what is written to `out` gets
+/// evaluated, everything meant for the user goes to `err`. Returns `false` if there was no fix,
+/// in which case nothing is written to `out` so the shell doesn't re-run the broken command.
+pub fn emit_fixed_commands(
+    mode: Mode,
+    format: OutputFormat,
+    command: &str,
+    fixed_commands: Vec<String>,
+    auto_confirm: Option<Duration>,
+    out: &mut impl Write,
+    err: &mut impl Write,
+) -> io::Result<bool> {
+    let prompt = Prompt {
+        mode,
+        auto_confirm,
+        keys: &mut TerminalKeys::default(),
+        choose: |fixed_commands| Some(choose_fixed_command(fixed_commands)),
+    };
+    emit_fixed_commands_with(prompt, format, command, fixed_commands, out, err)
+}
+
+/// How the user is asked about the fixes; the keys and the picker are swapped out in tests.
+struct Prompt<'a, K: KeySource, C: FnOnce(Vec<String>) -> Option<String>> {
+    mode: Mode,
+    auto_confirm: Option<Duration>,
+    keys: &'a mut K,
+    choose: C,
+}
+
+fn emit_fixed_commands_with(
+    prompt: Prompt<impl KeySource, impl FnOnce(Vec<String>) -> Option<String>>,
+    format: OutputFormat,
+    command: &str,
+    fixed_commands: Vec<String>,
+    out: &mut impl Write,
+    err: &mut impl Write,
+) -> io::Result<bool> {
+    let found = !fixed_commands.is_empty();
+    let fixed_commands: Vec<String> = match format {
+        OutputFormat::Shell => fixed_commands,
+        OutputFormat::Argv => fixed_commands
+            .into_iter()
+            .filter(|fixed_command| {
+                let runnable = misc::split_argv(fixed_command).is_some();
+                if !runnable {
+                    debug!("{fixed_command}: needs a shell, left out of argv output");
+                }
+                runnable
+            })
+            .collect(),
+    };
     if fixed_commands.is_empty() {
-        eprintln!(
-            "{}: {}",
-            "No fixed commands found".yellow(),
-            "Exiting...".red()
-        );
-        std::process::exit(1);
+        if !misc::log::is_quiet() {
+            let message = if found {
+                format!("No fix can be run without a shell for: {command}")
+            } else {
+                format!("No fix found for: {command}")
+            };
+            writeln!(err, "{}", theme::muted(message))?;
+        }
+        return Ok(false);
+    }
+    let Prompt {
+        mode,
+        auto_confirm,
+        keys,
+        choose,
+    } = prompt;
+    let chosen = match mode {
+        Mode::Auto | Mode::Confirm if requires_confirmation(&fixed_commands[0]) => {
+            let reason = if has_placeholder(&fixed_commands[0]) {
+                "The fix has a placeholder to fill in, confirm it first:"
+            } else {
+                "The fix bypasses safety checks, confirm it first:"
+            };
+            writeln!(err, "{}", theme::warning(reason))?;
+            choose(fixed_commands)
+        }
+        Mode::Auto => fixed_commands.into_iter().next(),
+        Mode::Confirm => match auto_confirm {
+            Some(timeout) => {
+                let mut fixed_commands = fixed_commands;
+                confirm_with_countdown(&fixed_commands[0], timeout, keys, err)?
+                    .then(|| fixed_commands.swap_remove(0))
+            }
+            None => choose(fixed_commands),
+        },
+        Mode::Suggest => {
+            for fixed_command in fixed_commands {
+                writeln!(err, "{fixed_command}")?;
+            }
+            return Ok(true);
+        }
+    };
+    match chosen {
+        Some(fixed_command) => write_fix(out, format, &fixed_command)?,
+        None => return Ok(false),
+    }
+    Ok(true)
+}
+
+/// Flags that switch off checks the user set up on purpose (git hooks), so a fix using them is
+/// never run without asking, whatever the mode.
+static RISKY_FLAGS: &[&str] = &["--no-verify"];
+
+/// A template argument such as `<url>` that the user has to replace before running the fix.
+fn has_placeholder(command: &str) -> bool {
+    misc::split_command(command).iter().any(|word| {
+        word.strip_prefix('<')
+            .and_then(|word| word.strip_suffix('>'))
+            .is_some_and(|name| {
+                !name.is_empty() && name.chars().all(|c| c.is_ascii_lowercase() || c == '-')
+            })
+    })
+}
+
+fn requires_confirmation(command: &str) -> bool {
+    has_placeholder(command)
+        || misc::split_command(command)
+            .iter()
+            .any(|word| RISKY_FLAGS.contains(&word.as_str()))
+}
+
+/// Writes the fix to run in the requested format.
+pub fn write_fix(out: &mut impl Write, format: OutputFormat, command: &str) -> io::Result<()> {
+    match format {
+        OutputFormat::Shell => write_command(out, command),
+        OutputFormat::Argv => write_argv(out, command),
+    }
+}
+
+/// Writes a command as a JSON array of its arguments, on one line.
+pub fn write_argv(out: &mut impl Write, command: &str) -> io::Result<()> {
+    let argv = misc::split_argv(command).ok_or_else(|| {
+        io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("'{command}' can't be run without a shell"),
+        )
+    })?;
+    let items = argv
+        .iter()
+        .map(|arg| json_string(arg))
+        .collect::<Vec<_>>()
+        .join(",");
+    writeln!(out, "[{items}]")
+}
+
+fn json_string(value: &str) -> String {
+    let mut json = String::with_capacity(value.len() + 2);
+    json.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// Writes a command for the shell to eval: exactly the command and one newline, since trailing
+/// whitespace left by a rule can confuse `eval` in some shells.
+pub fn write_command(out: &mut impl Write, command: &str) -> io::Result<()> {
+    writeln!(out, "{}", command.trim_end())
+}
+
+/// Where key presses come from, so the countdown can be driven without a terminal.
+trait KeySource {
+    /// Waits up to `timeout` for a key press; `None` means the time ran out.
+    fn next_key(&mut self, timeout: Duration) -> io::Result<Option<KeyEvent>>;
+}
+
+/// Reads keys from the terminal, which is put in raw mode on the first read and restored when
+/// this is dropped.
+#[derive(Default)]
+struct TerminalKeys {
+    raw_mode: Option<RawModeGuard>,
+}
+
+impl KeySource for TerminalKeys {
+    fn next_key(&mut self, timeout: Duration) -> io::Result<Option<KeyEvent>> {
+        self.raw_mode.get_or_insert_with(RawModeGuard::new);
+        if !poll(timeout)? {
+            return Ok(None);
+        }
+        match read()? {
+            Event::Key(key) => Ok(Some(key)),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Asks whether to run `command`, counting down once a second and accepting when the time is
+/// up. Enter or `y` accept early; `n`, Esc or Ctrl+C decline.
+fn confirm_with_countdown(
+    command: &str,
+    timeout: Duration,
+    keys: &mut impl KeySource,
+    err: &mut impl Write,
+) -> io::Result<bool> {
+    let tick = Duration::from_secs(1);
+    let mut remaining = timeout;
+    let accepted = loop {
+        queue!(err, Clear(ClearType::CurrentLine))?;
+        write!(
+            err,
+            "\rRun `{command}`? [{}/{}] (auto in {}s)",
+            theme::success("Y"),
+            theme::error("n"),
+            remaining.as_secs_f64().ceil()
+        )?;
+        err.flush()?;
+        if remaining.is_zero() {
+            break true;
+        }
+        let wait = remaining.min(tick);
+        match keys.next_key(wait)? {
+            None => remaining -= wait,
+            Some(KeyEvent {
+                code, modifiers, ..
+            }) => match (code, modifiers) {
+                (KeyCode::Enter | KeyCode::Char('y' | 'Y'), _) => break true,
+                (KeyCode::Char('c'), KeyModifiers::CONTROL)
+                | (KeyCode::Esc | KeyCode::Char('n' | 'N'), _) => break false,
+                _ => {}
+            },
+        }
+    };
+    write!(err, "\r\n")?;
+    Ok(accepted)
+}
+
+fn exit_without_fixes() -> ! {
+    eprintln!(
+        "{}: {}",
+        theme::warning("No fixed commands found"),
+        theme::error("Exiting...")
+    );
+    std::process::exit(1);
+}
+
+pub fn choose_fixed_command(mut fixed_commands: Vec<String>) -> String {
+    if fixed_commands.is_empty() {
+        exit_without_fixes();
     }
 
     let mut current_command = fixed_commands
@@ -200,10 +743,10 @@ fn choose_fixed_command(mut fixed_commands: Vec<String>) -> String {
         format!(
             "{} [{}/{}/{}/{}]",
             current_command,
-            "enter".green(),
-            "↑".cyan(),
-            "↓".cyan(),
-            "Ctrl+C".red()
+            theme::success("enter"),
+            theme::hint("↑"),
+            theme::hint("↓"),
+            theme::error("Ctrl+C")
         )
         .as_bytes(),
     ) {
@@ -218,66 +761,70 @@ fn choose_fixed_command(mut fixed_commands: Vec<String>) -> String {
                 }) = event
                 {
                     match (code, modifiers) {
-                        (KeyCode::Up, _) => {
-                            if fixed_commands.len() > 1 {
-                                if current_index > 0 {
-                                    current_index -= 1;
-                                } else {
-                                    current_index = fixed_commands.len() - 1;
-                                }
-                                current_command = fixed_commands
-                                    .get(current_index)
-                                    .expect("current_index is within bounds");
-                                if let Err(e) = err.write_all(
-                                    format!(
-                                        "{} [{}/{}/{}/{}]",
-                                        current_command,
-                                        "enter".green(),
-                                        "↑".cyan(),
-                                        "↓".cyan(),
-                                        "Ctrl+C".red()
-                                    )
-                                    .as_bytes(),
-                                ) {
-                                    eprintln!("Warning: failed to write to stderr: {}", e);
-                                }
+                        (KeyCode::Up, _) if fixed_commands.len() > 1 => {
+                            if current_index > 0 {
+                                current_index -= 1;
+                            } else {
+                                current_index = fixed_commands.len() - 1;
+                            }
+                            current_command = fixed_commands
+                                .get(current_index)
+                                .expect("current_index is within bounds");
+                            if let Err(e) = err.write_all(
+                                format!(
+                                    "{} [{}/{}/{}/{}]",
+                                    current_command,
+                                    theme::success("enter"),
+                                    theme::hint("↑"),
+                                    theme::hint("↓"),
+                                    theme::error("Ctrl+C")
+                                )
+                                .as_bytes(),
+                            ) {
+                                eprintln!("Warning: failed to write to stderr: {}", e);
                             }
                         }
-                        (KeyCode::Down, _) => {
-                            if fixed_commands.len() > 1 {
-                                if current_index < fixed_commands.len() - 1 {
-                                    current_index += 1;
-                                } else {
-                                    current_index = 0;
-                                }
-                                current_command = fixed_commands
-                                    .get(current_index)
-                                    .expect("current_index is within bounds");
-                                if let Err(e) = err.write_all(
-                                    format!(
-                                        "{} [{}/{}/{}/{}]",
-                                        current_command,
-                                        "enter".green(),
-                                        "↑".cyan(),
-                                        "↓".cyan(),
-                                        "Ctrl+C".red()
-                                    )
-                                    .as_bytes(),
-                                ) {
-                                    eprintln!("Warning: failed to write to stderr: {}", e);
-                                }
+                        (KeyCode::Down, _) if fixed_commands.len() > 1 => {
+                            if current_index < fixed_commands.len() - 1 {
+                                current_index += 1;
+                            } else {
+                                current_index = 0;
+                            }
+                            current_command = fixed_commands
+                                .get(current_index)
+                                .expect("current_index is within bounds");
+                            if let Err(e) = err.write_all(
+                                format!(
+                                    "{} [{}/{}/{}/{}]",
+                                    current_command,
+                                    theme::success("enter"),
+                                    theme::hint("↑"),
+                                    theme::hint("↓"),
+                                    theme::error("Ctrl+C")
+                                )
+                                .as_bytes(),
+                            ) {
+                                eprintln!("Warning: failed to write to stderr: {}", e);
                             }
                         }
                         (KeyCode::Enter, _) => {
                             drop(_raw_mode_guard);
                             eprintln!();
-                            eprintln!("{}: {}", "Selected command: ".green(), &current_command);
+                            eprintln!(
+                                "{}: {}",
+                                theme::success("Selected command: "),
+                                &current_command
+                            );
                             return fixed_commands.remove(current_index);
                         }
                         (KeyCode::Char('c'), KeyModifiers::CONTROL) => {
                             drop(_raw_mode_guard);
                             eprintln!();
-                            eprintln!("{}: {}", "Exiting...".yellow(), "User interrupted".red());
+                            eprintln!(
+                                "{}: {}",
+                                theme::warning("Exiting..."),
+                                theme::error("User interrupted")
+                            );
                             std::process::exit(1);
                         }
                         _ => {}
@@ -285,7 +832,11 @@ fn choose_fixed_command(mut fixed_commands: Vec<String>) -> String {
                 }
             }
             Err(_) => {
-                eprintln!("{}: {}", "Error reading input".red(), "Exiting...".yellow());
+                eprintln!(
+                    "{}: {}",
+                    theme::error("Error reading input"),
+                    theme::warning("Exiting...")
+                );
                 drop(_raw_mode_guard);
                 std::process::exit(1);
             }
@@ -335,6 +886,13 @@ mod tests {
         assert_eq!(err.kind(), ErrorKind::InvalidInput);
     }
 
+    #[test]
+    fn test_get_command_output_uses_c_locale() {
+        let output =
+            get_command_output("sh -c 'echo $LC_ALL'".to_string()).expect("Command should run");
+        assert_eq!(output.stdout(), "C\n");
+    }
+
     #[test]
     fn test_get_command_output_nonexistent_command() {
         let result = get_command_output("nonexistent_command_12345".to_string());
@@ -342,4 +900,550 @@ mod tests {
         let err = result.err().expect("Expected error but got success");
         assert!(matches!(err.kind(), ErrorKind::NotFound));
     }
+
+    #[test]
+    fn test_emit_auto_prints_top_fix() {
+        let (mut out, mut err) = (vec![], vec![]);
+        emit_fixed_commands(
+            Mode::Auto,
+            OutputFormat::Shell,
+            "git psuh",
+            vec!["git push".to_string(), "git pull".to_string()],
+            None,
+            &mut out,
+            &mut err,
+        )
+        .expect("Writing should succeed");
+        assert_eq!(String::from_utf8_lossy(&out), "git push\n");
+        assert!(err.is_empty());
+    }
+
+    #[test]
+    fn test_emit_strips_trailing_whitespace() {
+        let (mut out, mut err) = (vec![], vec![]);
+        emit_fixed_commands(
+            Mode::Auto,
+            OutputFormat::Shell,
+            "git psuh",
+            vec!["git push  \t\n\n".to_string()],
+            None,
+            &mut out,
+            &mut err,
+        )
+        .expect("Writing should succeed");
+        assert_eq!(String::from_utf8_lossy(&out), "git push\n");
+    }
+
+    #[test]
+    fn test_requires_confirmation() {
+        assert!(requires_confirmation("git commit -m 'x' --no-verify"));
+        assert!(!requires_confirmation("git commit -m '--no-verify is bad'"));
+        assert!(!requires_confirmation("git commit -m x"));
+        assert!(requires_confirmation("git remote add origin \"<url>\""));
+        assert!(!requires_confirmation("echo '<>'"));
+        assert!(!requires_confirmation("echo '<B>'"));
+    }
+
+    #[test]
+    fn test_emit_suggest_prints_nothing_to_eval() {
+        let (mut out, mut err) = (vec![], vec![]);
+        emit_fixed_commands(
+            Mode::Suggest,
+            OutputFormat::Shell,
+            "git psuh",
+            vec!["git push".to_string(), "git pull".to_string()],
+            None,
+            &mut out,
+            &mut err,
+        )
+        .expect("Writing should succeed");
+        assert!(out.is_empty());
+        assert_eq!(String::from_utf8_lossy(&err), "git push\ngit pull\n");
+    }
+
+    #[test]
+    fn test_emit_without_fixes() {
+        for mode in [Mode::Auto, Mode::Confirm, Mode::Suggest] {
+            let (mut out, mut err) = (vec![], vec![]);
+            let emitted = emit_fixed_commands(
+                mode,
+                OutputFormat::Shell,
+                "cs /tmp",
+                vec![],
+                None,
+                &mut out,
+                &mut err,
+            )
+            .expect("Writing should succeed");
+            assert!(!emitted);
+            assert!(out.is_empty());
+            assert!(String::from_utf8_lossy(&err).contains("No fix found for: cs /tmp"));
+        }
+    }
+
+    #[test]
+    fn test_emit_argv_prints_json_array() {
+        let (mut out, mut err) = (vec![], vec![]);
+        emit_fixed_commands(
+            Mode::Auto,
+            OutputFormat::Argv,
+            "git comit -m \"fix it\"",
+            vec![r#"git commit -m "fix the \"bug\"" --author='A\B'"#.to_string()],
+            None,
+            &mut out,
+            &mut err,
+        )
+        .expect("Writing should succeed");
+        assert_eq!(
+            String::from_utf8_lossy(&out),
+            "[\"git\",\"commit\",\"-m\",\"fix the \\\"bug\\\"\",\"--author=A\\\\B\"]\n"
+        );
+        assert!(err.is_empty());
+    }
+
+    #[test]
+    fn test_emit_argv_skips_fixes_needing_a_shell() {
+        let (mut out, mut err) = (vec![], vec![]);
+        emit_fixed_commands(
+            Mode::Auto,
+            OutputFormat::Argv,
+            "git push",
+            vec![
+                "git remote add origin x && git push".to_string(),
+                "git push upstream".to_string(),
+            ],
+            None,
+            &mut out,
+            &mut err,
+        )
+        .expect("Writing should succeed");
+        assert_eq!(
+            String::from_utf8_lossy(&out),
+            "[\"git\",\"push\",\"upstream\"]\n"
+        );
+
+        let (mut out, mut err) = (vec![], vec![]);
+        let emitted = emit_fixed_commands(
+            Mode::Auto,
+            OutputFormat::Argv,
+            "ls",
+            vec!["ls | less".to_string()],
+            None,
+            &mut out,
+            &mut err,
+        )
+        .expect("Writing should succeed");
+        assert!(!emitted);
+        assert!(out.is_empty());
+        assert!(
+            String::from_utf8_lossy(&err).contains("No fix can be run without a shell for: ls")
+        );
+    }
+
+    #[test]
+    fn test_json_string_escapes() {
+        assert_eq!(json_string("a\"b\\c\nd\u{1}"), r#""a\"b\\c\nd\u0001""#);
+    }
+
+    /// Replays scripted key presses; `None` stands for a second passing without input.
+    struct ScriptedKeys(Vec<Option<KeyCode>>);
+
+    impl KeySource for ScriptedKeys {
+        fn next_key(&mut self, _timeout: Duration) -> io::Result<Option<KeyEvent>> {
+            Ok(self.0.remove(0).map(KeyEvent::from))
+        }
+    }
+
+    #[test]
+    fn test_confirm_with_countdown_accepts_on_timeout() {
+        let mut err = vec![];
+        let mut keys = ScriptedKeys(vec![None, None, None]);
+        let accepted =
+            confirm_with_countdown("git push", Duration::from_secs(3), &mut keys, &mut err)
+                .expect("Writing should succeed");
+        assert!(accepted);
+        assert!(keys.0.is_empty());
+        let err = String::from_utf8_lossy(&err);
+        assert!(err.contains("(auto in 3s)"));
+        assert!(err.contains("(auto in 1s)"));
+        assert!(err.contains("(auto in 0s)"));
+    }
+
+    #[test]
+    fn test_countdown_never_runs_risky_fix() {
+        let (mut out, mut err) = (vec![], vec![]);
+        let mut keys = ScriptedKeys(vec![None, None, None]);
+        let mut offered = vec![];
+        let prompt = Prompt {
+            mode: Mode::Confirm,
+            auto_confirm: Some(Duration::from_secs(3)),
+            keys: &mut keys,
+            choose: |fixed_commands| {
+                offered = fixed_commands;
+                None
+            },
+        };
+        let emitted = emit_fixed_commands_with(
+            prompt,
+            OutputFormat::Shell,
+            "git commit -m x",
+            vec!["git commit -m x --no-verify".to_string()],
+            &mut out,
+            &mut err,
+        )
+        .expect("Writing should succeed");
+        assert!(!emitted);
+        assert!(out.is_empty());
+        assert_eq!(offered, vec!["git commit -m x --no-verify"]);
+        assert_eq!(keys.0.len(), 3, "the countdown should not start");
+        assert!(String::from_utf8_lossy(&err).contains("confirm it first"));
+    }
+
+    #[test]
+    fn test_auto_mode_never_runs_placeholder() {
+        let (mut out, mut err) = (vec![], vec![]);
+        let mut offered = vec![];
+        let prompt = Prompt {
+            mode: Mode::Auto,
+            auto_confirm: None,
+            keys: &mut ScriptedKeys(vec![]),
+            choose: |fixed_commands| {
+                offered = fixed_commands;
+                None
+            },
+        };
+        let emitted = emit_fixed_commands_with(
+            prompt,
+            OutputFormat::Shell,
+            "git push",
+            vec!["git remote add origin \"<url>\"".to_string()],
+            &mut out,
+            &mut err,
+        )
+        .expect("Writing should succeed");
+        assert!(!emitted);
+        assert!(out.is_empty());
+        assert_eq!(offered.len(), 1);
+        assert!(String::from_utf8_lossy(&err).contains("placeholder"));
+    }
+
+    #[test]
+    fn test_confirm_with_countdown_keys() {
+        for (key, expected) in [
+            (KeyCode::Enter, true),
+            (KeyCode::Char('y'), true),
+            (KeyCode::Char('n'), false),
+            (KeyCode::Esc, false),
+        ] {
+            let mut keys = ScriptedKeys(vec![None, Some(KeyCode::Left), Some(key)]);
+            let accepted =
+                confirm_with_countdown("git push", Duration::from_secs(5), &mut keys, &mut vec![])
+                    .expect("Writing should succeed");
+            assert_eq!(accepted, expected, "{key:?}");
+            assert!(keys.0.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_collect_rule_paths_recursive_sorted() {
+        let rules_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let root = rules_dir.path();
+        fs::create_dir_all(root.join("git/nested")).expect("Failed to create dirs");
+        fs::create_dir_all(root.join("__pycache__")).expect("Failed to create dirs");
+        for name in [
+            "zz.py",
+            "git/push.py",
+            "git/nested/amend.py",
+            "aa.native",
+            "__pycache__/zz.cpython-312.pyc",
+        ] {
+            fs::write(root.join(name), "").expect("Failed to write rule");
+        }
+
+        let paths = collect_rule_paths(root).expect("Collecting rule paths should succeed");
+        assert_eq!(
+            paths,
+            vec![
+                root.join("aa.native"),
+                root.join("git/nested/amend.py"),
+                root.join("git/push.py"),
+                root.join("zz.py"),
+            ]
+        );
+    }
+
+    fn create_rules_dir(active: &[&str], additional: &[&str]) -> tempfile::TempDir {
+        let rules_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        for (folder, rules) in [("active", active), ("additional", additional)] {
+            let dir = rules_dir.path().join(folder);
+            fs::create_dir_all(&dir).expect("Failed to create rules dir");
+            for rule in rules {
+                fs::write(dir.join(rule), "").expect("Failed to write rule");
+            }
+        }
+        rules_dir
+    }
+
+    #[test]
+    fn test_select_rule_paths_applies_config() {
+        let rules_dir = create_rules_dir(
+            &["sudo.native", "to_cd.native"],
+            &["unsudo.native", "custom.py"],
+        );
+        let root = rules_dir.path();
+        let config = Config {
+            enabled_rules: vec!["custom".to_string()],
+            disabled_rules: vec!["sudo".to_string()],
+            ..Config::default()
+        };
+
+        let paths = select_rule_paths(&[root.to_path_buf()], &config)
+            .expect("Selecting rules should succeed");
+        assert_eq!(
+            paths,
+            vec![
+                root.join("active/to_cd.native"),
+                root.join("additional/custom.py"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_select_rule_paths_from_data_dir() {
+        let data_dir = create_rules_dir(&["packaged.py", "shared.py", "sudo.native"], &[]);
+        let config_dir = create_rules_dir(&["shared.py", "to_cd.native"], &["sudo.native"]);
+        let (data, user) = (data_dir.path(), config_dir.path());
+        let missing = data.join("missing");
+
+        let paths = select_rule_paths(
+            &[missing, data.to_path_buf(), user.to_path_buf()],
+            &Config::default(),
+        )
+        .expect("Selecting rules should succeed");
+        assert_eq!(
+            paths,
+            vec![
+                data.join("active/packaged.py"),
+                user.join("active/shared.py"),
+                user.join("active/to_cd.native"),
+            ]
+        );
+
+        let paths = select_rule_paths(&[data.to_path_buf()], &Config::default())
+            .expect("Selecting rules should succeed");
+        assert_eq!(paths.len(), 3);
+    }
+
+    #[test]
+    fn test_group_by_rules_dir() {
+        let rules_dirs = [PathBuf::from("/usr/share/r"), PathBuf::from("/home/u/r")];
+        let groups = group_by_rules_dir(
+            vec![
+                PathBuf::from("/usr/share/r/active/a.py"),
+                PathBuf::from("/home/u/r/active/b.py"),
+                PathBuf::from("/usr/share/r/active/c.py"),
+            ],
+            &rules_dirs,
+        );
+        assert_eq!(
+            groups,
+            vec![
+                vec![
+                    PathBuf::from("/usr/share/r/active/a.py"),
+                    PathBuf::from("/usr/share/r/active/c.py"),
+                ],
+                vec![PathBuf::from("/home/u/r/active/b.py")],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_collect_fixed_commands_priority_override() {
+        let rules_dir = create_rules_dir(&["to_cd.native", "sudo.native"], &[]);
+        let output = CommandOutput::new(String::new(), "permission denied".to_string());
+        let command = structs::Command::new("cs /root".to_string(), output);
+
+        let default = collect_fixed_commands(
+            &command,
+            &[rules_dir.path().to_path_buf()],
+            &Config::default(),
+            false,
+        )
+        .expect("Collecting fixes should succeed");
+        assert_eq!(default, vec!["sudo cs /root", "cd /root"]);
+
+        let config = Config {
+            priorities: [("to_cd".to_string(), 90)].into(),
+            ..Config::default()
+        };
+        let boosted =
+            collect_fixed_commands(&command, &[rules_dir.path().to_path_buf()], &config, false)
+                .expect("Collecting fixes should succeed");
+        assert_eq!(boosted, vec!["cd /root", "sudo cs /root"]);
+    }
+
+    #[test]
+    fn test_collect_fixed_commands_deduplicates_across_rule_types() {
+        let rules_dir = create_rules_dir(&["sudo.native"], &[]);
+        fs::write(
+            rules_dir.path().join("active/rules.toml"),
+            "[root]\nmatch_stderr = 'permission denied'\nreplace = 'sudo {command}'\npriority = 80\n",
+        )
+        .expect("Failed to write rule");
+        let output = CommandOutput::new(String::new(), "permission denied".to_string());
+        let command = structs::Command::new("cat /etc/shadow".to_string(), output);
+        let fixed = collect_fixed_commands(
+            &command,
+            &[rules_dir.path().to_path_buf()],
+            &Config::default(),
+            false,
+        )
+        .expect("Collecting fixes should succeed");
+        assert_eq!(fixed, vec!["sudo cat /etc/shadow"]);
+    }
+
+    #[test]
+    fn test_collect_fixed_commands_with_provided_output() {
+        let rules_dir = create_rules_dir(&["to_cd.native", "sudo.native"], &[]);
+
+        let output = CommandOutput::new(String::new(), String::new()).with_exit_code(127);
+        let command = structs::Command::new("cs /tmp".to_string(), output);
+        let fixed = collect_fixed_commands(
+            &command,
+            &[rules_dir.path().to_path_buf()],
+            &Config::default(),
+            false,
+        )
+        .expect("Collecting fixes should succeed");
+        assert_eq!(fixed, vec!["cd /tmp".to_string()]);
+    }
+
+    #[test]
+    fn test_collect_fixed_commands_fallback_only_without_real_fix() {
+        let rules_dir = create_rules_dir(&["to_cd.native"], &["help_hint.native"]);
+        let config = Config {
+            enabled_rules: vec!["help_hint".to_string()],
+            ..Config::default()
+        };
+        let not_found = || CommandOutput::new(String::new(), String::new()).with_exit_code(127);
+
+        let command = structs::Command::new("cs /tmp".to_string(), not_found());
+        let fixed =
+            collect_fixed_commands(&command, &[rules_dir.path().to_path_buf()], &config, false)
+                .expect("Collecting fixes should succeed");
+        assert_eq!(fixed, vec!["cd /tmp".to_string()]);
+
+        let command = structs::Command::new("frobnicate -x".to_string(), not_found());
+        let fixed =
+            collect_fixed_commands(&command, &[rules_dir.path().to_path_buf()], &config, false)
+                .expect("Collecting fixes should succeed");
+        assert_eq!(fixed, vec!["frobnicate --help".to_string()]);
+    }
+
+    #[test]
+    fn test_run_single_rule() {
+        let rules_dir = create_rules_dir(&[], &["custom.py"]);
+        fs::write(
+            rules_dir.path().join("additional/custom.py"),
+            "def match(command, stdout, stderr):\n    return 'oops' in stderr\n\
+             def fix(command, stdout, stderr):\n    return 'fixed ' + command\n",
+        )
+        .expect("Failed to write rule");
+        let command = structs::Command::new(
+            "make".to_string(),
+            CommandOutput::new(String::new(), "oops".to_string()),
+        );
+        let rules_dirs = [rules_dir.path().to_path_buf()];
+
+        let fixed = run_single_rule(&command, "custom", &rules_dirs, false)
+            .expect("Python rule should run");
+        assert_eq!(fixed, vec!["fixed make".to_string()]);
+        let fixed =
+            run_single_rule(&command, "sudo", &rules_dirs, false).expect("Native rule should run");
+        assert!(fixed.is_empty());
+        assert!(run_single_rule(&command, "missing", &rules_dirs, false).is_err());
+    }
+
+    #[test]
+    fn test_find_first_fixable_uses_second_command() {
+        let rules_dir = create_rules_dir(&["to_cd.native"], &[]);
+
+        let history = vec!["ls -la", "cs /tmp", "cs /var"];
+        let fixed = find_first_fixable(history, |command| {
+            let output = CommandOutput::new(String::new(), String::new());
+            let command = structs::Command::new(command.to_string(), output);
+            collect_fixed_commands(
+                &command,
+                &[rules_dir.path().to_path_buf()],
+                &Config::default(),
+                false,
+            )
+        })
+        .expect("Collecting fixes should succeed");
+        assert_eq!(fixed, Some(("cs /tmp", vec!["cd /tmp".to_string()])));
+    }
+
+    #[test]
+    fn test_find_first_fixable_without_fixes() {
+        let fixed = find_first_fixable(vec!["ls", "pwd"], |_| Ok::<_, io::Error>(vec![]))
+            .expect("Collecting fixes should succeed");
+        assert!(fixed.is_none());
+    }
+
+    #[test]
+    fn test_collect_current_fixes_skips_output_rules() {
+        let rules_dir =
+            create_rules_dir(&["sudo.native", "to_cd.native", "quote_glob.native"], &[]);
+        let command = structs::Command::new(
+            "cs /tmp".to_string(),
+            CommandOutput::new("permission denied".to_string(), String::new()),
+        );
+        let fixes = collect_current_fixes(
+            &command,
+            &[rules_dir.path().to_path_buf()],
+            &Config::default(),
+        )
+        .expect("Rules should be collected");
+        assert_eq!(fixes, vec!["cd /tmp"]);
+    }
+
+    #[test]
+    fn test_rank_candidates_prefers_higher_confidence() {
+        let ranked = rank_candidates(vec![
+            ("first".to_string(), 50),
+            ("best".to_string(), 90),
+            ("worst".to_string(), 10),
+        ]);
+        assert_eq!(ranked, vec!["best", "first", "worst"]);
+    }
+
+    #[test]
+    fn test_rank_candidates_puts_fallback_last() {
+        let ranked = rank_candidates(vec![
+            ("git --help".to_string(), NativeRule::HelpHint.confidence()),
+            ("git status".to_string(), 10),
+            ("git stash".to_string(), structs::DEFAULT_CONFIDENCE),
+        ]);
+        assert_eq!(ranked, vec!["git stash", "git status", "git --help"]);
+    }
+
+    #[test]
+    fn test_rank_candidates_removes_duplicates() {
+        let ranked = rank_candidates(vec![
+            ("sudo foo".to_string(), 40),
+            ("foo --force".to_string(), 50),
+            ("sudo foo".to_string(), 70),
+        ]);
+        assert_eq!(ranked, vec!["sudo foo", "foo --force"]);
+    }
+
+    #[test]
+    fn test_rank_candidates_keeps_order_for_equal_confidence() {
+        let ranked = rank_candidates(vec![
+            ("a".to_string(), structs::DEFAULT_CONFIDENCE),
+            ("b".to_string(), structs::DEFAULT_CONFIDENCE),
+            ("c".to_string(), structs::DEFAULT_CONFIDENCE),
+        ]);
+        assert_eq!(ranked, vec!["a", "b", "c"]);
+    }
 }
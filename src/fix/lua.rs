@@ -0,0 +1,248 @@
+//! Lua rules, run through the `lua` interpreter found on `PATH` rather than an embedded one, so
+//! the feature adds no build dependency. Every rule is a separate `lua` process per fixed
+//! command. Each rule file either returns a table or defines globals with `match(command,
+//! stdout, stderr)`, `fix(command, stdout, stderr)` and an optional `priority`, like Python
+//! rules.
+use super::python::{get_common_parent, partition_secure_rules, security_summary};
+use super::rule_name;
+use super::structs::{Command, rule_confidence};
+use crate::config::{self, Value};
+use crate::error::{AppError, AppResult};
+use crate::misc::log::{debug, diagnostic};
+use crate::misc::theme;
+use std::collections::HashMap;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::process::{self, Stdio};
+
+const INTERPRETER: &str = "lua";
+
+/// Loads the rule named by `THESHIT_RULE`, reads the command, stdout and stderr from stdin
+/// (NUL-separated) and prints the priority followed by each fix, all NUL-separated. Prints
+/// nothing when the rule doesn't match.
+const DRIVER: &str = r#"
+package.path = os.getenv("THESHIT_RULES_DIR") .. "/?.lua;" .. package.path
+local loaded = assert(loadfile(os.getenv("THESHIT_RULE")))()
+local rule = type(loaded) == "table" and loaded or _G
+
+local input, fields, start = io.read("*a"), {}, 1
+while true do
+  local stop = string.find(input, "\0", start, true)
+  if not stop then
+    fields[#fields + 1] = string.sub(input, start)
+    break
+  end
+  fields[#fields + 1] = string.sub(input, start, stop - 1)
+  start = stop + 1
+end
+local command, stdout, stderr = fields[1], fields[2] or "", fields[3] or ""
+
+assert(type(rule.match) == "function" and type(rule.fix) == "function",
+  "rule is missing required functions (match, fix)")
+if not rule.match(command, stdout, stderr) then
+  return
+end
+local fixes = rule.fix(command, stdout, stderr)
+if type(fixes) ~= "table" then
+  fixes = { fixes }
+end
+local priority = rule.priority
+if type(priority) == "function" then
+  priority = priority()
+end
+io.write(priority == nil and "" or tostring(priority))
+for _, fix in ipairs(fixes) do
+  io.write("\0", tostring(fix))
+end
+"#;
+
+pub fn process_lua_rules(
+    command: &Command,
+    mut rule_paths: Vec<PathBuf>,
+    priorities: &HashMap<String, u8>,
+    verbose: bool,
+) -> AppResult<Vec<(String, u8)>> {
+    if rule_paths.is_empty() {
+        return Ok(vec![]);
+    }
+    rule_paths.sort();
+    let module_path = get_common_parent(&rule_paths)
+        .ok_or_else(|| AppError::Config("No common parent found for rule paths".to_string()))?;
+    let (rule_paths, skipped) = partition_secure_rules(rule_paths, verbose);
+    let mut fixed_commands = vec![];
+    for rule_path in rule_paths {
+        let result = run_lua_rule(command, &module_path, &rule_path);
+        debug!(
+            "lua rule {}: {}",
+            rule_path.display(),
+            if matches!(result, Ok(Some(_))) {
+                "matched"
+            } else {
+                "no match"
+            }
+        );
+        match result {
+            Ok(Some((priority, fixes))) => {
+                let confidence = rule_confidence(&rule_name(&rule_path), priority, priorities);
+                fixed_commands.extend(fixes.into_iter().map(|fix| (fix, confidence)));
+            }
+            Ok(None) => {}
+            Err(AppError::Io(e)) if e.kind() == ErrorKind::NotFound => {
+                return Err(AppError::Other(format!(
+                    "'{INTERPRETER}' was not found on PATH, Lua rules can't run"
+                )));
+            }
+            Err(e) => {
+                diagnostic!(
+                    "{}{}{}",
+                    theme::warning("Failed to run Lua rule '"),
+                    rule_path.display(),
+                    theme::warning("': "),
+                );
+                diagnostic!("{e}");
+            }
+        }
+    }
+    if let Some(summary) = security_summary(&skipped, verbose) {
+        diagnostic!("{summary}");
+    }
+    Ok(fixed_commands)
+}
+
+/// Returns the rule's priority and fixes, or `None` when it didn't match.
+fn run_lua_rule(
+    command: &Command,
+    module_path: &Path,
+    rule_path: &Path,
+) -> AppResult<Option<(Option<u8>, Vec<String>)>> {
+    let mut child = process::Command::new(INTERPRETER)
+        .args(["-e", DRIVER])
+        .env("THESHIT_RULES_DIR", module_path)
+        .env("THESHIT_RULE", rule_path)
+        .current_dir(command.cwd())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let input = [
+        command.command(),
+        command.output().stdout(),
+        command.output().stderr(),
+    ]
+    .join("\0");
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(input.as_bytes())?;
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(AppError::Other(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    parse_driver_output(&String::from_utf8_lossy(&output.stdout))
+}
+
+fn parse_driver_output(raw: &str) -> AppResult<Option<(Option<u8>, Vec<String>)>> {
+    if raw.is_empty() {
+        return Ok(None);
+    }
+    let mut fields = raw.split('\0');
+    let priority = match fields.next().unwrap_or_default() {
+        "" => None,
+        priority => {
+            let value = priority
+                .parse()
+                .map(Value::Integer)
+                .unwrap_or_else(|_| Value::String(priority.to_string()));
+            Some(config::expect_priority("priority", value)?)
+        }
+    };
+    Ok(Some((priority, fields.map(str::to_string).collect())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fix::structs::CommandOutput;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    fn create_rule_file(dir: &Path, name: &str, content: &str) -> PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, content).expect("Failed to write rule");
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600))
+            .expect("Failed to set permissions");
+        path
+    }
+
+    #[test]
+    fn parse_output() {
+        assert_eq!(parse_driver_output("").expect("Should parse"), None);
+        assert_eq!(
+            parse_driver_output("80\0git push\0git pull").expect("Should parse"),
+            Some((
+                Some(80),
+                vec!["git push".to_string(), "git pull".to_string()]
+            ))
+        );
+        assert_eq!(
+            parse_driver_output("\0ls").expect("Should parse"),
+            Some((None, vec!["ls".to_string()]))
+        );
+        assert!(parse_driver_output("high\0ls").is_err());
+        assert!(parse_driver_output("101\0ls").is_err());
+        assert!(parse_driver_output("-1\0ls").is_err());
+        assert_eq!(
+            parse_driver_output("100\0ls").expect("Should parse"),
+            Some((Some(100), vec!["ls".to_string()]))
+        );
+    }
+
+    #[test]
+    fn process_lua_fixture() {
+        // Lua rules need an interpreter on PATH; without one there is nothing to run them with.
+        if process::Command::new(INTERPRETER)
+            .arg("-v")
+            .output()
+            .is_err()
+        {
+            eprintln!("skipping process_lua_fixture: no `{INTERPRETER}` on PATH");
+            return;
+        }
+        let temp = tempfile::tempdir().expect("Failed to create temp dir");
+        let rule = create_rule_file(
+            temp.path(),
+            "git_push.lua",
+            r#"
+local M = { priority = 70 }
+function M.match(command, stdout, stderr)
+  return command == "git psuh" and stderr:find("not a git command") ~= nil
+end
+function M.fix(command, stdout, stderr)
+  return { "git push", "git pull" }
+end
+return M
+"#,
+        );
+        let no_match = create_rule_file(
+            temp.path(),
+            "never.lua",
+            "function match() return false end\nfunction fix() return 'never' end\n",
+        );
+        let command = Command::new(
+            "git psuh".to_string(),
+            CommandOutput::new(
+                String::new(),
+                "git: 'psuh' is not a git command.".to_string(),
+            ),
+        );
+        let fixes = process_lua_rules(&command, vec![rule, no_match], &HashMap::new(), false)
+            .expect("Processing should succeed");
+        assert_eq!(
+            fixes,
+            vec![("git push".to_string(), 70), ("git pull".to_string(), 70)]
+        );
+    }
+}
@@ -1,32 +1,73 @@
-use super::structs::Command;
+use super::rule_name;
+use super::structs::{Command, rule_confidence};
 use crate::error::{AppError, AppResult};
-use crossterm::style::Stylize;
-use pyo3::Python;
-use pyo3::types::{PyAnyMethods, PyList, PyListMethods};
-use std::fs;
+use crate::misc;
+use crate::misc::log::{debug, diagnostic};
+use crate::misc::profile::{self, Stage};
+use crate::misc::theme;
+use pyo3::types::{PyAny, PyAnyMethods, PyDict, PyDictMethods, PyList, PyListMethods, PyModule};
+use pyo3::{Bound, PyResult, Python};
+use std::collections::HashMap;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::num::NonZeroUsize;
+use std::os::fd::{AsRawFd, FromRawFd, RawFd};
 use std::os::unix::fs::{MetadataExt, PermissionsExt};
 use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
 
-fn check_security(path: &Path) -> AppResult<()> {
+/// Directories listed in `THESHIT_TRUST_RULES_DIR` (separated like `PATH`). Relative entries are
+/// ignored, since they would depend on the directory theshit happens to run in.
+fn get_trusted_rules_dirs() -> Vec<PathBuf> {
+    env::var_os("THESHIT_TRUST_RULES_DIR")
+        .map(|dirs| {
+            env::split_paths(&dirs)
+                .filter(|dir| dir.is_absolute())
+                .map(|dir| fs::canonicalize(&dir).unwrap_or(dir))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn is_trusted(path: &Path, trusted_dirs: &[PathBuf]) -> bool {
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    trusted_dirs.iter().any(|dir| path.starts_with(dir))
+}
+
+/// Rejects rules owned by another user or writable by non-owners. For rules inside an explicitly
+/// trusted directory a foreign owner only produces a warning; writable rules are always rejected.
+fn check_security(path: &Path, current_uid: u32, trusted_dirs: &[PathBuf]) -> AppResult<()> {
     let metadata = fs::metadata(path).map_err(AppError::Io)?;
 
     let file_uid = metadata.uid();
-    let current_uid = unsafe { libc::geteuid() };
 
     if current_uid != file_uid {
-        return Err(AppError::Security(format!(
-            "{} Running with UID {}, but file '{}' is owned by UID {}.",
-            "SECURITY ERROR:".red().bold(),
+        let message = format!(
+            "Running with UID {}, but file '{}' is owned by UID {}.",
             current_uid,
             path.display(),
             file_uid
-        )));
+        );
+        if !is_trusted(path, trusted_dirs) {
+            return Err(AppError::Security(format!(
+                "{} {}",
+                theme::critical("SECURITY ERROR:"),
+                message
+            )));
+        }
+        diagnostic!(
+            "{} {} Loading it anyway because its directory is in THESHIT_TRUST_RULES_DIR.",
+            theme::warning("SECURITY WARNING:"),
+            message
+        );
     }
 
     if metadata.permissions().mode() & 0o022 != 0 {
         return Err(AppError::Security(format!(
-            "{} Python rule '{}' is writable by non-owners.",
-            "SECURITY ERROR:".red().bold(),
+            "{} Rule '{}' is writable by non-owners.",
+            theme::critical("SECURITY ERROR:"),
             path.display()
         )));
     }
@@ -34,146 +75,596 @@ fn check_security(path: &Path) -> AppResult<()> {
     Ok(())
 }
 
-pub fn process_python_rules(command: &Command, rule_paths: Vec<PathBuf>) -> AppResult<Vec<String>> {
+fn get_priority(module: &Bound<PyModule>) -> PyResult<Option<u8>> {
+    if !module.hasattr("priority")? {
+        return Ok(None);
+    }
+    let priority = module.getattr("priority")?;
+    let value = if priority.is_callable() {
+        priority.call0()?
+    } else {
+        priority
+    };
+    value.extract::<u8>().map(Some)
+}
+
+/// Splits rules into those safe to import and those skipped by [`check_security`],
+/// printing the individual reasons only when `verbose` is set.
+pub(super) fn partition_secure_rules(
+    rule_paths: Vec<PathBuf>,
+    verbose: bool,
+) -> (Vec<PathBuf>, Vec<PathBuf>) {
+    let current_uid = unsafe { libc::geteuid() };
+    let trusted_dirs = get_trusted_rules_dirs();
+    let mut secure = vec![];
+    let mut skipped = vec![];
+    for rule_path in rule_paths {
+        match check_security(&rule_path, current_uid, &trusted_dirs) {
+            Ok(()) => secure.push(rule_path),
+            Err(e) => {
+                if verbose {
+                    diagnostic!("{}", e);
+                }
+                skipped.push(rule_path);
+            }
+        }
+    }
+    (secure, skipped)
+}
+
+pub(super) fn security_summary(skipped: &[PathBuf], verbose: bool) -> Option<String> {
+    if skipped.is_empty() {
+        return None;
+    }
+    let names = skipped
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    Some(format!(
+        "{} {}{}",
+        theme::critical(format!(
+            "{} rule{} skipped due to permissions:",
+            skipped.len(),
+            if skipped.len() == 1 { "" } else { "s" }
+        )),
+        names,
+        if verbose {
+            ""
+        } else {
+            " (run with --verbose for details)"
+        }
+    ))
+}
+
+/// Builds the `ctx` object passed to single-parameter `match(ctx)`/`fix(ctx)` rules.
+fn build_context<'py>(py: Python<'py>, command: &Command) -> PyResult<Bound<'py, PyAny>> {
+    let kwargs = PyDict::new(py);
+    kwargs.set_item("command", command.command())?;
+    kwargs.set_item("stdout", command.output().stdout())?;
+    kwargs.set_item("stderr", command.output().stderr())?;
+    kwargs.set_item("output", command.output().combined())?;
+    kwargs.set_item("exit_code", command.output().exit_code())?;
+    kwargs.set_item("cwd", command.cwd().to_string_lossy())?;
+    kwargs.set_item("shell", env::var("SH_SHELL").ok())?;
+    py.import("types")?
+        .getattr("SimpleNamespace")?
+        .call((), Some(&kwargs))
+}
+
+/// Rules opt into the context API by declaring exactly one parameter.
+fn takes_context(py: Python, func: &Bound<PyAny>) -> bool {
+    py.import("inspect")
+        .and_then(|inspect| inspect.call_method1("signature", (func,)))
+        .and_then(|signature| signature.getattr("parameters"))
+        .and_then(|parameters| parameters.len())
+        .is_ok_and(|count| count == 1)
+}
+
+fn call_rule_function<'py>(
+    py: Python<'py>,
+    func: &Bound<'py, PyAny>,
+    command: &Command,
+) -> PyResult<Bound<'py, PyAny>> {
+    if takes_context(py, func) {
+        func.call1((build_context(py, command)?,))
+    } else {
+        func.call1((
+            command.command(),
+            command.output().stdout(),
+            command.output().stderr(),
+        ))
+    }
+}
+
+/// A rule's `fix` may return a single command or a list of alternatives.
+fn extract_fixes(result: Bound<PyAny>) -> PyResult<Vec<String>> {
+    match result.downcast::<PyList>() {
+        Ok(list) => list.extract(),
+        Err(_) => result.extract::<String>().map(|fix| vec![fix]),
+    }
+}
+
+pub fn process_python_rules(
+    command: &Command,
+    mut rule_paths: Vec<PathBuf>,
+    priorities: &HashMap<String, u8>,
+    verbose: bool,
+) -> AppResult<Vec<(String, u8)>> {
     if rule_paths.is_empty() {
         return Ok(vec![]);
     }
+    // Candidates with equal priority keep this order, so it must not depend on the caller.
+    rule_paths.sort();
     let module_path = get_common_parent(&rule_paths)
         .ok_or_else(|| AppError::Config("No common parent found for rule paths".to_string()))?;
-    let mut fixed_commands: Vec<String> = vec![];
+    let (rule_paths, skipped) = partition_secure_rules(rule_paths, verbose);
+    profile::timed(Stage::PythonInit, pyo3::prepare_freethreaded_python);
+    let fixed_commands = profile::timed(Stage::PythonRules, || {
+        match worker_count(rule_paths.len()) {
+            0 | 1 => run_python_rules(command, &module_path, rule_paths, priorities),
+            _ if !is_single_threaded() => {
+                debug!("python rules: other threads are running, not forking workers");
+                run_python_rules(command, &module_path, rule_paths, priorities)
+            }
+            workers => run_python_rules_parallel(
+                command,
+                &module_path,
+                rule_paths,
+                priorities,
+                workers,
+                WORKER_TIMEOUT,
+            ),
+        }
+    });
+    if let Some(summary) = security_summary(&skipped, verbose) {
+        diagnostic!("{summary}");
+    }
+    fixed_commands
+}
+
+fn run_python_rules(
+    command: &Command,
+    module_path: &Path,
+    rule_paths: Vec<PathBuf>,
+    priorities: &HashMap<String, u8>,
+) -> AppResult<Vec<(String, u8)>> {
+    let fixes = evaluate_rules(command, module_path, &rule_paths, priorities)?;
+    Ok(fixes.into_iter().flatten().collect())
+}
+
+/// Below this many rules per worker, forking costs more than it saves.
+const MIN_RULES_PER_WORKER: usize = 4;
+const MAX_WORKERS: usize = 8;
+/// How long the workers get for their rules before they are killed and their rules count as
+/// not matching.
+const WORKER_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn worker_count(rule_count: usize) -> usize {
+    thread::available_parallelism()
+        .map_or(1, NonZeroUsize::get)
+        .min(MAX_WORKERS)
+        .min(rule_count / MIN_RULES_PER_WORKER)
+}
+
+/// Forking is only safe without other threads: one holding a lock (the allocator's, say) at
+/// that moment would leave it locked forever in the child. Only Linux can tell cheaply, so
+/// elsewhere this relies on theshit not starting threads.
+fn is_single_threaded() -> bool {
+    if cfg!(target_os = "linux") {
+        fs::read_dir("/proc/self/task").is_ok_and(|tasks| tasks.count() == 1)
+    } else {
+        true
+    }
+}
+
+/// Spreads the rules over `workers` forked processes so matching isn't serialized by the GIL.
+/// Each worker sends its fixes back over a pipe and they are reassembled in rule order, so the
+/// result is the same as [`run_python_rules`]. A worker that can't be started or dies is
+/// redone in this process; one still running after `timeout` is killed and its rules are
+/// treated as not matching.
+fn run_python_rules_parallel(
+    command: &Command,
+    module_path: &Path,
+    rule_paths: Vec<PathBuf>,
+    priorities: &HashMap<String, u8>,
+    workers: usize,
+    timeout: Duration,
+) -> AppResult<Vec<(String, u8)>> {
+    let chunks = (0..workers)
+        .map(|worker| {
+            (worker..rule_paths.len())
+                .step_by(workers)
+                .map(|index| rule_paths[index].clone())
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
     pyo3::prepare_freethreaded_python();
-    Python::with_gil(|py| -> Result<(), AppError> {
-        {
-            let raw_sys_path = py
-                .import("sys")
-                .map_err(|e| AppError::Python(format!("Failed to import sys: {}", e)))?;
-            let sys_path = raw_sys_path
-                .getattr("path")
-                .map_err(|e| AppError::Python(format!("Failed to get sys.path: {}", e)))?;
-            let sys_path = sys_path
-                .downcast::<PyList>()
-                .map_err(|e| AppError::Python(format!("sys.path is not a list: {}", e)))?;
-            sys_path
-                .insert(0, module_path.to_string_lossy())
-                .map_err(|e| AppError::Python(format!("Failed to insert path: {}", e)))?;
+    let spawned = Python::with_gil(|py| {
+        let mut spawned: Vec<io::Result<Worker>> = vec![];
+        for chunk in &chunks {
+            let inherited = spawned
+                .iter()
+                .flatten()
+                .map(|worker| worker.results.as_raw_fd())
+                .collect::<Vec<_>>();
+            spawned.push(spawn_worker(
+                py,
+                command,
+                module_path,
+                chunk,
+                priorities,
+                &inherited,
+            ));
         }
+        spawned
+    });
+    let deadline = Instant::now() + timeout;
 
-        for rule_path in rule_paths {
-            if let Err(e) = check_security(&rule_path) {
-                eprintln!("{}", e);
-                continue;
+    let mut fixes = vec![vec![]; rule_paths.len()];
+    for (worker, (chunk, spawned)) in chunks.iter().zip(spawned).enumerate() {
+        let collected = match spawned {
+            Ok(spawned) => collect_worker(spawned, chunk.len(), deadline),
+            Err(_) => Collected::Failed,
+        };
+        let chunk_fixes = match collected {
+            Collected::Fixes(chunk_fixes) => chunk_fixes,
+            Collected::TimedOut => {
+                diagnostic!(
+                    "{} {}",
+                    theme::warning(format!(
+                        "Python rules took longer than {timeout:?} and were skipped:"
+                    )),
+                    chunk
+                        .iter()
+                        .map(|path| path.display().to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+                vec![vec![]; chunk.len()]
             }
+            Collected::Failed => evaluate_rules(command, module_path, chunk, priorities)?,
+        };
+        for (offset, rule_fixes) in chunk_fixes.into_iter().enumerate() {
+            fixes[worker + offset * workers] = rule_fixes;
+        }
+    }
+    Ok(fixes.into_iter().flatten().collect())
+}
 
-            let module_name = match get_module_name(&module_path, &rule_path) {
-                Some(module_name) => module_name,
-                None => continue,
-            };
-            let module = match py.import(&module_name) {
-                Ok(module) => module,
-                Err(e) => {
-                    eprintln!(
-                        "{}{}{}",
-                        "Failed to import rule module '".yellow(),
-                        rule_path.display(),
-                        "': ".yellow(),
-                    );
-                    eprintln!("{e}");
-                    continue;
-                }
-            };
-            let match_func = match module.getattr("match") {
-                Ok(func) => func,
-                Err(e) => {
-                    eprintln!(
-                        "{}{}{}",
-                        "Failed to get 'match' function from rule '".yellow(),
-                        rule_path.display(),
-                        "': ".yellow(),
-                    );
-                    eprintln!("{e}");
-                    continue;
-                }
-            };
-            let fix_func = match module.getattr("fix") {
-                Ok(func) => func,
+struct Worker {
+    pid: libc::pid_t,
+    results: File,
+}
+
+enum Collected {
+    Fixes(Vec<Vec<(String, u8)>>),
+    TimedOut,
+    Failed,
+}
+
+/// Forks a process that evaluates `rule_paths` and writes the encoded fixes to a pipe. Takes
+/// the GIL token because the fork goes through the same hooks as Python's own `os.fork()`.
+/// `inherited` are the pipes of the workers started before, which the child closes.
+fn spawn_worker(
+    _py: Python,
+    command: &Command,
+    module_path: &Path,
+    rule_paths: &[PathBuf],
+    priorities: &HashMap<String, u8>,
+    inherited: &[RawFd],
+) -> io::Result<Worker> {
+    let mut fds = [0; 2];
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let [read_fd, write_fd] = fds;
+    // Close-on-exec, so programs started by rules don't hold the pipe open. `pipe2` would do
+    // this in one step but doesn't exist on macOS; with no other threads nothing can fork
+    // in between.
+    for fd in fds {
+        unsafe { libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) };
+    }
+    unsafe { pyo3::ffi::PyOS_BeforeFork() };
+    let pid = unsafe { libc::fork() };
+    let fork_error = io::Error::last_os_error();
+    if pid == 0 {
+        unsafe {
+            pyo3::ffi::PyOS_AfterFork_Child();
+            libc::close(read_fd);
+            for &fd in inherited {
+                libc::close(fd);
+            }
+        }
+        let mut pipe = unsafe { File::from_raw_fd(write_fd) };
+        let status = match evaluate_rules(command, module_path, rule_paths, priorities) {
+            Ok(fixes) if pipe.write_all(&encode_fixes(&fixes)).is_ok() => 0,
+            _ => 1,
+        };
+        // Skip destructors and atexit handlers, which belong to the parent.
+        unsafe { libc::_exit(status) };
+    }
+    unsafe {
+        pyo3::ffi::PyOS_AfterFork_Parent();
+        libc::close(write_fd);
+    }
+    if pid < 0 {
+        unsafe { libc::close(read_fd) };
+        return Err(fork_error);
+    }
+    Ok(Worker {
+        pid,
+        results: unsafe { File::from_raw_fd(read_fd) },
+    })
+}
+
+fn collect_worker(mut worker: Worker, rule_count: usize, deadline: Instant) -> Collected {
+    let read = misc::pipe::read_to_end_by(&mut [&mut worker.results], deadline);
+    let status = match read {
+        Ok(Some(_)) => misc::pipe::wait_by(deadline, || {
+            let mut status = 0;
+            match unsafe { libc::waitpid(worker.pid, &mut status, libc::WNOHANG) } {
+                0 => Ok(None),
+                pid if pid == worker.pid => Ok(Some(status)),
+                _ => Err(io::Error::last_os_error()),
+            }
+        }),
+        _ => Ok(None),
+    };
+    match (read, status) {
+        (Ok(Some(raw)), Ok(Some(status)))
+            if libc::WIFEXITED(status) && libc::WEXITSTATUS(status) == 0 =>
+        {
+            match decode_fixes(&raw[0]).filter(|fixes| fixes.len() == rule_count) {
+                Some(fixes) => Collected::Fixes(fixes),
+                None => Collected::Failed,
+            }
+        }
+        (Ok(Some(_)), Ok(Some(_))) => Collected::Failed,
+        (read, status) => {
+            unsafe {
+                libc::kill(worker.pid, libc::SIGKILL);
+                libc::waitpid(worker.pid, std::ptr::null_mut(), 0);
+            }
+            if read.is_err() || status.is_err() {
+                Collected::Failed
+            } else {
+                Collected::TimedOut
+            }
+        }
+    }
+}
+
+/// Encodes per-rule fixes as a fix count per rule, then each fix as its confidence, byte
+/// length and text.
+fn encode_fixes(fixes: &[Vec<(String, u8)>]) -> Vec<u8> {
+    let mut raw = vec![];
+    for rule_fixes in fixes {
+        raw.extend((rule_fixes.len() as u32).to_le_bytes());
+        for (fix, confidence) in rule_fixes {
+            raw.push(*confidence);
+            raw.extend((fix.len() as u32).to_le_bytes());
+            raw.extend(fix.as_bytes());
+        }
+    }
+    raw
+}
+
+fn decode_fixes(mut raw: &[u8]) -> Option<Vec<Vec<(String, u8)>>> {
+    fn take<'a>(raw: &mut &'a [u8], len: usize) -> Option<&'a [u8]> {
+        let (head, tail) = raw.split_at_checked(len)?;
+        *raw = tail;
+        Some(head)
+    }
+    fn take_u32(raw: &mut &[u8]) -> Option<usize> {
+        Some(u32::from_le_bytes(take(raw, 4)?.try_into().ok()?) as usize)
+    }
+
+    let mut fixes = vec![];
+    while !raw.is_empty() {
+        let count = take_u32(&mut raw)?;
+        let mut rule_fixes = vec![];
+        for _ in 0..count {
+            let confidence = take(&mut raw, 1)?[0];
+            let len = take_u32(&mut raw)?;
+            let fix = String::from_utf8(take(&mut raw, len)?.to_vec()).ok()?;
+            rule_fixes.push((fix, confidence));
+        }
+        fixes.push(rule_fixes);
+    }
+    Some(fixes)
+}
+
+/// Runs each rule in order and returns its fixes, which are empty when it didn't match or
+/// failed.
+fn evaluate_rules(
+    command: &Command,
+    module_path: &Path,
+    rule_paths: &[PathBuf],
+    priorities: &HashMap<String, u8>,
+) -> AppResult<Vec<Vec<(String, u8)>>> {
+    if rule_paths.is_empty() {
+        return Ok(vec![]);
+    }
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| {
+        add_to_sys_path(py, module_path)?;
+        set_pycache_prefix(py);
+        Ok(rule_paths
+            .iter()
+            .map(|rule_path| {
+                let fixes = evaluate_rule(py, command, module_path, rule_path, priorities);
+                debug!(
+                    "python rule {}: {}",
+                    rule_path.display(),
+                    if fixes.is_empty() {
+                        "no match"
+                    } else {
+                        "matched"
+                    }
+                );
+                fixes
+            })
+            .collect())
+    })
+}
+
+/// Keeps compiled bytecode in the cache dir instead of `__pycache__` next to the rules.
+fn set_pycache_prefix(py: Python) {
+    if let Ok(cache_dir) = misc::get_cache_dir()
+        && let Ok(sys) = py.import("sys")
+    {
+        let _ = sys.setattr(
+            "pycache_prefix",
+            cache_dir.join("pycache").to_string_lossy(),
+        );
+    }
+}
+
+fn add_to_sys_path(py: Python, module_path: &Path) -> AppResult<()> {
+    let raw_sys_path = py
+        .import("sys")
+        .map_err(|e| AppError::Python(format!("Failed to import sys: {}", e)))?;
+    let sys_path = raw_sys_path
+        .getattr("path")
+        .map_err(|e| AppError::Python(format!("Failed to get sys.path: {}", e)))?;
+    let sys_path = sys_path
+        .downcast::<PyList>()
+        .map_err(|e| AppError::Python(format!("sys.path is not a list: {}", e)))?;
+    let module_path = module_path.to_string_lossy();
+    // The interpreter outlives a single call, so only add the path the first time.
+    let present = sys_path
+        .contains(module_path.as_ref())
+        .map_err(|e| AppError::Python(format!("Failed to inspect sys.path: {}", e)))?;
+    if !present {
+        sys_path
+            .insert(0, module_path.as_ref())
+            .map_err(|e| AppError::Python(format!("Failed to insert path: {}", e)))?;
+    }
+    Ok(())
+}
+
+fn evaluate_rule(
+    py: Python,
+    command: &Command,
+    module_path: &Path,
+    rule_path: &Path,
+    priorities: &HashMap<String, u8>,
+) -> Vec<(String, u8)> {
+    let module_name = match get_module_name(module_path, rule_path) {
+        Some(module_name) => module_name,
+        None => return vec![],
+    };
+    let module = match py.import(&module_name) {
+        Ok(module) => module,
+        Err(e) => {
+            diagnostic!(
+                "{}{}{}",
+                theme::warning("Failed to import rule module '"),
+                rule_path.display(),
+                theme::warning("': "),
+            );
+            diagnostic!("{e}");
+            return vec![];
+        }
+    };
+    let match_func = match module.getattr("match") {
+        Ok(func) => func,
+        Err(e) => {
+            diagnostic!(
+                "{}{}{}",
+                theme::warning("Failed to get 'match' function from rule '"),
+                rule_path.display(),
+                theme::warning("': "),
+            );
+            diagnostic!("{e}");
+            return vec![];
+        }
+    };
+    let fix_func = match module.getattr("fix") {
+        Ok(func) => func,
+        Err(e) => {
+            diagnostic!(
+                "{}{}{}",
+                theme::warning("Failed to get 'fix' function from rule '"),
+                rule_path.display(),
+                theme::warning("': "),
+            );
+            diagnostic!("{e}");
+            return vec![];
+        }
+    };
+    if match_func.is_callable() && fix_func.is_callable() {
+        let is_match = match call_rule_function(py, &match_func, command)
+            .and_then(|result| result.extract::<bool>())
+        {
+            Ok(result) => result,
+            Err(e) => {
+                diagnostic!(
+                    "{}{}{}",
+                    theme::warning("Failed to execute 'match' function in rule '"),
+                    rule_path.display(),
+                    theme::warning("': "),
+                );
+                diagnostic!("{e}");
+                return vec![];
+            }
+        };
+        if is_match {
+            let fixes = match call_rule_function(py, &fix_func, command).and_then(extract_fixes) {
+                Ok(fixes) => fixes,
                 Err(e) => {
-                    eprintln!(
+                    diagnostic!(
                         "{}{}{}",
-                        "Failed to get 'fix' function from rule '".yellow(),
+                        theme::warning("Failed to execute 'fix' function in rule '"),
                         rule_path.display(),
-                        "': ".yellow(),
+                        theme::warning("': "),
                     );
-                    eprintln!("{e}");
-                    continue;
+                    diagnostic!("{e}");
+                    return vec![];
                 }
             };
-            if match_func.is_callable() && fix_func.is_callable() {
-                let is_match = match match_func
-                    .call1((
-                        command.command(),
-                        command.output().stdout(),
-                        command.output().stderr(),
-                    ))
-                    .and_then(|result| result.extract::<bool>())
-                {
-                    Ok(result) => result,
-                    Err(e) => {
-                        eprintln!(
-                            "{}{}{}",
-                            "Failed to execute 'match' function in rule '".yellow(),
-                            rule_path.display(),
-                            "': ".yellow(),
-                        );
-                        eprintln!("{e}");
-                        continue;
-                    }
-                };
-                if is_match {
-                    let fixed_command: String = match fix_func
-                        .call1((
-                            command.command(),
-                            command.output().stdout(),
-                            command.output().stderr(),
-                        ))
-                        .and_then(|result| result.extract())
-                    {
-                        Ok(cmd) => cmd,
-                        Err(e) => {
-                            eprintln!(
-                                "{}{}{}",
-                                "Failed to execute 'fix' function in rule '".yellow(),
-                                rule_path.display(),
-                                "': ".yellow(),
-                            );
-                            eprintln!("{e}");
-                            continue;
-                        }
-                    };
-                    fixed_commands.push(fixed_command);
-                }
-            } else {
-                eprintln!(
+            let declared = get_priority(&module).unwrap_or_else(|e| {
+                diagnostic!(
                     "{}{}{}",
-                    "Rule '".yellow(),
+                    theme::warning("Invalid 'priority' in rule '"),
                     rule_path.display(),
-                    "' is missing required functions (match, fix)".yellow()
+                    theme::warning("', using the default: "),
                 );
-            }
+                diagnostic!("{e}");
+                None
+            });
+            let confidence = rule_confidence(&rule_name(rule_path), declared, priorities);
+            return fixes.into_iter().map(|fix| (fix, confidence)).collect();
         }
-        Ok(())
-    })?;
-    Ok(fixed_commands)
+    } else {
+        diagnostic!(
+            "{}{}{}",
+            theme::warning("Rule '"),
+            rule_path.display(),
+            theme::warning("' is missing required functions (match, fix)")
+        );
+    }
+    vec![]
+}
+
+/// Rewrites `\` separators as `/` so Windows-style paths split into components on every
+/// platform, even when both styles are mixed.
+fn normalize_separators(path: &Path) -> PathBuf {
+    PathBuf::from(path.to_string_lossy().replace('\\', "/"))
 }
 
 fn get_module_name(modules_dir_path: &Path, rule_path: &Path) -> Option<String> {
-    let mut module_path = match rule_path.strip_prefix(modules_dir_path) {
+    let modules_dir_path = normalize_separators(modules_dir_path);
+    let rule_path = normalize_separators(rule_path);
+    let mut module_path = match rule_path.strip_prefix(&modules_dir_path) {
         Ok(module_path) => module_path.parent().unwrap_or(Path::new("")).to_path_buf(),
         Err(_) => {
-            eprintln!(
+            diagnostic!(
                 "{}{}{}",
-                "Rule path '".yellow(),
+                theme::warning("Rule path '"),
                 rule_path.display(),
-                "' is not a subpath of the common parent".yellow()
+                theme::warning("' is not a subpath of the common parent")
             );
             return None;
         }
@@ -183,19 +674,19 @@ fn get_module_name(modules_dir_path: &Path, rule_path: &Path) -> Option<String>
             module_path.push(module_stem);
         }
         None => {
-            eprintln!(
+            diagnostic!(
                 "{}{}{}",
-                "Rule path '".yellow(),
+                theme::warning("Rule path '"),
                 rule_path.display(),
-                "' has no valid file stem".yellow()
+                theme::warning("' has no valid file stem")
             );
             return None;
         }
     }
-    Some(module_path.to_string_lossy().replace(['/', '\\'], "."))
+    Some(module_path.to_string_lossy().replace('/', "."))
 }
 
-fn get_common_parent(paths: &[PathBuf]) -> Option<PathBuf> {
+pub(super) fn get_common_parent(paths: &[PathBuf]) -> Option<PathBuf> {
     if paths.is_empty() {
         return None;
     }
@@ -227,6 +718,7 @@ fn get_common_parent(paths: &[PathBuf]) -> Option<PathBuf> {
 mod tests {
     use super::*;
     use crate::fix::structs::CommandOutput;
+    use crate::fix::structs::DEFAULT_CONFIDENCE;
     use std::fs;
     use std::io::Write;
     use tempfile::tempdir;
@@ -276,6 +768,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn module_name_windows_path() {
+        let modules_dir = PathBuf::from("C:\\rules");
+        let rule_path = PathBuf::from("C:\\rules\\sub\\r.py");
+        assert_eq!(
+            get_module_name(&modules_dir, &rule_path),
+            Some("sub.r".to_string())
+        );
+    }
+
+    #[test]
+    fn module_name_mixed_separators() {
+        let modules_dir = PathBuf::from("C:/rules");
+        let rule_path = PathBuf::from("C:\\rules/sub\\dir/r.py");
+        assert_eq!(
+            get_module_name(&modules_dir, &rule_path),
+            Some("sub.dir.r".to_string())
+        );
+    }
+
     #[test]
     fn module_name_not_subpath() {
         let modules_dir = PathBuf::from("/root/modules");
@@ -340,7 +852,7 @@ mod tests {
         }
 
         let cmd = dummy_command();
-        let result = process_python_rules(&cmd, vec![path]);
+        let result = process_python_rules(&cmd, vec![path], &HashMap::new(), false);
         assert!(result.is_ok());
         let commands = result.expect("Processing should succeed");
         assert!(commands.is_empty());
@@ -360,10 +872,13 @@ def fix(command, stdout, stderr):
 "#,
         );
         let cmd = dummy_command();
-        let result = process_python_rules(&cmd, vec![rule_path]);
+        let result = process_python_rules(&cmd, vec![rule_path], &HashMap::new(), false);
         assert!(result.is_ok());
         let commands = result.expect("Processing should succeed");
-        assert_eq!(commands, vec!["fixed-command".to_string()]);
+        assert_eq!(
+            commands,
+            vec![("fixed-command".to_string(), DEFAULT_CONFIDENCE)]
+        );
     }
 
     #[test]
@@ -380,7 +895,7 @@ def fix(command, stdout, stderr):
 "#,
         );
         let cmd = dummy_command();
-        let result = process_python_rules(&cmd, vec![rule_path]);
+        let result = process_python_rules(&cmd, vec![rule_path], &HashMap::new(), false);
         assert!(result.is_ok());
         let commands = result.expect("Processing should succeed");
         assert!(commands.is_empty());
@@ -398,7 +913,7 @@ def fix(command, stdout, stderr):
 "#,
         );
         let cmd = dummy_command();
-        let result = process_python_rules(&cmd, vec![rule_path]);
+        let result = process_python_rules(&cmd, vec![rule_path], &HashMap::new(), false);
         assert!(result.is_ok());
         let commands = result.expect("Processing should succeed");
         assert!(commands.is_empty());
@@ -418,7 +933,7 @@ def fix(command, stdout, stderr):
 "#,
         );
         let cmd = dummy_command();
-        let result = process_python_rules(&cmd, vec![rule_path]);
+        let result = process_python_rules(&cmd, vec![rule_path], &HashMap::new(), false);
         assert!(result.is_ok());
         let commands = result.expect("Processing should succeed");
         assert!(commands.is_empty());
@@ -438,7 +953,7 @@ def fix(command, stdout, stderr):
 "#,
         );
         let cmd = dummy_command();
-        let result = process_python_rules(&cmd, vec![rule_path]);
+        let result = process_python_rules(&cmd, vec![rule_path], &HashMap::new(), false);
         assert!(result.is_ok());
         let commands = result.expect("Processing should succeed");
         assert!(commands.is_empty());
@@ -472,17 +987,394 @@ def fix(c, o, e): return "cmd3"
 "#,
         );
         let cmd = dummy_command();
-        let result = process_python_rules(&cmd, vec![rule1, rule2, rule3]);
+        let result = process_python_rules(&cmd, vec![rule1, rule2, rule3], &HashMap::new(), false);
         assert!(result.is_ok());
         let commands = result.expect("Processing should succeed");
-        assert_eq!(commands, vec!["cmd1".to_string(), "cmd3".to_string()]);
+        assert_eq!(
+            commands,
+            vec![
+                ("cmd1".to_string(), DEFAULT_CONFIDENCE),
+                ("cmd3".to_string(), DEFAULT_CONFIDENCE)
+            ]
+        );
+    }
+
+    #[test]
+    fn process_nested_rules_in_sorted_order() {
+        let temp = tempdir().expect("Failed to create temp dir");
+        let rule = |name: &str| {
+            create_rule_file(
+                temp.path(),
+                name,
+                &format!("def match(c, o, e): return True\ndef fix(c, o, e): return {name:?}\n"),
+            )
+        };
+        let paths = vec![
+            rule("zz.py"),
+            rule("git/push_rule.py"),
+            rule("aa.py"),
+            rule("git/nested/amend_rule.py"),
+        ];
+        let cmd = dummy_command();
+        let commands = process_python_rules(&cmd, paths, &HashMap::new(), false)
+            .expect("Processing should succeed");
+        assert_eq!(
+            commands
+                .into_iter()
+                .map(|(command, _)| command)
+                .collect::<Vec<_>>(),
+            vec![
+                "aa.py",
+                "git/nested/amend_rule.py",
+                "git/push_rule.py",
+                "zz.py"
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn insecure_rules_yield_one_summary_line() {
+        let temp = tempdir().expect("Failed to create temp dir");
+        let mut paths = vec![];
+        for name in ["open1.py", "open2.py", "open3.py"] {
+            let path = create_rule_file(temp.path(), name, "def match(c, o, e): return True");
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o666))
+                .expect("Failed to set permissions");
+            paths.push(path);
+        }
+        let secure = create_rule_file(temp.path(), "secure.py", "");
+        paths.push(secure.clone());
+
+        let (allowed, skipped) = partition_secure_rules(paths, false);
+        assert_eq!(allowed, vec![secure]);
+        assert_eq!(skipped.len(), 3);
+
+        let summary = security_summary(&skipped, false).expect("Summary should be present");
+        assert_eq!(summary.lines().count(), 1);
+        assert!(summary.contains("3 rules skipped due to permissions:"));
+        assert!(summary.contains("open2.py"));
+        assert_eq!(security_summary(&[], false), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn foreign_owner_rejected_outside_trusted_dirs() {
+        let temp = tempdir().expect("Failed to create temp dir");
+        let rule = create_rule_file(temp.path(), "rule.py", "");
+        let other_uid = fs::metadata(&rule).expect("Rule should exist").uid() + 1;
+
+        assert!(check_security(&rule, other_uid, &[]).is_err());
+        let elsewhere = tempdir().expect("Failed to create temp dir");
+        let trusted = vec![fs::canonicalize(elsewhere.path()).expect("Dir should exist")];
+        assert!(check_security(&rule, other_uid, &trusted).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn foreign_owner_allowed_in_trusted_dir() {
+        let temp = tempdir().expect("Failed to create temp dir");
+        let rule = create_rule_file(temp.path(), "rule.py", "");
+        let other_uid = fs::metadata(&rule).expect("Rule should exist").uid() + 1;
+        let trusted = vec![fs::canonicalize(temp.path()).expect("Dir should exist")];
+
+        assert!(check_security(&rule, other_uid, &trusted).is_ok());
+
+        fs::set_permissions(&rule, fs::Permissions::from_mode(0o666))
+            .expect("Failed to set permissions");
+        assert!(check_security(&rule, other_uid, &trusted).is_err());
+    }
+
+    #[test]
+    fn process_rule_returning_list() {
+        let temp = tempdir().expect("Failed to create temp dir");
+        let rule_path = create_rule_file(
+            temp.path(),
+            "list_fix.py",
+            r#"
+def match(c, o, e): return True
+def fix(c, o, e): return ["git push", "git push --force-with-lease"]
+"#,
+        );
+        let cmd = dummy_command();
+        let commands = process_python_rules(&cmd, vec![rule_path], &HashMap::new(), false)
+            .expect("Processing should succeed");
+        assert_eq!(
+            commands,
+            vec![
+                ("git push".to_string(), DEFAULT_CONFIDENCE),
+                (
+                    "git push --force-with-lease".to_string(),
+                    DEFAULT_CONFIDENCE
+                )
+            ]
+        );
+    }
+
+    #[test]
+    fn process_rule_returning_string() {
+        let temp = tempdir().expect("Failed to create temp dir");
+        let rule_path = create_rule_file(
+            temp.path(),
+            "string_fix.py",
+            r#"
+def match(c, o, e): return True
+def fix(c, o, e): return "git push"
+"#,
+        );
+        let cmd = dummy_command();
+        let commands = process_python_rules(&cmd, vec![rule_path], &HashMap::new(), false)
+            .expect("Processing should succeed");
+        assert_eq!(commands, vec![("git push".to_string(), DEFAULT_CONFIDENCE)]);
+    }
+
+    #[test]
+    fn process_rule_returning_invalid_type() {
+        let temp = tempdir().expect("Failed to create temp dir");
+        let rule_path = create_rule_file(
+            temp.path(),
+            "invalid_fix.py",
+            r#"
+def match(c, o, e): return True
+def fix(c, o, e): return ["git push", 42]
+"#,
+        );
+        let cmd = dummy_command();
+        let commands = process_python_rules(&cmd, vec![rule_path], &HashMap::new(), false)
+            .expect("Processing should succeed");
+        assert!(commands.is_empty());
+    }
+
+    #[test]
+    fn process_rule_with_context() {
+        let temp = tempdir().expect("Failed to create temp dir");
+        let rule_path = create_rule_file(
+            temp.path(),
+            "ctx_rule.py",
+            r#"
+def match(ctx):
+    return ctx.exit_code == 127 and "not found" in ctx.stderr and ctx.cwd == "/srv/app"
+def fix(ctx):
+    assert ctx.output == ctx.stderr
+    return ctx.command + " --help"
+"#,
+        );
+        let output = CommandOutput::new(String::new(), "gti: command not found".to_string())
+            .with_exit_code(127);
+        let cmd = Command::new("gti".to_string(), output).with_cwd(PathBuf::from("/srv/app"));
+        let commands = process_python_rules(&cmd, vec![rule_path], &HashMap::new(), false)
+            .expect("Processing should succeed");
+        assert_eq!(
+            commands,
+            vec![("gti --help".to_string(), DEFAULT_CONFIDENCE)]
+        );
+    }
+
+    #[test]
+    fn process_rule_positional_and_context_mixed() {
+        let temp = tempdir().expect("Failed to create temp dir");
+        let rule_path = create_rule_file(
+            temp.path(),
+            "mixed_rule.py",
+            r#"
+def match(command, stdout, stderr):
+    return command == "test"
+def fix(ctx):
+    return f"{ctx.command} {ctx.exit_code}"
+"#,
+        );
+        let cmd = dummy_command();
+        let commands = process_python_rules(&cmd, vec![rule_path], &HashMap::new(), false)
+            .expect("Processing should succeed");
+        assert_eq!(
+            commands,
+            vec![("test None".to_string(), DEFAULT_CONFIDENCE)]
+        );
+    }
+
+    #[test]
+    fn process_twice_does_not_duplicate_sys_path() {
+        let temp = tempdir().expect("Failed to create temp dir");
+        let rule_path = create_rule_file(
+            temp.path(),
+            "sys_path_rule.py",
+            r#"
+def match(c, o, e): return True
+def fix(c, o, e): return "fixed"
+"#,
+        );
+        let cmd = dummy_command();
+        for _ in 0..2 {
+            process_python_rules(&cmd, vec![rule_path.clone()], &HashMap::new(), false)
+                .expect("Processing should succeed");
+        }
+
+        let module_path = temp.path().to_string_lossy().into_owned();
+        let count = Python::with_gil(|py| -> PyResult<usize> {
+            let sys_path: Vec<String> = py.import("sys")?.getattr("path")?.extract()?;
+            Ok(sys_path.iter().filter(|path| **path == module_path).count())
+        })
+        .expect("sys.path should be readable");
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn process_rule_with_priority() {
+        let temp = tempdir().expect("Failed to create temp dir");
+        let callable = create_rule_file(
+            temp.path(),
+            "priority_callable.py",
+            r#"
+def match(c, o, e): return True
+def fix(c, o, e): return "callable"
+def priority(): return 90
+"#,
+        );
+        let attribute = create_rule_file(
+            temp.path(),
+            "priority_attribute.py",
+            r#"
+priority = 10
+def match(c, o, e): return True
+def fix(c, o, e): return "attribute"
+"#,
+        );
+        let invalid = create_rule_file(
+            temp.path(),
+            "priority_invalid.py",
+            r#"
+def priority(): return "high"
+def match(c, o, e): return True
+def fix(c, o, e): return "invalid"
+"#,
+        );
+        let cmd = dummy_command();
+        let result = process_python_rules(
+            &cmd,
+            vec![callable, attribute, invalid],
+            &HashMap::new(),
+            false,
+        );
+        let commands = result.expect("Processing should succeed");
+        assert_eq!(
+            commands,
+            vec![
+                ("attribute".to_string(), 10),
+                ("callable".to_string(), 90),
+                ("invalid".to_string(), DEFAULT_CONFIDENCE)
+            ]
+        );
+    }
+
+    #[test]
+    fn process_rule_with_configured_priority() {
+        let temp = tempdir().expect("Failed to create temp dir");
+        let rule_path = create_rule_file(
+            temp.path(),
+            "configured.py",
+            r#"
+priority = 10
+def match(c, o, e): return True
+def fix(c, o, e): return "configured"
+"#,
+        );
+        let cmd = dummy_command();
+        let priorities = HashMap::from([("configured".to_string(), 75)]);
+        let commands = process_python_rules(&cmd, vec![rule_path], &priorities, false)
+            .expect("Processing should succeed");
+        assert_eq!(commands, vec![("configured".to_string(), 75)]);
+    }
+
+    #[test]
+    fn encoded_fixes_round_trip() {
+        let fixes = vec![
+            vec![
+                ("git push".to_string(), 90),
+                ("multi\nline\tfix".to_string(), 10),
+            ],
+            vec![],
+            vec![("ünïcode".to_string(), DEFAULT_CONFIDENCE)],
+        ];
+        assert_eq!(decode_fixes(&encode_fixes(&fixes)), Some(fixes.clone()));
+        let encoded = encode_fixes(&fixes);
+        assert_eq!(decode_fixes(&encoded[..encoded.len() - 1]), None);
+    }
+
+    #[test]
+    fn parallel_rules_match_serial() {
+        let temp = tempdir().expect("Failed to create temp dir");
+        let rules = (0..10)
+            .map(|index| {
+                let source = match index % 4 {
+                    0 => format!("def match(c, o, e): return True\ndef fix(c, o, e): return 'fix{index}'\n"),
+                    1 => "def match(c, o, e): return False\ndef fix(c, o, e): return 'never'\n".to_string(),
+                    2 => format!(
+                        "priority = {index}\ndef match(ctx): return ctx.command == 'test'\ndef fix(ctx): return ['a{index}', 'b{index}']\n"
+                    ),
+                    _ => "def match(c, o, e): raise ValueError('boom')\ndef fix(c, o, e): return 'never'\n".to_string(),
+                };
+                create_rule_file(temp.path(), &format!("parallel_rule_{index}.py"), &source)
+            })
+            .collect::<Vec<_>>();
+        let cmd = dummy_command();
+        let priorities = HashMap::from([("parallel_rule_4".to_string(), 77)]);
+
+        let parallel = run_python_rules_parallel(
+            &cmd,
+            temp.path(),
+            rules.clone(),
+            &priorities,
+            3,
+            WORKER_TIMEOUT,
+        )
+        .expect("Parallel processing should succeed");
+        let serial = run_python_rules(&cmd, temp.path(), rules, &priorities)
+            .expect("Serial processing should succeed");
+        assert_eq!(parallel, serial);
+        assert_eq!(
+            parallel.first(),
+            Some(&("fix0".to_string(), DEFAULT_CONFIDENCE))
+        );
+        assert!(parallel.contains(&("b6".to_string(), 6)));
+        assert!(parallel.contains(&("fix4".to_string(), 77)));
+    }
+
+    #[test]
+    fn parallel_rules_skip_worker_past_deadline() {
+        let temp = tempdir().expect("Failed to create temp dir");
+        let rules = (0..8)
+            .map(|index| {
+                let source = if index == 0 {
+                    "import time\ndef match(c, o, e):\n    time.sleep(60)\n    return True\ndef fix(c, o, e): return 'slow'\n".to_string()
+                } else {
+                    format!("def match(c, o, e): return True\ndef fix(c, o, e): return 'fix{index}'\n")
+                };
+                create_rule_file(temp.path(), &format!("deadline_rule_{index}.py"), &source)
+            })
+            .collect::<Vec<_>>();
+        let cmd = dummy_command();
+
+        let started = Instant::now();
+        let fixes = run_python_rules_parallel(
+            &cmd,
+            temp.path(),
+            rules,
+            &HashMap::new(),
+            2,
+            Duration::from_secs(2),
+        )
+        .expect("Parallel processing should succeed");
+        assert!(started.elapsed() < Duration::from_secs(30));
+        // Rules 0, 2, 4 and 6 shared the worker that was killed.
+        let fixes = fixes.into_iter().map(|(fix, _)| fix).collect::<Vec<_>>();
+        assert_eq!(fixes, vec!["fix1", "fix3", "fix5", "fix7"]);
     }
 
     #[test]
     fn process_no_common_parent() {
         let paths = vec![PathBuf::from("a/b.py"), PathBuf::from("c/d.py")];
         let cmd = dummy_command();
-        let result = process_python_rules(&cmd, paths);
+        let result = process_python_rules(&cmd, paths, &HashMap::new(), false);
         assert!(result.is_err());
         let err = result.unwrap_err();
         assert!(err.to_string().contains("No common parent found"));
@@ -491,7 +1383,7 @@ def fix(c, o, e): return "cmd3"
     #[test]
     fn process_empty_rules() {
         let cmd = dummy_command();
-        let result = process_python_rules(&cmd, vec![]);
+        let result = process_python_rules(&cmd, vec![], &HashMap::new(), false);
         assert!(result.is_ok());
         let commands = result.expect("Processing should succeed");
         assert!(commands.is_empty());
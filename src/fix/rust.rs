@@ -1,13 +1,56 @@
+mod apt_fetch_404;
+mod apt_purge;
+mod aws_config;
+mod cargo_flag;
 mod cargo_no_command;
+mod cd_back;
+mod cd_typo;
+mod chown_sudo;
+mod cli_login;
+mod curl_follow;
+mod dash_filename;
+mod git_add_remote;
+mod git_alias;
+mod git_branches;
+mod git_clone_existing;
+mod git_conflict;
+mod git_detached_head;
+mod git_identity;
+mod git_no_verify;
+mod git_not_repo;
+mod git_protected_branch;
+mod git_push;
+mod git_push_head;
+mod git_remote_protocol;
+mod git_remotes;
+mod help_hint;
+mod history_repair;
+mod kubectl_context;
+mod ln_order;
 mod mkdir_p;
+mod node_version;
+mod pager_hint;
+mod paste_prompt;
+mod pip_venv;
+mod pip_version;
+mod python_module;
+mod quote_glob;
+mod ssh_legacy_key;
 mod sudo;
+mod sudo_builtin;
+mod systemctl_unit;
+mod tar_compression;
 mod to_cd;
+mod ts_did_you_mean;
 mod unsudo;
+mod unzip_dir;
+mod wrapper;
 
-use super::structs::Command;
-use strum::EnumString;
+use super::structs::{Command, DEFAULT_CONFIDENCE, FALLBACK_CONFIDENCE};
+use crate::misc::log::diagnostic;
+use strum::{EnumIter, EnumString, IntoStaticStr};
 
-#[derive(EnumString, Debug)]
+#[derive(EnumString, IntoStaticStr, EnumIter, Debug)]
 pub enum NativeRule {
     #[strum(serialize = "sudo")]
     Sudo,
@@ -19,10 +62,145 @@ pub enum NativeRule {
     MkdirP,
     #[strum(serialize = "cargo_no_command")]
     CargoNoCommand,
+    #[strum(serialize = "cli_login")]
+    CliLogin,
+    #[strum(serialize = "unzip_dir")]
+    UnzipDir,
+    #[strum(serialize = "ts_did_you_mean")]
+    TsDidYouMean,
+    #[strum(serialize = "aws_config")]
+    AwsConfig,
+    #[strum(serialize = "git_conflict")]
+    GitConflict,
+    #[strum(serialize = "git_identity")]
+    GitIdentity,
+    #[strum(serialize = "apt_fetch_404")]
+    AptFetch404,
+    #[strum(serialize = "dash_filename")]
+    DashFilename,
+    #[strum(serialize = "cd_typo")]
+    CdTypo,
+    #[strum(serialize = "git_detached_head")]
+    GitDetachedHead,
+    #[strum(serialize = "history_repair")]
+    HistoryRepair,
+    #[strum(serialize = "cd_back")]
+    CdBack,
+    #[strum(serialize = "git_clone_existing")]
+    GitCloneExisting,
+    #[strum(serialize = "pip_version")]
+    PipVersion,
+    #[strum(serialize = "git_not_repo")]
+    GitNotRepo,
+    #[strum(serialize = "cargo_flag")]
+    CargoFlag,
+    #[strum(serialize = "apt_purge")]
+    AptPurge,
+    #[strum(serialize = "node_version")]
+    NodeVersion,
+    #[strum(serialize = "git_push")]
+    GitPush,
+    #[strum(serialize = "paste_prompt")]
+    PastePrompt,
+    #[strum(serialize = "quote_glob")]
+    QuoteGlob,
+    #[strum(serialize = "kubectl_context")]
+    KubectlContext,
+    #[strum(serialize = "sudo_builtin")]
+    SudoBuiltin,
+    #[strum(serialize = "pip_venv")]
+    PipVenv,
+    #[strum(serialize = "git_no_verify")]
+    GitNoVerify,
+    #[strum(serialize = "chown_sudo")]
+    ChownSudo,
+    #[strum(serialize = "git_remote_protocol")]
+    GitRemoteProtocol,
+    #[strum(serialize = "ssh_legacy_key")]
+    SshLegacyKey,
+    #[strum(serialize = "help_hint")]
+    HelpHint,
+    #[strum(serialize = "tar_compression")]
+    TarCompression,
+    #[strum(serialize = "ln_order")]
+    LnOrder,
+    #[strum(serialize = "git_protected_branch")]
+    GitProtectedBranch,
+    #[strum(serialize = "pager_hint")]
+    PagerHint,
+    #[strum(serialize = "python_module")]
+    PythonModule,
+    #[strum(serialize = "curl_follow")]
+    CurlFollow,
+    #[strum(serialize = "systemctl_unit")]
+    SystemctlUnit,
+    #[strum(serialize = "git_add_remote")]
+    GitAddRemote,
+    #[strum(serialize = "git_push_head")]
+    GitPushHead,
 }
 
 impl NativeRule {
-    pub fn fix_native(self, command: &Command) -> Option<String> {
+    /// Returns the rule's fixes, best first; empty when it doesn't match.
+    pub fn fix_native(&self, command: &Command) -> Vec<String> {
+        let fixed = self.fix_resolved(command);
+        if !fixed.is_empty() {
+            return fixed;
+        }
+        // Retry without `env`/`nice`/... so rules see the real command; rules about sudo keep it.
+        let Some(wrapped) = wrapper::strip(command, !self.is_sudo_rule()) else {
+            return vec![];
+        };
+        self.fix_resolved(wrapped.inner())
+            .iter()
+            .map(|fixed| wrapped.rewrap(fixed))
+            .collect()
+    }
+
+    /// Fixes a command that hasn't run yet, so only rules that can tell from the command alone
+    /// take part.
+    pub fn fix_current(&self, command: &Command) -> Vec<String> {
+        match self {
+            NativeRule::ToCd => {
+                Self::match_and_fix(to_cd::is_match, || Some(to_cd::fix(command)), command)
+            }
+            NativeRule::PastePrompt => Self::match_and_fix(
+                paste_prompt::is_prompt,
+                || Some(paste_prompt::fix(command)),
+                command,
+            ),
+            NativeRule::QuoteGlob => Self::match_and_fix(
+                quote_glob::has_literal_glob,
+                || Some(quote_glob::fix(command)),
+                command,
+            ),
+            _ => vec![],
+        }
+    }
+
+    fn fix_resolved(&self, command: &Command) -> Vec<String> {
+        // `git_*` rules see user aliases expanded, so `git co` is handled like `git checkout`.
+        if self.is_git_rule()
+            && let Some(resolved) = git_alias::resolve(command)
+        {
+            return self.apply(&resolved);
+        }
+        self.apply(command)
+    }
+
+    fn is_sudo_rule(&self) -> bool {
+        matches!(
+            self,
+            NativeRule::Sudo | NativeRule::Unsudo | NativeRule::SudoBuiltin
+        )
+    }
+
+    fn is_git_rule(&self) -> bool {
+        let name: &'static str = self.into();
+        name.starts_with("git_")
+    }
+
+    fn apply(&self, command: &Command) -> Vec<String> {
         match self {
             NativeRule::Sudo => {
                 Self::match_and_fix(sudo::is_match, || Some(sudo::fix(command)), command)
@@ -38,7 +216,7 @@ impl NativeRule {
                 || match mkdir_p::fix(command) {
                     Ok(s) => Some(s),
                     Err(e) => {
-                        eprintln!("Error in mkdir_p fix: {}", e);
+                        diagnostic!("Error in mkdir_p fix: {}", e);
                         None
                     }
                 },
@@ -49,27 +227,292 @@ impl NativeRule {
                 || match cargo_no_command::fix(command) {
                     Ok(s) => Some(s),
                     Err(e) => {
-                        eprintln!("Error in cargo_no_command fix: {}", e);
+                        diagnostic!("Error in cargo_no_command fix: {}", e);
                         None
                     }
                 },
                 command,
             ),
+            NativeRule::CliLogin => Self::match_and_fix(
+                cli_login::is_match,
+                || Some(cli_login::fix(command)),
+                command,
+            ),
+            NativeRule::UnzipDir => Self::match_and_fix(
+                unzip_dir::is_match,
+                || Some(unzip_dir::fix(command)),
+                command,
+            ),
+            NativeRule::TsDidYouMean => Self::match_and_fix(
+                ts_did_you_mean::is_match,
+                || match ts_did_you_mean::fix(command) {
+                    Ok(s) => Some(s),
+                    Err(e) => {
+                        diagnostic!("Error in ts_did_you_mean fix: {}", e);
+                        None
+                    }
+                },
+                command,
+            ),
+            NativeRule::AwsConfig => Self::match_and_fix(
+                aws_config::is_match,
+                || Some(aws_config::fix(command)),
+                command,
+            ),
+            NativeRule::GitConflict => Self::match_and_fix(
+                git_conflict::is_match,
+                || Some(git_conflict::fix(command)),
+                command,
+            ),
+            NativeRule::GitIdentity => Self::match_and_fix(
+                git_identity::is_match,
+                || Some(git_identity::fix(command)),
+                command,
+            ),
+            NativeRule::AptFetch404 => Self::match_and_fix(
+                apt_fetch_404::is_match,
+                || Some(apt_fetch_404::fix(command)),
+                command,
+            ),
+            NativeRule::DashFilename => Self::match_and_fix(
+                dash_filename::is_match,
+                || Some(dash_filename::fix(command)),
+                command,
+            ),
+            NativeRule::CdTypo => {
+                Self::match_and_fix(cd_typo::is_match, || Some(cd_typo::fix(command)), command)
+            }
+            NativeRule::GitDetachedHead => Self::match_and_fix(
+                git_detached_head::is_match,
+                || Some(git_detached_head::fix(command)),
+                command,
+            ),
+            NativeRule::HistoryRepair => Self::match_and_fix(
+                history_repair::is_match,
+                || Some(history_repair::fix(command)),
+                command,
+            ),
+            NativeRule::CdBack => {
+                Self::match_and_fix(cd_back::is_match, || Some(cd_back::fix(command)), command)
+            }
+            NativeRule::GitCloneExisting => Self::match_and_fix(
+                git_clone_existing::is_match,
+                || Some(git_clone_existing::fix(command)),
+                command,
+            ),
+            NativeRule::PipVersion => Self::match_and_fix(
+                pip_version::is_match,
+                || Some(pip_version::fix(command)),
+                command,
+            ),
+            NativeRule::GitNotRepo => Self::match_and_fix(
+                git_not_repo::is_match,
+                || Some(git_not_repo::fix(command)),
+                command,
+            ),
+            NativeRule::CargoFlag => Self::match_and_fix(
+                cargo_flag::is_match,
+                || Some(cargo_flag::fix(command)),
+                command,
+            ),
+            NativeRule::AptPurge => Self::match_and_fix(
+                apt_purge::is_match,
+                || Some(apt_purge::fix(command)),
+                command,
+            ),
+            NativeRule::NodeVersion => Self::match_and_fix(
+                node_version::is_match,
+                || Some(node_version::fix(command)),
+                command,
+            ),
+            NativeRule::GitPush => {
+                Self::match_and_fix(git_push::is_match, || Some(git_push::fix(command)), command)
+            }
+            NativeRule::PastePrompt => Self::match_and_fix(
+                paste_prompt::is_match,
+                || Some(paste_prompt::fix(command)),
+                command,
+            ),
+            NativeRule::QuoteGlob => Self::match_and_fix(
+                quote_glob::is_match,
+                || Some(quote_glob::fix(command)),
+                command,
+            ),
+            NativeRule::KubectlContext => Self::match_and_fix(
+                kubectl_context::is_match,
+                || Some(kubectl_context::fix(command)),
+                command,
+            ),
+            NativeRule::SudoBuiltin => Self::match_and_fix(
+                sudo_builtin::is_match,
+                || Some(sudo_builtin::fix(command)),
+                command,
+            ),
+            NativeRule::PipVenv => {
+                Self::match_and_fix_all(pip_venv::is_match, || pip_venv::fix(command), command)
+            }
+            NativeRule::GitNoVerify => Self::match_and_fix(
+                git_no_verify::is_match,
+                || Some(git_no_verify::fix(command)),
+                command,
+            ),
+            NativeRule::ChownSudo => Self::match_and_fix(
+                chown_sudo::is_match,
+                || Some(chown_sudo::fix(command)),
+                command,
+            ),
+            NativeRule::GitRemoteProtocol => Self::match_and_fix(
+                git_remote_protocol::is_match,
+                || Some(git_remote_protocol::fix(command)),
+                command,
+            ),
+            NativeRule::SshLegacyKey => Self::match_and_fix(
+                ssh_legacy_key::is_match,
+                || Some(ssh_legacy_key::fix(command)),
+                command,
+            ),
+            NativeRule::HelpHint => Self::match_and_fix(
+                help_hint::is_match,
+                || Some(help_hint::fix(command)),
+                command,
+            ),
+            NativeRule::TarCompression => Self::match_and_fix(
+                tar_compression::is_match,
+                || Some(tar_compression::fix(command)),
+                command,
+            ),
+            NativeRule::LnOrder => {
+                Self::match_and_fix(ln_order::is_match, || Some(ln_order::fix(command)), command)
+            }
+            NativeRule::GitProtectedBranch => Self::match_and_fix(
+                git_protected_branch::is_match,
+                || Some(git_protected_branch::fix(command)),
+                command,
+            ),
+            NativeRule::PagerHint => Self::match_and_fix(
+                pager_hint::is_match,
+                || Some(pager_hint::fix(command)),
+                command,
+            ),
+            NativeRule::PythonModule => Self::match_and_fix(
+                python_module::is_match,
+                || Some(python_module::fix(command)),
+                command,
+            ),
+            NativeRule::CurlFollow => Self::match_and_fix(
+                curl_follow::is_match,
+                || Some(curl_follow::fix(command)),
+                command,
+            ),
+            NativeRule::SystemctlUnit => Self::match_and_fix(
+                systemctl_unit::is_match,
+                || Some(systemctl_unit::fix(command)),
+                command,
+            ),
+            NativeRule::GitAddRemote => Self::match_and_fix(
+                git_add_remote::is_match,
+                || Some(git_add_remote::fix(command)),
+                command,
+            ),
+            NativeRule::GitPushHead => Self::match_and_fix(
+                git_push_head::is_match,
+                || Some(git_push_head::fix(command)),
+                command,
+            ),
+        }
+    }
+
+    /// What the rule matches and what it suggests, for `theshit rules explain`.
+    pub fn description(&self) -> &'static str {
+        match self {
+            NativeRule::Sudo => sudo::DESCRIPTION,
+            NativeRule::ToCd => to_cd::DESCRIPTION,
+            NativeRule::Unsudo => unsudo::DESCRIPTION,
+            NativeRule::MkdirP => mkdir_p::DESCRIPTION,
+            NativeRule::CargoNoCommand => cargo_no_command::DESCRIPTION,
+            NativeRule::CliLogin => cli_login::DESCRIPTION,
+            NativeRule::UnzipDir => unzip_dir::DESCRIPTION,
+            NativeRule::TsDidYouMean => ts_did_you_mean::DESCRIPTION,
+            NativeRule::AwsConfig => aws_config::DESCRIPTION,
+            NativeRule::GitConflict => git_conflict::DESCRIPTION,
+            NativeRule::GitIdentity => git_identity::DESCRIPTION,
+            NativeRule::AptFetch404 => apt_fetch_404::DESCRIPTION,
+            NativeRule::DashFilename => dash_filename::DESCRIPTION,
+            NativeRule::CdTypo => cd_typo::DESCRIPTION,
+            NativeRule::GitDetachedHead => git_detached_head::DESCRIPTION,
+            NativeRule::HistoryRepair => history_repair::DESCRIPTION,
+            NativeRule::CdBack => cd_back::DESCRIPTION,
+            NativeRule::GitCloneExisting => git_clone_existing::DESCRIPTION,
+            NativeRule::PipVersion => pip_version::DESCRIPTION,
+            NativeRule::GitNotRepo => git_not_repo::DESCRIPTION,
+            NativeRule::CargoFlag => cargo_flag::DESCRIPTION,
+            NativeRule::AptPurge => apt_purge::DESCRIPTION,
+            NativeRule::NodeVersion => node_version::DESCRIPTION,
+            NativeRule::GitPush => git_push::DESCRIPTION,
+            NativeRule::PastePrompt => paste_prompt::DESCRIPTION,
+            NativeRule::QuoteGlob => quote_glob::DESCRIPTION,
+            NativeRule::KubectlContext => kubectl_context::DESCRIPTION,
+            NativeRule::SudoBuiltin => sudo_builtin::DESCRIPTION,
+            NativeRule::PipVenv => pip_venv::DESCRIPTION,
+            NativeRule::GitNoVerify => git_no_verify::DESCRIPTION,
+            NativeRule::ChownSudo => chown_sudo::DESCRIPTION,
+            NativeRule::GitRemoteProtocol => git_remote_protocol::DESCRIPTION,
+            NativeRule::SshLegacyKey => ssh_legacy_key::DESCRIPTION,
+            NativeRule::HelpHint => help_hint::DESCRIPTION,
+            NativeRule::TarCompression => tar_compression::DESCRIPTION,
+            NativeRule::LnOrder => ln_order::DESCRIPTION,
+            NativeRule::GitProtectedBranch => git_protected_branch::DESCRIPTION,
+            NativeRule::PagerHint => pager_hint::DESCRIPTION,
+            NativeRule::PythonModule => python_module::DESCRIPTION,
+            NativeRule::CurlFollow => curl_follow::DESCRIPTION,
+            NativeRule::SystemctlUnit => systemctl_unit::DESCRIPTION,
+            NativeRule::GitAddRemote => git_add_remote::DESCRIPTION,
+            NativeRule::GitPushHead => git_push_head::DESCRIPTION,
+        }
+    }
+
+    /// How sure this rule is about its fix, on a 0-100 scale; higher sorts first.
+    pub fn confidence(&self) -> u8 {
+        match self {
+            NativeRule::PagerHint => pager_hint::CONFIDENCE,
+            _ if self.is_fallback() => FALLBACK_CONFIDENCE,
+            _ => DEFAULT_CONFIDENCE,
         }
     }
 
+    /// Fallback rules are generic hints that are dropped whenever a real fix exists.
+    pub fn is_fallback(&self) -> bool {
+        matches!(self, NativeRule::HelpHint)
+    }
+
     fn match_and_fix<F>(
         match_function: fn(&Command) -> bool,
         fix_function: F,
         command: &Command,
-    ) -> Option<String>
+    ) -> Vec<String>
     where
         F: FnOnce() -> Option<String>,
+    {
+        Self::match_and_fix_all(
+            match_function,
+            || fix_function().into_iter().collect(),
+            command,
+        )
+    }
+
+    /// Like [`NativeRule::match_and_fix`], for rules that offer several alternatives.
+    fn match_and_fix_all<F>(
+        match_function: fn(&Command) -> bool,
+        fix_function: F,
+        command: &Command,
+    ) -> Vec<String>
+    where
+        F: FnOnce() -> Vec<String>,
     {
         if match_function(command) {
             fix_function()
         } else {
-            None
+            vec![]
         }
     }
 }
@@ -79,6 +522,24 @@ mod tests {
     use super::*;
     use crate::fix::structs::CommandOutput;
     use std::str::FromStr;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn test_every_rule_has_a_description() {
+        for rule in NativeRule::iter() {
+            assert!(
+                !rule.description().trim().is_empty(),
+                "{rule:?} has no description"
+            );
+        }
+    }
+
+    #[test]
+    fn test_is_git_rule() {
+        assert!(NativeRule::GitConflict.is_git_rule());
+        assert!(NativeRule::GitIdentity.is_git_rule());
+        assert!(!NativeRule::Sudo.is_git_rule());
+    }
 
     #[test]
     fn test_native_rule_from_str_sudo() {
@@ -118,6 +579,344 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_native_rule_from_str_cli_login() {
+        let rule = NativeRule::from_str("cli_login");
+        assert!(rule.is_ok());
+        assert!(matches!(rule.expect("should be Ok"), NativeRule::CliLogin));
+    }
+
+    #[test]
+    fn test_native_rule_from_str_unzip_dir() {
+        let rule = NativeRule::from_str("unzip_dir");
+        assert!(rule.is_ok());
+        assert!(matches!(rule.expect("should be Ok"), NativeRule::UnzipDir));
+    }
+
+    #[test]
+    fn test_native_rule_from_str_ts_did_you_mean() {
+        let rule = NativeRule::from_str("ts_did_you_mean");
+        assert!(rule.is_ok());
+        assert!(matches!(
+            rule.expect("should be Ok"),
+            NativeRule::TsDidYouMean
+        ));
+    }
+
+    #[test]
+    fn test_native_rule_from_str_aws_config() {
+        let rule = NativeRule::from_str("aws_config");
+        assert!(rule.is_ok());
+        assert!(matches!(rule.expect("should be Ok"), NativeRule::AwsConfig));
+    }
+
+    #[test]
+    fn test_native_rule_from_str_git_conflict() {
+        let rule = NativeRule::from_str("git_conflict");
+        assert!(rule.is_ok());
+        assert!(matches!(
+            rule.expect("should be Ok"),
+            NativeRule::GitConflict
+        ));
+    }
+
+    #[test]
+    fn test_native_rule_from_str_git_identity() {
+        let rule = NativeRule::from_str("git_identity");
+        assert!(rule.is_ok());
+        assert!(matches!(
+            rule.expect("should be Ok"),
+            NativeRule::GitIdentity
+        ));
+    }
+
+    #[test]
+    fn test_native_rule_from_str_apt_fetch_404() {
+        let rule = NativeRule::from_str("apt_fetch_404");
+        assert!(rule.is_ok());
+        assert!(matches!(
+            rule.expect("should be Ok"),
+            NativeRule::AptFetch404
+        ));
+    }
+
+    #[test]
+    fn test_native_rule_from_str_dash_filename() {
+        let rule = NativeRule::from_str("dash_filename");
+        assert!(rule.is_ok());
+        assert!(matches!(
+            rule.expect("should be Ok"),
+            NativeRule::DashFilename
+        ));
+    }
+
+    #[test]
+    fn test_native_rule_from_str_cd_typo() {
+        let rule = NativeRule::from_str("cd_typo");
+        assert!(rule.is_ok());
+        assert!(matches!(rule.expect("should be Ok"), NativeRule::CdTypo));
+    }
+
+    #[test]
+    fn test_native_rule_from_str_git_detached_head() {
+        let rule = NativeRule::from_str("git_detached_head");
+        assert!(rule.is_ok());
+        assert!(matches!(
+            rule.expect("should be Ok"),
+            NativeRule::GitDetachedHead
+        ));
+    }
+
+    #[test]
+    fn test_native_rule_from_str_history_repair() {
+        let rule = NativeRule::from_str("history_repair");
+        assert!(rule.is_ok());
+        assert!(matches!(
+            rule.expect("should be Ok"),
+            NativeRule::HistoryRepair
+        ));
+    }
+
+    #[test]
+    fn test_native_rule_from_str_cd_back() {
+        let rule = NativeRule::from_str("cd_back");
+        assert!(rule.is_ok());
+        assert!(matches!(rule.expect("should be Ok"), NativeRule::CdBack));
+    }
+
+    #[test]
+    fn test_native_rule_from_str_git_clone_existing() {
+        let rule = NativeRule::from_str("git_clone_existing");
+        assert!(rule.is_ok());
+        assert!(matches!(
+            rule.expect("should be Ok"),
+            NativeRule::GitCloneExisting
+        ));
+    }
+
+    #[test]
+    fn test_native_rule_from_str_pip_version() {
+        let rule = NativeRule::from_str("pip_version");
+        assert!(rule.is_ok());
+        assert!(matches!(
+            rule.expect("should be Ok"),
+            NativeRule::PipVersion
+        ));
+    }
+
+    #[test]
+    fn test_native_rule_from_str_git_not_repo() {
+        let rule = NativeRule::from_str("git_not_repo");
+        assert!(rule.is_ok());
+        assert!(matches!(
+            rule.expect("should be Ok"),
+            NativeRule::GitNotRepo
+        ));
+    }
+
+    #[test]
+    fn test_native_rule_from_str_cargo_flag() {
+        let rule = NativeRule::from_str("cargo_flag");
+        assert!(rule.is_ok());
+        assert!(matches!(rule.expect("should be Ok"), NativeRule::CargoFlag));
+    }
+
+    #[test]
+    fn test_native_rule_from_str_apt_purge() {
+        let rule = NativeRule::from_str("apt_purge");
+        assert!(rule.is_ok());
+        assert!(matches!(rule.expect("should be Ok"), NativeRule::AptPurge));
+    }
+
+    #[test]
+    fn test_native_rule_from_str_node_version() {
+        let rule = NativeRule::from_str("node_version");
+        assert!(rule.is_ok());
+        assert!(matches!(
+            rule.expect("should be Ok"),
+            NativeRule::NodeVersion
+        ));
+    }
+
+    #[test]
+    fn test_native_rule_from_str_git_push() {
+        let rule = NativeRule::from_str("git_push");
+        assert!(rule.is_ok());
+        assert!(matches!(rule.expect("should be Ok"), NativeRule::GitPush));
+    }
+
+    #[test]
+    fn test_native_rule_from_str_paste_prompt() {
+        let rule = NativeRule::from_str("paste_prompt");
+        assert!(rule.is_ok());
+        assert!(matches!(
+            rule.expect("should be Ok"),
+            NativeRule::PastePrompt
+        ));
+    }
+
+    #[test]
+    fn test_native_rule_from_str_quote_glob() {
+        let rule = NativeRule::from_str("quote_glob");
+        assert!(rule.is_ok());
+        assert!(matches!(rule.expect("should be Ok"), NativeRule::QuoteGlob));
+    }
+
+    #[test]
+    fn test_native_rule_from_str_kubectl_context() {
+        let rule = NativeRule::from_str("kubectl_context");
+        assert!(rule.is_ok());
+        assert!(matches!(
+            rule.expect("should be Ok"),
+            NativeRule::KubectlContext
+        ));
+    }
+
+    #[test]
+    fn test_native_rule_from_str_sudo_builtin() {
+        let rule = NativeRule::from_str("sudo_builtin");
+        assert!(rule.is_ok());
+        assert!(matches!(
+            rule.expect("should be Ok"),
+            NativeRule::SudoBuiltin
+        ));
+    }
+
+    #[test]
+    fn test_native_rule_from_str_pip_venv() {
+        let rule = NativeRule::from_str("pip_venv");
+        assert!(rule.is_ok());
+        assert!(matches!(rule.expect("should be Ok"), NativeRule::PipVenv));
+    }
+
+    #[test]
+    fn test_native_rule_from_str_git_no_verify() {
+        let rule = NativeRule::from_str("git_no_verify");
+        assert!(rule.is_ok());
+        assert!(matches!(
+            rule.expect("should be Ok"),
+            NativeRule::GitNoVerify
+        ));
+    }
+
+    #[test]
+    fn test_native_rule_from_str_chown_sudo() {
+        let rule = NativeRule::from_str("chown_sudo");
+        assert!(rule.is_ok());
+        assert!(matches!(rule.expect("should be Ok"), NativeRule::ChownSudo));
+    }
+
+    #[test]
+    fn test_native_rule_from_str_git_remote_protocol() {
+        let rule = NativeRule::from_str("git_remote_protocol");
+        assert!(rule.is_ok());
+        assert!(matches!(
+            rule.expect("should be Ok"),
+            NativeRule::GitRemoteProtocol
+        ));
+    }
+
+    #[test]
+    fn test_native_rule_from_str_ssh_legacy_key() {
+        let rule = NativeRule::from_str("ssh_legacy_key");
+        assert!(rule.is_ok());
+        assert!(matches!(
+            rule.expect("should be Ok"),
+            NativeRule::SshLegacyKey
+        ));
+    }
+
+    #[test]
+    fn test_native_rule_from_str_help_hint() {
+        let rule = NativeRule::from_str("help_hint");
+        assert!(rule.is_ok());
+        assert!(matches!(rule.expect("should be Ok"), NativeRule::HelpHint));
+    }
+
+    #[test]
+    fn test_native_rule_from_str_tar_compression() {
+        let rule = NativeRule::from_str("tar_compression");
+        assert!(rule.is_ok());
+        assert!(matches!(
+            rule.expect("should be Ok"),
+            NativeRule::TarCompression
+        ));
+    }
+
+    #[test]
+    fn test_native_rule_from_str_ln_order() {
+        let rule = NativeRule::from_str("ln_order");
+        assert!(rule.is_ok());
+        assert!(matches!(rule.expect("should be Ok"), NativeRule::LnOrder));
+    }
+
+    #[test]
+    fn test_native_rule_from_str_git_protected_branch() {
+        let rule = NativeRule::from_str("git_protected_branch");
+        assert!(rule.is_ok());
+        assert!(matches!(
+            rule.expect("should be Ok"),
+            NativeRule::GitProtectedBranch
+        ));
+    }
+
+    #[test]
+    fn test_native_rule_from_str_pager_hint() {
+        let rule = NativeRule::from_str("pager_hint");
+        assert!(rule.is_ok());
+        assert!(matches!(rule.expect("should be Ok"), NativeRule::PagerHint));
+    }
+
+    #[test]
+    fn test_native_rule_from_str_python_module() {
+        let rule = NativeRule::from_str("python_module");
+        assert!(rule.is_ok());
+        assert!(matches!(
+            rule.expect("should be Ok"),
+            NativeRule::PythonModule
+        ));
+    }
+
+    #[test]
+    fn test_native_rule_from_str_curl_follow() {
+        let rule = NativeRule::from_str("curl_follow");
+        assert!(rule.is_ok());
+        assert!(matches!(
+            rule.expect("should be Ok"),
+            NativeRule::CurlFollow
+        ));
+    }
+
+    #[test]
+    fn test_native_rule_from_str_systemctl_unit() {
+        let rule = NativeRule::from_str("systemctl_unit");
+        assert!(rule.is_ok());
+        assert!(matches!(
+            rule.expect("should be Ok"),
+            NativeRule::SystemctlUnit
+        ));
+    }
+
+    #[test]
+    fn test_native_rule_from_str_git_add_remote() {
+        let rule = NativeRule::from_str("git_add_remote");
+        assert!(rule.is_ok());
+        assert!(matches!(
+            rule.expect("should be Ok"),
+            NativeRule::GitAddRemote
+        ));
+    }
+
+    #[test]
+    fn test_native_rule_from_str_git_push_head() {
+        let rule = NativeRule::from_str("git_push_head");
+        assert!(rule.is_ok());
+        assert!(matches!(
+            rule.expect("should be Ok"),
+            NativeRule::GitPushHead
+        ));
+    }
+
     #[test]
     fn test_native_rule_from_str_invalid() {
         let rule = NativeRule::from_str("invalid_rule");
@@ -132,8 +931,7 @@ mod tests {
         );
         let rule = NativeRule::Sudo;
         let result = rule.fix_native(&command);
-        assert!(result.is_some());
-        assert_eq!(result.expect("should be Some"), "sudo some_command");
+        assert_eq!(result, vec!["sudo some_command"]);
     }
 
     #[test]
@@ -144,8 +942,7 @@ mod tests {
         );
         let rule = NativeRule::ToCd;
         let result = rule.fix_native(&command);
-        assert!(result.is_some());
-        assert_eq!(result.expect("should be Some"), "cd /some/directory");
+        assert_eq!(result, vec!["cd /some/directory"]);
     }
 
     #[test]
@@ -156,6 +953,81 @@ mod tests {
         );
         let rule = NativeRule::Sudo;
         let result = rule.fix_native(&command);
-        assert!(result.is_none());
+        assert!(result.is_empty());
+    }
+
+    #[test]
+    fn test_fix_native_sees_through_wrappers() {
+        let command = Command::new(
+            "nice -n 10 cargo build --relase".to_string(),
+            CommandOutput::new(
+                "".to_string(),
+                "error: unexpected argument '--relase' found".to_string(),
+            ),
+        );
+        let result = NativeRule::CargoFlag.fix_native(&command);
+        assert_eq!(result, vec!["nice -n 10 cargo build --release"]);
+
+        let command = Command::new(
+            "env X=1 cs /tmp".to_string(),
+            CommandOutput::new("".to_string(), "".to_string()),
+        );
+        let result = NativeRule::ToCd.fix_native(&command);
+        assert_eq!(result, vec!["env X=1 cd /tmp"]);
+    }
+
+    #[test]
+    fn test_fix_native_sudo_rule_keeps_sudo() {
+        let command = Command::new(
+            "sudo ls /root".to_string(),
+            CommandOutput::new("".to_string(), "permission denied".to_string()),
+        );
+        assert!(NativeRule::Sudo.fix_native(&command).is_empty());
+    }
+
+    #[test]
+    fn test_fix_native_offers_alternatives() {
+        let command = Command::new(
+            "pip install requests".to_string(),
+            CommandOutput::new(
+                "".to_string(),
+                "error: externally-managed-environment".to_string(),
+            ),
+        );
+        let result = NativeRule::PipVenv.fix_native(&command);
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[1], "pip install requests --break-system-packages");
+    }
+
+    #[test]
+    fn test_fix_current_uses_syntactic_rules_only() {
+        let typed = |command: &str| {
+            Command::new(
+                command.to_string(),
+                CommandOutput::new("".to_string(), "".to_string()),
+            )
+        };
+        assert_eq!(
+            NativeRule::ToCd.fix_current(&typed("cs /tmp")),
+            vec!["cd /tmp"]
+        );
+        assert_eq!(
+            NativeRule::PastePrompt.fix_current(&typed("$ npm i")),
+            vec!["npm i"]
+        );
+        assert_eq!(
+            NativeRule::QuoteGlob.fix_current(&typed("find . -name *.rs")),
+            vec!["find . -name '*.rs'"]
+        );
+        assert!(
+            NativeRule::Sudo
+                .fix_current(&typed("cat /etc/shadow"))
+                .is_empty()
+        );
+        assert!(
+            NativeRule::PipVenv
+                .fix_current(&typed("pip install x"))
+                .is_empty()
+        );
     }
 }
@@ -0,0 +1,88 @@
+use crate::fix::structs::Command;
+
+pub const DESCRIPTION: &str = "Refreshes stale apt package lists. When apt fails with \
+    \"Failed to fetch ... 404 Not Found\", runs \"sudo apt update\" before retrying the \
+    original command.";
+
+fn get_apt_binary(command: &Command) -> Option<&str> {
+    command
+        .parts()
+        .iter()
+        .find(|part| *part == "apt" || *part == "apt-get")
+        .map(|part| part.as_str())
+}
+
+pub fn is_match(command: &Command) -> bool {
+    let stderr = command.output().stderr();
+    get_apt_binary(command).is_some()
+        && stderr.contains("Failed to fetch")
+        && stderr.contains("404")
+}
+
+pub fn fix(command: &Command) -> String {
+    let apt = get_apt_binary(command).unwrap_or("apt");
+    format!("sudo {} update && {}", apt, command.command())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fix::structs::{Command, CommandOutput};
+
+    static FETCH_ERROR: &str = "\
+Err:1 http://archive.ubuntu.com/ubuntu jammy-updates/main amd64 curl amd64 7.81.0-1ubuntu1.15
+  404  Not Found [IP: 185.125.190.36 80]
+E: Failed to fetch http://archive.ubuntu.com/ubuntu/pool/main/c/curl/curl_7.81.0-1ubuntu1.15_amd64.deb  404  Not Found [IP: 185.125.190.36 80]
+E: Unable to fetch some archives, maybe run apt-get update or try with --fix-missing?";
+
+    #[test]
+    fn test_is_match_true() {
+        let command = Command::new(
+            "sudo apt install curl".to_string(),
+            CommandOutput::new(String::new(), FETCH_ERROR.to_string()),
+        );
+        assert!(is_match(&command));
+    }
+
+    #[test]
+    fn test_is_match_unknown_package() {
+        let command = Command::new(
+            "sudo apt install curll".to_string(),
+            CommandOutput::new(
+                String::new(),
+                "E: Unable to locate package curll".to_string(),
+            ),
+        );
+        assert!(!is_match(&command));
+    }
+
+    #[test]
+    fn test_is_match_without_apt() {
+        let command = Command::new(
+            "wget http://example.com/file".to_string(),
+            CommandOutput::new(String::new(), FETCH_ERROR.to_string()),
+        );
+        assert!(!is_match(&command));
+    }
+
+    #[test]
+    fn test_fix() {
+        let command = Command::new(
+            "sudo apt install curl".to_string(),
+            CommandOutput::new(String::new(), FETCH_ERROR.to_string()),
+        );
+        assert_eq!(fix(&command), "sudo apt update && sudo apt install curl");
+    }
+
+    #[test]
+    fn test_fix_apt_get() {
+        let command = Command::new(
+            "sudo apt-get install curl".to_string(),
+            CommandOutput::new(String::new(), FETCH_ERROR.to_string()),
+        );
+        assert_eq!(
+            fix(&command),
+            "sudo apt-get update && sudo apt-get install curl"
+        );
+    }
+}
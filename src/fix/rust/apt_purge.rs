@@ -0,0 +1,129 @@
+use crate::fix::structs::Command;
+use crate::misc;
+
+pub const DESCRIPTION: &str = "Finishes an apt removal. When \"apt remove\" leaves config \
+    directories behind, suggests \"apt purge\" instead; when removal runs into dependency \
+    problems or leftover packages, suggests \"sudo apt autoremove\".";
+
+/// dpkg keeps config directories it couldn't empty, which `purge` would have removed.
+static LEFTOVER_CONFIG_PATTERNS: &[&str] = &["not empty so not removed"];
+
+static DEPENDENCY_PATTERNS: &[&str] = &[
+    "have unmet dependencies",
+    "unable to correct problems",
+    "use 'sudo apt autoremove' to remove them",
+    "use 'apt autoremove' to remove them",
+];
+
+enum Problem {
+    LeftoverConfig,
+    Dependencies,
+}
+
+/// Index of the `apt`/`apt-get` binary, skipping a leading `sudo`.
+fn apt_index(command: &Command) -> Option<usize> {
+    let parts = command.parts();
+    let index = usize::from(parts.first().is_some_and(|part| part == "sudo"));
+    let binary = parts.get(index)?;
+    (binary == "apt" || binary == "apt-get").then_some(index)
+}
+
+fn get_problem(command: &Command) -> Option<Problem> {
+    let index = apt_index(command)?;
+    if command
+        .parts()
+        .get(index + 1)
+        .is_none_or(|subcommand| subcommand != "remove")
+    {
+        return None;
+    }
+    let output = command.output().combined().to_lowercase();
+    if LEFTOVER_CONFIG_PATTERNS
+        .iter()
+        .any(|pattern| output.contains(pattern))
+    {
+        Some(Problem::LeftoverConfig)
+    } else if DEPENDENCY_PATTERNS
+        .iter()
+        .any(|pattern| output.contains(pattern))
+    {
+        Some(Problem::Dependencies)
+    } else {
+        None
+    }
+}
+
+pub fn is_match(command: &Command) -> bool {
+    get_problem(command).is_some()
+}
+
+pub fn fix(command: &Command) -> String {
+    let Some(index) = apt_index(command) else {
+        return command.command().to_string();
+    };
+    let binary = &command.parts()[index];
+    match get_problem(command) {
+        Some(Problem::LeftoverConfig) => {
+            misc::replace_argument(command.command(), "remove", "purge")
+        }
+        Some(Problem::Dependencies) => misc::sudo_command(&format!("{binary} autoremove")),
+        None => command.command().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fix::structs::CommandOutput;
+
+    fn apt_command(cmd: &str, stdout: &str, stderr: &str) -> Command {
+        Command::new(
+            cmd.to_string(),
+            CommandOutput::new(stdout.to_string(), stderr.to_string()),
+        )
+    }
+
+    #[test]
+    fn test_leftover_config() {
+        let command = apt_command(
+            "sudo apt remove nginx",
+            "Removing nginx (1.24.0-2) ...",
+            "dpkg: warning: while removing nginx, directory '/etc/nginx' not empty so not removed",
+        );
+        assert!(is_match(&command));
+        assert_eq!(fix(&command), "sudo apt purge nginx");
+    }
+
+    #[test]
+    fn test_dependency_failure() {
+        let command = apt_command(
+            "sudo apt-get remove libfoo",
+            "The following packages have unmet dependencies:\n libbar : Depends: libfoo but it is not going to be installed",
+            "E: Unable to correct problems, you have held broken packages.",
+        );
+        assert!(is_match(&command));
+        assert_eq!(fix(&command), "sudo apt-get autoremove");
+    }
+
+    #[test]
+    fn test_autoremove_hint() {
+        let command = apt_command(
+            "apt remove vim",
+            "The following packages were automatically installed and are no longer required:\n  vim-runtime\n\
+             Use 'sudo apt autoremove' to remove them.",
+            "",
+        );
+        assert!(is_match(&command));
+        assert_eq!(fix(&command), "sudo apt autoremove");
+    }
+
+    #[test]
+    fn test_is_match_other_subcommand() {
+        let command = apt_command(
+            "sudo apt install nginx",
+            "",
+            "E: Unable to correct problems, you have held broken packages.",
+        );
+        assert!(!is_match(&command));
+    }
+}
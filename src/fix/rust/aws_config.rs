@@ -0,0 +1,135 @@
+use crate::fix::structs::Command;
+use std::{env, fs};
+
+pub const DESCRIPTION: &str = "Fixes aws CLI configuration errors. Appends --region on \"You \
+    must specify a region\" and runs \"aws configure\" first on \"Unable to locate \
+    credentials\".";
+
+static REGION_PATTERN: &str = "you must specify a region";
+static CREDENTIALS_PATTERN: &str = "unable to locate credentials";
+static FALLBACK_REGION: &str = "us-east-1";
+
+pub fn is_match(command: &Command) -> bool {
+    if command.parts().is_empty() || command.parts()[0] != "aws" {
+        return false;
+    }
+    let stderr = command.output().stderr().to_lowercase();
+    stderr.contains(REGION_PATTERN) || stderr.contains(CREDENTIALS_PATTERN)
+}
+
+pub fn fix(command: &Command) -> String {
+    fix_with_region(command, &get_default_region())
+}
+
+fn fix_with_region(command: &Command, region: &str) -> String {
+    if command
+        .output()
+        .stderr()
+        .to_lowercase()
+        .contains(CREDENTIALS_PATTERN)
+    {
+        format!("aws configure && {}", command.command())
+    } else {
+        format!("{} --region {}", command.command(), region)
+    }
+}
+
+fn get_default_region() -> String {
+    env::var("AWS_DEFAULT_REGION")
+        .or_else(|_| env::var("AWS_REGION"))
+        .ok()
+        .filter(|region| !region.is_empty())
+        .or_else(|| {
+            dirs::home_dir()
+                .and_then(|home| fs::read_to_string(home.join(".aws/config")).ok())
+                .and_then(|config| parse_config_region(&config))
+        })
+        .unwrap_or_else(|| FALLBACK_REGION.to_string())
+}
+
+fn parse_config_region(config: &str) -> Option<String> {
+    let mut in_default = false;
+    for line in config.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_default = line == "[default]";
+        } else if in_default
+            && let Some((key, value)) = line.split_once('=')
+            && key.trim() == "region"
+        {
+            return Some(value.trim().to_string());
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fix::structs::{Command, CommandOutput};
+
+    #[test]
+    fn test_is_match_region() {
+        let command = Command::new(
+            "aws s3 ls".to_string(),
+            CommandOutput::new(String::new(), "You must specify a region.".to_string()),
+        );
+        assert!(is_match(&command));
+    }
+
+    #[test]
+    fn test_is_match_credentials() {
+        let command = Command::new(
+            "aws s3 ls".to_string(),
+            CommandOutput::new(
+                String::new(),
+                "Unable to locate credentials. You can configure credentials by running \
+                 \"aws configure\"."
+                    .to_string(),
+            ),
+        );
+        assert!(is_match(&command));
+    }
+
+    #[test]
+    fn test_is_match_without_aws() {
+        let command = Command::new(
+            "terraform plan".to_string(),
+            CommandOutput::new(String::new(), "You must specify a region.".to_string()),
+        );
+        assert!(!is_match(&command));
+    }
+
+    #[test]
+    fn test_fix_region() {
+        let command = Command::new(
+            "aws s3 ls".to_string(),
+            CommandOutput::new(String::new(), "You must specify a region.".to_string()),
+        );
+        assert_eq!(
+            fix_with_region(&command, "eu-west-1"),
+            "aws s3 ls --region eu-west-1"
+        );
+    }
+
+    #[test]
+    fn test_fix_credentials() {
+        let command = Command::new(
+            "aws s3 ls".to_string(),
+            CommandOutput::new(String::new(), "Unable to locate credentials.".to_string()),
+        );
+        assert_eq!(fix(&command), "aws configure && aws s3 ls");
+    }
+
+    #[test]
+    fn test_parse_config_region() {
+        let config = "[profile dev]\nregion = us-west-2\n\n[default]\noutput = json\nregion = eu-central-1\n";
+        assert_eq!(
+            parse_config_region(config),
+            Some("eu-central-1".to_string())
+        );
+        assert_eq!(
+            parse_config_region("[profile dev]\nregion = us-west-2\n"),
+            None
+        );
+    }
+}
@@ -0,0 +1,177 @@
+use crate::fix::structs::Command;
+use crate::misc;
+use regex::Regex;
+
+pub const DESCRIPTION: &str = "Corrects misspelled cargo flags. When cargo rejects an unknown \
+    long flag, replaces it with the closest flag the subcommand accepts, keeping the other \
+    arguments.";
+
+const MIN_SIMILARITY: f64 = 0.6;
+
+/// Long flags most cargo subcommands accept.
+static COMMON_FLAGS: &[&str] = &[
+    "--release",
+    "--workspace",
+    "--all-features",
+    "--no-default-features",
+    "--features",
+    "--offline",
+    "--locked",
+    "--frozen",
+    "--verbose",
+    "--quiet",
+    "--target",
+    "--target-dir",
+    "--package",
+    "--all-targets",
+    "--lib",
+    "--bins",
+    "--examples",
+    "--tests",
+    "--benches",
+    "--jobs",
+    "--profile",
+    "--manifest-path",
+    "--message-format",
+    "--color",
+    "--keep-going",
+];
+
+static SUBCOMMAND_FLAGS: &[(&str, &[&str])] = &[
+    ("build", &["--timings"]),
+    ("check", &["--timings"]),
+    ("test", &["--no-run", "--no-fail-fast", "--doc"]),
+    ("bench", &["--no-run", "--no-fail-fast"]),
+    ("run", &["--bin", "--example"]),
+    ("clippy", &["--fix", "--allow-dirty", "--allow-staged"]),
+    ("fmt", &["--all", "--check"]),
+    ("doc", &["--open", "--no-deps", "--document-private-items"]),
+    ("publish", &["--dry-run", "--allow-dirty"]),
+];
+
+fn get_unknown_flag(command: &Command) -> Option<String> {
+    let flag_regex = Regex::new(r"(?:unexpected argument|[Uu]nknown flag:?) '(--[^'=\s]+)")
+        .expect("Hardcoded regex pattern should be valid");
+    flag_regex
+        .captures(command.output().stderr())
+        .map(|caps| caps[1].to_string())
+}
+
+fn known_flags(subcommand: &str) -> impl Iterator<Item = &'static str> {
+    let specific = SUBCOMMAND_FLAGS
+        .iter()
+        .find(|(name, _)| *name == subcommand)
+        .map_or(&[][..], |(_, flags)| flags);
+    COMMON_FLAGS.iter().chain(specific).copied()
+}
+
+fn correct_flag(flag: &str, subcommand: &str) -> Option<&'static str> {
+    known_flags(subcommand)
+        .filter(|known| *known != flag)
+        .map(|known| (misc::string_similarity(flag, known), known))
+        .filter(|(similarity, _)| *similarity >= MIN_SIMILARITY)
+        .max_by(|(a, _), (b, _)| a.total_cmp(b))
+        .map(|(_, known)| known)
+}
+
+fn get_correction(command: &Command) -> Option<(String, &'static str)> {
+    let subcommand = command.parts().get(1)?;
+    let flag = get_unknown_flag(command)?;
+    let corrected = correct_flag(&flag, subcommand)?;
+    Some((flag, corrected))
+}
+
+pub fn is_match(command: &Command) -> bool {
+    command
+        .parts()
+        .first()
+        .is_some_and(|binary| binary == "cargo")
+        && get_correction(command).is_some()
+}
+
+pub fn fix(command: &Command) -> String {
+    let Some((flag, corrected)) = get_correction(command) else {
+        return command.command().to_string();
+    };
+    // Also catches `--flag=value`, keeping the value.
+    let flag_regex = Regex::new(&format!(r"(^|\s){}(=|\s|$)", regex::escape(&flag)))
+        .expect("Escaped flag should be a valid regex");
+    flag_regex
+        .replacen(command.command(), 1, format!("${{1}}{corrected}${{2}}"))
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fix::structs::CommandOutput;
+
+    fn cargo_command(cmd: &str, flag: &str) -> Command {
+        Command::new(
+            cmd.to_string(),
+            CommandOutput::new(
+                String::new(),
+                format!(
+                    "error: unexpected argument '{flag}' found\n\nUsage: cargo build [OPTIONS]\n\nFor more information, try '--help'."
+                ),
+            ),
+        )
+    }
+
+    #[test]
+    fn test_is_match() {
+        assert!(is_match(&cargo_command("cargo build --relese", "--relese")));
+    }
+
+    #[test]
+    fn test_is_match_unknown_flag_message() {
+        let command = Command::new(
+            "cargo build --relese".to_string(),
+            CommandOutput::new(String::new(), "Unknown flag: '--relese'".to_string()),
+        );
+        assert!(is_match(&command));
+    }
+
+    #[test]
+    fn test_is_match_nothing_close() {
+        assert!(!is_match(&cargo_command(
+            "cargo build --banana",
+            "--banana"
+        )));
+        assert!(!is_match(&cargo_command("rustc --relese", "--relese")));
+    }
+
+    #[test]
+    fn test_fix() {
+        assert_eq!(
+            fix(&cargo_command("cargo build --relese", "--relese")),
+            "cargo build --release"
+        );
+    }
+
+    #[test]
+    fn test_fix_keeps_other_arguments() {
+        assert_eq!(
+            fix(&cargo_command(
+                "cargo test -p core --workspce --features serde my_test",
+                "--workspce"
+            )),
+            "cargo test -p core --workspace --features serde my_test"
+        );
+        assert_eq!(
+            fix(&cargo_command(
+                "cargo run --exmaple=demo -- --relese",
+                "--exmaple"
+            )),
+            "cargo run --example=demo -- --relese"
+        );
+    }
+
+    #[test]
+    fn test_fix_subcommand_flags() {
+        assert_eq!(
+            fix(&cargo_command("cargo test --no-rn", "--no-rn")),
+            "cargo test --no-run"
+        );
+    }
+}
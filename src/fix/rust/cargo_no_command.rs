@@ -3,6 +3,9 @@ use crate::fix::structs::Command;
 use crate::misc;
 use regex::Regex;
 
+pub const DESCRIPTION: &str = "Fixes mistyped cargo subcommands. When cargo reports \"no such \
+    command\" and names a similar one, replaces the subcommand with it.";
+
 pub fn is_match(command: &Command) -> bool {
     command.output().stderr().contains("no such command")
         && command
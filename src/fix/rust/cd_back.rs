@@ -0,0 +1,74 @@
+use crate::fix::structs::Command;
+use crate::misc;
+
+pub const DESCRIPTION: &str = "Goes back to the previous directory. When a command fails with \
+    a missing file or project right after a \"cd\", suggests \"cd -\" before rerunning it.";
+
+static PATTERNS: &[&str] = &[
+    "no such file or directory",
+    "not a git repository",
+    "could not find `cargo.toml`",
+    "enoent",
+];
+
+/// Whether the command run just before the failing one moved to another directory.
+fn follows_cd(history: &[String]) -> bool {
+    history.first().is_some_and(|previous| {
+        let parts = misc::split_command(previous);
+        parts.first().is_some_and(|binary| binary == "cd")
+            && parts.get(1).is_none_or(|target| target != "-")
+    })
+}
+
+fn is_match_with(command: &Command, history: &[String]) -> bool {
+    let output = command.output().combined().to_lowercase();
+    command.parts().first().is_some_and(|binary| binary != "cd")
+        && PATTERNS.iter().any(|pattern| output.contains(pattern))
+        && follows_cd(history)
+}
+
+pub fn is_match(command: &Command) -> bool {
+    is_match_with(command, &misc::previous_commands())
+}
+
+pub fn fix(command: &Command) -> String {
+    format!("cd - && {}", command.command())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(entries: &[&str]) -> Vec<String> {
+        entries.iter().map(|entry| entry.to_string()).collect()
+    }
+
+    #[test]
+    fn test_is_match_after_cd() {
+        let command = Command::failed(
+            "git status",
+            "fatal: not a git repository (or any of the parent directories): .git",
+        );
+        assert!(is_match_with(&command, &history(&["cd /tmp", "git log"])));
+    }
+
+    #[test]
+    fn test_is_match_without_recent_cd() {
+        let command = Command::failed("cat notes.txt", "cat: notes.txt: No such file or directory");
+        assert!(!is_match_with(&command, &history(&["ls", "cd /tmp"])));
+        assert!(!is_match_with(&command, &history(&["cd -"])));
+        assert!(!is_match_with(&command, &[]));
+    }
+
+    #[test]
+    fn test_is_match_unrelated_error() {
+        let command = Command::failed("make", "make: *** No rule to make target 'all'.  Stop.");
+        assert!(!is_match_with(&command, &history(&["cd build"])));
+    }
+
+    #[test]
+    fn test_fix() {
+        let command = Command::failed("cargo build", "error: could not find `Cargo.toml`");
+        assert_eq!(fix(&command), "cd - && cargo build");
+    }
+}
@@ -0,0 +1,182 @@
+use crate::fix::structs::Command;
+use crate::misc;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+pub const DESCRIPTION: &str = "Fixes typos in \"cd\" paths. When cd reports \"No such file or \
+    directory\", replaces the first missing path component with the closest existing \
+    directory name.";
+
+const MIN_SIMILARITY: f64 = 0.6;
+
+static PATTERNS: &[&str] = &["no such file or directory", "does not exist"];
+
+fn closest_sibling(dir: &Path, name: &str) -> Option<String> {
+    fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .map(|sibling| (misc::string_similarity(name, &sibling), sibling))
+        .filter(|(similarity, _)| *similarity >= MIN_SIMILARITY)
+        .max_by(|(a, _), (b, _)| a.total_cmp(b))
+        .map(|(_, sibling)| sibling)
+}
+
+/// Replaces the first missing component of `path` with the closest existing directory name.
+/// Relative paths are resolved against `base`, but the result stays relative.
+fn correct_path(path: &str, base: &Path) -> Option<String> {
+    let mut resolved = base.to_path_buf();
+    let mut corrected = PathBuf::new();
+    let mut components = Path::new(path).components();
+    while let Some(component) = components.next() {
+        match component {
+            Component::Normal(name) => {
+                let name = name.to_string_lossy();
+                if resolved.join(name.as_ref()).is_dir() {
+                    resolved.push(name.as_ref());
+                    corrected.push(name.as_ref());
+                    continue;
+                }
+                corrected.push(closest_sibling(&resolved, &name)?);
+                corrected.extend(components);
+                return Some(corrected.to_string_lossy().into_owned());
+            }
+            other => {
+                resolved.push(other);
+                corrected.push(other);
+            }
+        }
+    }
+    None
+}
+
+fn get_correction(command: &Command) -> Option<String> {
+    let target = command.parts().get(1)?;
+    if let Some(rest) = target.strip_prefix("~/") {
+        let corrected = correct_path(rest, &dirs::home_dir()?)?;
+        return Some(format!("~/{}", shell_words::quote(&corrected)));
+    }
+    let corrected = correct_path(&misc::expand_path(target), command.cwd())?;
+    Some(shell_words::quote(&corrected).into_owned())
+}
+
+pub fn is_match(command: &Command) -> bool {
+    if command.parts().len() != 2 || command.parts()[0] != "cd" {
+        return false;
+    }
+    let output = command.output().combined().to_lowercase();
+    PATTERNS.iter().any(|pattern| output.contains(pattern)) && get_correction(command).is_some()
+}
+
+pub fn fix(command: &Command) -> String {
+    match get_correction(command) {
+        Some(corrected) => format!("cd {corrected}"),
+        None => command.command().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fix::structs::{Command, CommandOutput};
+
+    fn create_tree() -> tempfile::TempDir {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        fs::create_dir_all(temp_dir.path().join("usr/local/bin")).expect("Failed to create dirs");
+        fs::create_dir_all(temp_dir.path().join("usr/lib")).expect("Failed to create dirs");
+        temp_dir
+    }
+
+    fn cd_command(path: &str) -> Command {
+        Command::new(
+            format!("cd {path}"),
+            CommandOutput::new(
+                String::new(),
+                format!("bash: cd: {path}: No such file or directory"),
+            ),
+        )
+    }
+
+    #[test]
+    fn test_correct_path_absolute() {
+        let tree = create_tree();
+        let root = tree.path().display();
+        assert_eq!(
+            correct_path(&format!("{root}/usr/loca/bin"), Path::new("/")),
+            Some(format!("{root}/usr/local/bin"))
+        );
+    }
+
+    #[test]
+    fn test_correct_path_relative() {
+        let tree = create_tree();
+        assert_eq!(
+            correct_path("usr/loca/bin", tree.path()),
+            Some("usr/local/bin".to_string())
+        );
+        assert_eq!(
+            correct_path("./usr/lbi", tree.path()),
+            Some("./usr/lib".to_string())
+        );
+    }
+
+    #[test]
+    fn test_correct_path_stops_at_first_correction() {
+        let tree = create_tree();
+        assert_eq!(
+            correct_path("usr/loca/bni", tree.path()),
+            Some("usr/local/bni".to_string())
+        );
+    }
+
+    #[test]
+    fn test_correct_path_no_close_sibling() {
+        let tree = create_tree();
+        assert_eq!(correct_path("usr/something", tree.path()), None);
+        assert_eq!(correct_path("usr/local", tree.path()), None);
+    }
+
+    #[test]
+    fn test_is_match() {
+        let tree = create_tree();
+        let command = cd_command(&format!("{}/usr/loca/bin", tree.path().display()));
+        assert!(is_match(&command));
+    }
+
+    #[test]
+    fn test_is_match_without_error() {
+        let tree = create_tree();
+        let command = Command::new(
+            format!("cd {}/usr/loca/bin", tree.path().display()),
+            CommandOutput::new(String::new(), String::new()),
+        );
+        assert!(!is_match(&command));
+    }
+
+    #[test]
+    fn test_is_match_not_cd() {
+        let tree = create_tree();
+        let command = Command::new(
+            format!("ls {}/usr/loca/bin", tree.path().display()),
+            CommandOutput::new(String::new(), "No such file or directory".to_string()),
+        );
+        assert!(!is_match(&command));
+    }
+
+    #[test]
+    fn test_fix_relative_to_command_cwd() {
+        let tree = create_tree();
+        let command = cd_command("usr/loca/bin").with_cwd(tree.path().to_path_buf());
+        assert!(is_match(&command));
+        assert_eq!(fix(&command), "cd usr/local/bin");
+    }
+
+    #[test]
+    fn test_fix() {
+        let tree = create_tree();
+        let root = tree.path().display();
+        let command = cd_command(&format!("{root}/usr/loca/bin"));
+        assert_eq!(fix(&command), format!("cd {root}/usr/local/bin"));
+    }
+}
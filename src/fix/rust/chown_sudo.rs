@@ -0,0 +1,69 @@
+use crate::fix::structs::Command;
+use crate::misc;
+
+pub const DESCRIPTION: &str = "Reruns chown, chmod or chgrp with sudo. When one of them \
+    reports \"Operation not permitted\" for a file, prefixes the command with sudo.";
+
+static BINARIES: &[&str] = &["chown", "chmod", "chgrp"];
+
+/// GNU coreutils print `changing ownership of 'x': Operation not permitted`, BSD prints
+/// `x: Operation not permitted`; both end the same way.
+const PATTERN: &str = ": Operation not permitted";
+
+pub fn is_match(command: &Command) -> bool {
+    command
+        .parts()
+        .first()
+        .is_some_and(|binary| BINARIES.contains(&binary.as_str()))
+        && command
+            .output()
+            .stderr()
+            .lines()
+            .any(|line| line.starts_with(command.parts()[0].as_str()) && line.contains(PATTERN))
+}
+
+pub fn fix(command: &Command) -> String {
+    misc::sudo_command(command.command())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chown_not_permitted() {
+        let command = Command::failed(
+            "chown www-data:www-data /var/www/index.html",
+            "chown: changing ownership of '/var/www/index.html': Operation not permitted",
+        );
+        assert!(is_match(&command));
+        assert_eq!(
+            fix(&command),
+            "sudo chown www-data:www-data /var/www/index.html"
+        );
+    }
+
+    #[test]
+    fn test_chmod_recursive_bsd() {
+        let command = Command::failed(
+            "chmod -R 755 /usr/local/share",
+            "chmod: /usr/local/share/man: Operation not permitted\nchmod: /usr/local/share/doc: Operation not permitted",
+        );
+        assert!(is_match(&command));
+        assert_eq!(fix(&command), "sudo chmod -R 755 /usr/local/share");
+    }
+
+    #[test]
+    fn test_no_match() {
+        let command = Command::failed(
+            "chmod 755 missing",
+            "chmod: cannot access 'missing': No such file or directory",
+        );
+        assert!(!is_match(&command));
+        let command = Command::failed(
+            "rm /etc/x",
+            "rm: cannot remove '/etc/x': Operation not permitted",
+        );
+        assert!(!is_match(&command));
+    }
+}
@@ -0,0 +1,116 @@
+use crate::fix::structs::Command;
+
+pub const DESCRIPTION: &str = "Logs in to CLIs that report a missing session. Detects \
+    login-required messages from heroku, railway, gh, aws and gcloud and prepends the \
+    matching login command.";
+
+static LOGIN_COMMANDS: &[(&str, &str)] = &[
+    ("heroku", "heroku login"),
+    ("railway", "railway login"),
+    ("gh", "gh auth login"),
+    ("aws", "aws sso login"),
+    ("gcloud", "gcloud auth login"),
+];
+
+static PATTERNS: &[&str] = &[
+    "not logged in",
+    "please run: heroku login",
+    "you are not logged into any github hosts",
+    "to get started with github cli, please run:  gh auth login",
+    "the sso session associated with this profile has expired",
+    "error loading sso token",
+    "you do not currently have an active account selected",
+    "please log in",
+    "please login",
+    "login required",
+];
+
+fn get_login_command(tool: &str) -> Option<&'static str> {
+    LOGIN_COMMANDS
+        .iter()
+        .find(|(name, _)| *name == tool)
+        .map(|(_, login)| *login)
+}
+
+pub fn is_match(command: &Command) -> bool {
+    if command.parts().is_empty() || get_login_command(&command.parts()[0]).is_none() {
+        return false;
+    }
+
+    let output = command.output().combined().to_lowercase();
+    PATTERNS.iter().any(|pattern| output.contains(pattern))
+}
+
+pub fn fix(command: &Command) -> String {
+    let login = get_login_command(&command.parts()[0]).expect("is_match guarantees a known tool");
+    format!("{} && {}", login, command.command())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fix::structs::{Command, CommandOutput};
+
+    #[test]
+    fn test_is_match_heroku() {
+        let command = Command::new(
+            "heroku ps".to_string(),
+            CommandOutput::new(
+                String::new(),
+                " ›   Error: not logged in\n ›   please run: heroku login".to_string(),
+            ),
+        );
+        assert!(is_match(&command));
+    }
+
+    #[test]
+    fn test_is_match_gh() {
+        let command = Command::new(
+            "gh pr list".to_string(),
+            CommandOutput::new(
+                String::new(),
+                "To get started with GitHub CLI, please run:  gh auth login".to_string(),
+            ),
+        );
+        assert!(is_match(&command));
+    }
+
+    #[test]
+    fn test_is_match_unknown_tool() {
+        let command = Command::new(
+            "docker ps".to_string(),
+            CommandOutput::new(String::new(), "Error: not logged in".to_string()),
+        );
+        assert!(!is_match(&command));
+    }
+
+    #[test]
+    fn test_is_match_without_login_error() {
+        let command = Command::new(
+            "heroku ps".to_string(),
+            CommandOutput::new(String::new(), "Error: app not found".to_string()),
+        );
+        assert!(!is_match(&command));
+    }
+
+    #[test]
+    fn test_fix_heroku() {
+        let command = Command::new(
+            "heroku ps".to_string(),
+            CommandOutput::new(String::new(), "Error: not logged in".to_string()),
+        );
+        assert_eq!(fix(&command), "heroku login && heroku ps");
+    }
+
+    #[test]
+    fn test_fix_gh() {
+        let command = Command::new(
+            "gh pr list".to_string(),
+            CommandOutput::new(
+                String::new(),
+                "You are not logged into any GitHub hosts.".to_string(),
+            ),
+        );
+        assert_eq!(fix(&command), "gh auth login && gh pr list");
+    }
+}
@@ -0,0 +1,139 @@
+use crate::fix::structs::Command;
+use regex::Regex;
+
+pub const DESCRIPTION: &str = "Follows HTTP redirects. When curl prints a redirect response \
+    (such as 301 Moved Permanently) instead of following it, adds -L; when wget keeps being \
+    redirected from an http:// URL, switches the URL to https://.";
+
+/// What a redirect looks like to a client that didn't follow it: the status line with `-i`/`-I`,
+/// or the stock body servers send with it.
+static REDIRECT_PATTERNS: &[&str] = &[
+    "moved permanently",
+    "302 found",
+    "<title>301",
+    "<title>302",
+    "307 temporary redirect",
+    "308 permanent redirect",
+    "redirections exceeded",
+];
+
+static FOLLOW_FLAGS: &[&str] = &["-L", "--location", "--location-trusted"];
+
+fn is_redirect(command: &Command) -> bool {
+    let output = command.output().combined().to_lowercase();
+    REDIRECT_PATTERNS
+        .iter()
+        .any(|pattern| output.contains(pattern))
+}
+
+/// Whether curl was already told to follow redirects, including as part of a cluster (`-sL`).
+fn follows(command: &Command) -> bool {
+    command.parts().iter().skip(1).any(|part| {
+        FOLLOW_FLAGS.contains(&part.as_str())
+            || (part.starts_with('-') && !part.starts_with("--") && part.contains('L'))
+    })
+}
+
+fn get_http_url(command: &Command) -> Option<&str> {
+    command
+        .parts()
+        .iter()
+        .skip(1)
+        .find(|part| part.starts_with("http://"))
+        .map(String::as_str)
+}
+
+pub fn is_match(command: &Command) -> bool {
+    match command.parts().first().map(String::as_str) {
+        Some("curl") => !follows(command) && is_redirect(command),
+        Some("wget") => get_http_url(command).is_some() && is_redirect(command),
+        _ => false,
+    }
+}
+
+pub fn fix(command: &Command) -> String {
+    let script = command.command();
+    match command.parts().first().map(String::as_str) {
+        Some("curl") => {
+            let curl_regex =
+                Regex::new(r"^curl\b").expect("Hardcoded regex pattern should be valid");
+            curl_regex.replace(script, "curl -L").into_owned()
+        }
+        Some("wget") => match get_http_url(command) {
+            Some(url) => script.replacen(url, &url.replacen("http://", "https://", 1), 1),
+            None => script.to_string(),
+        },
+        _ => script.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fix::structs::CommandOutput;
+
+    fn http_command(cmd: &str, stdout: &str, stderr: &str) -> Command {
+        Command::new(
+            cmd.to_string(),
+            CommandOutput::new(stdout.to_string(), stderr.to_string()),
+        )
+    }
+
+    #[test]
+    fn test_curl_redirect_body() {
+        let command = http_command(
+            "curl http://example.com/install.sh",
+            "<html>\n<head><title>301 Moved Permanently</title></head>\n<body>\n<center><h1>301 Moved Permanently</h1></center>\n</body>\n</html>",
+            "",
+        );
+        assert!(is_match(&command));
+        assert_eq!(fix(&command), "curl -L http://example.com/install.sh");
+    }
+
+    #[test]
+    fn test_curl_redirect_headers() {
+        let command = http_command(
+            "curl -sI http://github.com",
+            "HTTP/1.1 301 Moved Permanently\r\nContent-Length: 0\r\nLocation: https://github.com/\r\n",
+            "",
+        );
+        assert!(is_match(&command));
+        assert_eq!(fix(&command), "curl -L -sI http://github.com");
+    }
+
+    #[test]
+    fn test_wget_switches_to_https() {
+        let command = http_command(
+            "wget http://example.com/file.tar.gz",
+            "",
+            "HTTP request sent, awaiting response... 301 Moved Permanently\n20 redirections exceeded.",
+        );
+        assert!(is_match(&command));
+        assert_eq!(fix(&command), "wget https://example.com/file.tar.gz");
+    }
+
+    #[test]
+    fn test_no_match() {
+        let redirect = "<title>301 Moved Permanently</title>";
+        assert!(!is_match(&http_command(
+            "curl -sL http://example.com",
+            redirect,
+            ""
+        )));
+        assert!(!is_match(&http_command(
+            "curl --location http://example.com",
+            redirect,
+            ""
+        )));
+        assert!(!is_match(&http_command(
+            "curl http://example.com",
+            "<h1>Hello</h1>",
+            ""
+        )));
+        assert!(!is_match(&http_command(
+            "wget https://example.com",
+            "",
+            redirect
+        )));
+    }
+}
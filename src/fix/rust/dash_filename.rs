@@ -0,0 +1,137 @@
+use crate::fix::structs::Command;
+use crate::misc;
+use regex::Regex;
+
+pub const DESCRIPTION: &str = "Inserts \"--\" before a filename that starts with a dash. When \
+    a command fails with \"invalid option\" or \"unrecognized option\" and an argument \
+    starting with \"-\" is the culprit, retries with \"--\" so the argument is treated as a \
+    filename.";
+
+enum Offender {
+    Short(char),
+    Long(String),
+}
+
+fn get_offender(stderr: &str) -> Option<Offender> {
+    let short = Regex::new(r"invalid option -- '?([^'\s])'?")
+        .expect("Hardcoded regex pattern should be valid");
+    let long = Regex::new(r"(?:unrecognized|invalid) option '(-[^'=]+)")
+        .expect("Hardcoded regex pattern should be valid");
+    if let Some(caps) = short.captures(stderr) {
+        caps[1].chars().next().map(Offender::Short)
+    } else {
+        long.captures(stderr)
+            .map(|caps| Offender::Long(caps[1].to_string()))
+    }
+}
+
+fn find_dash_filename(command: &Command) -> Option<&str> {
+    let offender = get_offender(command.output().stderr());
+    command
+        .parts()
+        .iter()
+        .skip(1)
+        .take_while(|part| *part != "--")
+        .filter(|part| part.starts_with('-') && part.len() > 1)
+        .find(|part| {
+            command.cwd().join(part.as_str()).exists()
+                || match &offender {
+                    Some(Offender::Short(option)) => {
+                        !part.starts_with("--") && part[1..].contains(*option)
+                    }
+                    Some(Offender::Long(option)) => part.split('=').next() == Some(option.as_str()),
+                    None => false,
+                }
+        })
+        .map(|part| part.as_str())
+}
+
+pub fn is_match(command: &Command) -> bool {
+    let stderr = command.output().stderr();
+    (stderr.contains("invalid option") || stderr.contains("unrecognized option"))
+        && find_dash_filename(command).is_some()
+}
+
+pub fn fix(command: &Command) -> String {
+    match find_dash_filename(command) {
+        Some(filename) => {
+            misc::replace_argument(command.command(), filename, &format!("-- {filename}"))
+        }
+        None => command.command().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fix::structs::{Command, CommandOutput};
+
+    #[test]
+    fn test_is_match_short_option() {
+        let command = Command::new(
+            "rm -weird".to_string(),
+            CommandOutput::new(
+                String::new(),
+                "rm: invalid option -- 'w'\nTry 'rm --help' for more information.".to_string(),
+            ),
+        );
+        assert!(is_match(&command));
+    }
+
+    #[test]
+    fn test_is_match_long_option() {
+        let command = Command::new(
+            "cat --weird-name".to_string(),
+            CommandOutput::new(
+                String::new(),
+                "cat: unrecognized option '--weird-name'".to_string(),
+            ),
+        );
+        assert!(is_match(&command));
+    }
+
+    #[test]
+    fn test_is_match_without_dash_argument() {
+        let command = Command::new(
+            "rm file".to_string(),
+            CommandOutput::new(String::new(), "rm: invalid option -- 'w'".to_string()),
+        );
+        assert!(!is_match(&command));
+    }
+
+    #[test]
+    fn test_is_match_after_double_dash() {
+        let command = Command::new(
+            "rm -- -weird".to_string(),
+            CommandOutput::new(String::new(), "rm: invalid option -- 'w'".to_string()),
+        );
+        assert!(!is_match(&command));
+    }
+
+    #[test]
+    fn test_is_match_without_error() {
+        let command = Command::new(
+            "rm -weird".to_string(),
+            CommandOutput::new(String::new(), String::new()),
+        );
+        assert!(!is_match(&command));
+    }
+
+    #[test]
+    fn test_fix() {
+        let command = Command::new(
+            "rm -weird".to_string(),
+            CommandOutput::new(String::new(), "rm: invalid option -- 'w'".to_string()),
+        );
+        assert_eq!(fix(&command), "rm -- -weird");
+    }
+
+    #[test]
+    fn test_fix_keeps_valid_options() {
+        let command = Command::new(
+            "rm -f -weird".to_string(),
+            CommandOutput::new(String::new(), "rm: invalid option -- 'w'".to_string()),
+        );
+        assert_eq!(fix(&command), "rm -f -- -weird");
+    }
+}
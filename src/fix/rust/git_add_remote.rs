@@ -0,0 +1,147 @@
+use super::git_remotes;
+use crate::fix::structs::Command;
+use crate::misc;
+use regex::Regex;
+
+pub const DESCRIPTION: &str = "Handles a push, pull or fetch without the remote it needs. \
+    When the repository has no remotes, suggests a \"git remote add origin\" template to \
+    fill in; when it has a single remote with another name, reruns the command against that \
+    remote.";
+
+static SUBCOMMANDS: &[&str] = &["push", "pull", "fetch"];
+
+const NO_DESTINATION: &str = "No configured push destination";
+
+/// Left quoted so the placeholder can't be mistaken for a redirect; fixes with a placeholder
+/// are always confirmed first, whatever the mode.
+const REMOTE_TEMPLATE: &str = "git remote add origin \"<url>\"";
+
+/// The remote the command asked for, from `'origin' does not appear to be a git repository`.
+fn get_missing_remote(command: &Command) -> Option<String> {
+    let missing_regex = Regex::new(r"'([^']+)' does not appear to be a git repository")
+        .expect("Hardcoded regex pattern should be valid");
+    missing_regex
+        .captures(command.output().stderr())
+        .map(|caps| caps[1].to_string())
+}
+
+fn is_missing_remote(command: &Command) -> bool {
+    let parts = command.parts();
+    parts.first().is_some_and(|binary| binary == "git")
+        && parts
+            .get(1)
+            .is_some_and(|subcommand| SUBCOMMANDS.contains(&subcommand.as_str()))
+        && (command.output().stderr().contains(NO_DESTINATION)
+            || get_missing_remote(command).is_some())
+}
+
+/// Without remotes, a template for adding one; with a single remote of another name, the
+/// command pointed at it instead.
+fn fix_with(command: &Command, remotes: &[String]) -> Option<String> {
+    let [remote] = remotes else {
+        return remotes.is_empty().then(|| REMOTE_TEMPLATE.to_string());
+    };
+    match get_missing_remote(command) {
+        Some(missing) if missing == *remote => None,
+        Some(missing) if command.parts().contains(&missing) => {
+            Some(misc::replace_argument(command.command(), &missing, remote))
+        }
+        _ => {
+            let subcommand = &command.parts()[1];
+            let retargeted = Regex::new(&format!(r"\b{}\b", regex::escape(subcommand)))
+                .expect("Escaped regex pattern should be valid")
+                .replace(command.command(), format!("{subcommand} {remote}"));
+            Some(retargeted.into_owned())
+        }
+    }
+}
+
+pub fn is_match(command: &Command) -> bool {
+    is_missing_remote(command)
+        && fix_with(command, &git_remotes::get_remotes(command.cwd())).is_some()
+}
+
+pub fn fix(command: &Command) -> String {
+    fix_with(command, &git_remotes::get_remotes(command.cwd()))
+        .unwrap_or_else(|| command.command().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOT_A_REPOSITORY: &str = "fatal: 'origin' does not appear to be a git repository
+fatal: Could not read from remote repository.
+
+Please make sure you have the correct access rights
+and the repository exists.";
+
+    const NO_PUSH_DESTINATION: &str = "fatal: No configured push destination.
+Either specify the URL from the command-line or configure a remote repository using
+
+    git remote add <name> <url>
+
+and then push using the remote name
+
+    git push <name>";
+
+    fn remotes(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_is_missing_remote() {
+        assert!(is_missing_remote(&Command::failed(
+            "git push",
+            NO_PUSH_DESTINATION
+        )));
+        assert!(is_missing_remote(&Command::failed(
+            "git pull origin main",
+            NOT_A_REPOSITORY
+        )));
+        assert!(!is_missing_remote(&Command::failed(
+            "git push",
+            "Everything up-to-date"
+        )));
+        assert!(!is_missing_remote(&Command::failed(
+            "git clone origin",
+            NOT_A_REPOSITORY
+        )));
+    }
+
+    #[test]
+    fn test_no_remotes_gives_template() {
+        let command = Command::failed("git push", NO_PUSH_DESTINATION);
+        assert_eq!(
+            fix_with(&command, &[]).as_deref(),
+            Some("git remote add origin \"<url>\"")
+        );
+    }
+
+    #[test]
+    fn test_single_remote_retargets_push() {
+        let upstream = remotes(&["upstream"]);
+        let command = Command::failed("git push origin main", NOT_A_REPOSITORY);
+        assert_eq!(
+            fix_with(&command, &upstream).as_deref(),
+            Some("git push upstream main")
+        );
+        let command = Command::failed("git push --tags", NO_PUSH_DESTINATION);
+        assert_eq!(
+            fix_with(&command, &upstream).as_deref(),
+            Some("git push upstream --tags")
+        );
+        let command = Command::failed("git push", NOT_A_REPOSITORY);
+        assert_eq!(
+            fix_with(&command, &upstream).as_deref(),
+            Some("git push upstream")
+        );
+    }
+
+    #[test]
+    fn test_no_fix_when_ambiguous() {
+        let command = Command::failed("git push origin main", NOT_A_REPOSITORY);
+        assert_eq!(fix_with(&command, &remotes(&["upstream", "fork"])), None);
+        assert_eq!(fix_with(&command, &remotes(&["origin"])), None);
+    }
+}
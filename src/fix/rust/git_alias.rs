@@ -0,0 +1,108 @@
+//! Resolves user-defined git aliases (`git co` → `git checkout`) for the `git_*` rules.
+use crate::fix::structs::Command;
+use std::collections::HashMap;
+use std::process;
+use std::sync::OnceLock;
+
+/// Parses the output of `git config --get-regexp ^alias\.`, one `alias.<name> <expansion>`
+/// per line.
+pub fn parse_git_aliases(raw: &str) -> HashMap<String, String> {
+    raw.lines()
+        .filter_map(|line| line.strip_prefix("alias."))
+        .filter_map(|line| line.split_once(char::is_whitespace))
+        .map(|(name, expansion)| (name.to_string(), expansion.trim().to_string()))
+        .collect()
+}
+
+fn get_git_aliases() -> &'static HashMap<String, String> {
+    static ALIASES: OnceLock<HashMap<String, String>> = OnceLock::new();
+    ALIASES.get_or_init(|| {
+        process::Command::new("git")
+            .args(["config", "--get-regexp", r"^alias\."])
+            .output()
+            .map(|output| parse_git_aliases(&String::from_utf8_lossy(&output.stdout)))
+            .unwrap_or_default()
+    })
+}
+
+/// Returns the command with its git subcommand alias expanded, or `None` if there is nothing
+/// to expand. Shell aliases (`!...`) are left alone since they aren't git subcommands.
+pub fn expand_git_alias(command: &Command, aliases: &HashMap<String, String>) -> Option<Command> {
+    let parts = command.parts();
+    if parts.len() < 2 || parts[0] != "git" {
+        return None;
+    }
+    let expansion = aliases
+        .get(&parts[1])
+        .filter(|expansion| !expansion.starts_with('!'))?;
+    let rest = shell_words::join(&parts[2..]);
+    let expanded = if rest.is_empty() {
+        format!("git {expansion}")
+    } else {
+        format!("git {expansion} {rest}")
+    };
+    Some(Command::new(expanded, command.output().clone()).with_cwd(command.cwd().to_path_buf()))
+}
+
+pub fn resolve(command: &Command) -> Option<Command> {
+    if command.parts().first().is_none_or(|binary| binary != "git") {
+        return None;
+    }
+    expand_git_alias(command, get_git_aliases())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fix::structs::CommandOutput;
+
+    fn fixture_aliases() -> HashMap<String, String> {
+        parse_git_aliases(
+            "alias.co checkout\nalias.st status -sb\nalias.up !git pull --rebase\nuser.name someone\n",
+        )
+    }
+
+    fn git_command(command: &str) -> Command {
+        Command::new(
+            command.to_string(),
+            CommandOutput::new(String::new(), "error".to_string()),
+        )
+    }
+
+    #[test]
+    fn test_parse_git_aliases() {
+        let aliases = fixture_aliases();
+        assert_eq!(aliases.len(), 3);
+        assert_eq!(aliases["co"], "checkout");
+        assert_eq!(aliases["st"], "status -sb");
+        assert_eq!(aliases["up"], "!git pull --rebase");
+    }
+
+    #[test]
+    fn test_expand_git_alias() {
+        let expanded = expand_git_alias(&git_command("git co -b feature"), &fixture_aliases())
+            .expect("Alias should expand");
+        assert_eq!(expanded.command(), "git checkout -b feature");
+        assert_eq!(expanded.parts()[1], "checkout");
+        assert_eq!(expanded.output().stderr(), "error");
+    }
+
+    #[test]
+    fn test_expand_git_alias_with_arguments_in_alias() {
+        let expanded = expand_git_alias(&git_command("git st"), &fixture_aliases())
+            .expect("Alias should expand");
+        assert_eq!(expanded.command(), "git status -sb");
+    }
+
+    #[test]
+    fn test_expand_git_alias_skips_shell_aliases() {
+        assert!(expand_git_alias(&git_command("git up"), &fixture_aliases()).is_none());
+    }
+
+    #[test]
+    fn test_expand_git_alias_not_an_alias() {
+        assert!(expand_git_alias(&git_command("git checkout main"), &fixture_aliases()).is_none());
+        assert!(expand_git_alias(&git_command("hg co"), &fixture_aliases()).is_none());
+        assert!(expand_git_alias(&git_command("git"), &fixture_aliases()).is_none());
+    }
+}
@@ -0,0 +1,90 @@
+//! Names and creates branches for the rules that move work off the current HEAD before pushing.
+use std::path::Path;
+use std::process;
+
+const FALLBACK_BRANCH: &str = "feature";
+
+/// Keeps generated names readable; longer subjects are cut at a word boundary.
+const MAX_BRANCH_LENGTH: usize = 40;
+
+/// Turns a commit subject into a branch name: `Fix login on Safari!` becomes
+/// `fix-login-on-safari`.
+pub fn slugify(subject: &str) -> String {
+    let mut slug = String::new();
+    for word in subject
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+    {
+        let extra = word.len() + usize::from(!slug.is_empty());
+        if !slug.is_empty() && slug.len() + extra > MAX_BRANCH_LENGTH {
+            break;
+        }
+        if !slug.is_empty() {
+            slug.push('-');
+        }
+        slug.push_str(&word.to_ascii_lowercase());
+    }
+    slug.truncate(MAX_BRANCH_LENGTH);
+    slug
+}
+
+/// The branch to move work to, named after the last commit subject when it slugifies to
+/// anything.
+pub fn branch_name(subject: Option<&str>) -> String {
+    subject
+        .map(slugify)
+        .filter(|branch| !branch.is_empty())
+        .unwrap_or_else(|| FALLBACK_BRANCH.to_string())
+}
+
+pub fn get_last_subject(cwd: &Path) -> Option<String> {
+    process::Command::new("git")
+        .args(["log", "-1", "--format=%s"])
+        .current_dir(cwd)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Creates `branch` at HEAD and pushes it to `remote` with upstream tracking.
+pub fn switch_and_push(branch: &str, remote: &str) -> String {
+    format!(
+        "git switch -c {branch} && git push -u {} {branch}",
+        shell_words::quote(remote)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("Fix login on Safari!"), "fix-login-on-safari");
+        assert_eq!(
+            slugify("  feat(api): add /v2 users  "),
+            "feat-api-add-v2-users"
+        );
+        assert_eq!(
+            slugify("Refactor the configuration loader so that project files override globals"),
+            "refactor-the-configuration-loader-so"
+        );
+        assert_eq!(slugify("!!!"), "");
+    }
+
+    #[test]
+    fn test_branch_name() {
+        assert_eq!(branch_name(Some("Add dark mode")), "add-dark-mode");
+        assert_eq!(branch_name(Some("!!!")), "feature");
+        assert_eq!(branch_name(None), "feature");
+    }
+
+    #[test]
+    fn test_switch_and_push() {
+        assert_eq!(
+            switch_and_push("add-dark-mode", "my remote"),
+            "git switch -c add-dark-mode && git push -u 'my remote' add-dark-mode"
+        );
+    }
+}
@@ -0,0 +1,161 @@
+use crate::fix::structs::Command;
+use crate::misc;
+use regex::Regex;
+use std::fs;
+use std::path::Path;
+
+pub const DESCRIPTION: &str = "Clones into a fresh directory when the destination exists. \
+    When git clone fails with \"destination path ... already exists\", suggests a suffixed \
+    directory such as \"repo-1\", or removes the directory first if it only holds OS \
+    leftovers like .DS_Store.";
+
+/// Files an OS leaves behind in an otherwise empty directory.
+static LEFTOVERS: &[&str] = &[".DS_Store", "Thumbs.db", "desktop.ini"];
+
+fn get_destination(command: &Command) -> Option<String> {
+    let destination_regex =
+        Regex::new(r"destination path '([^']+)' already exists and is not an empty directory")
+            .expect("Hardcoded regex pattern should be valid");
+    destination_regex
+        .captures(command.output().stderr())
+        .map(|caps| caps[1].to_string())
+}
+
+fn is_leftover(dir: &Path) -> bool {
+    fs::read_dir(dir).is_ok_and(|entries| {
+        entries.filter_map(|entry| entry.ok()).all(|entry| {
+            LEFTOVERS
+                .iter()
+                .any(|leftover| entry.file_name() == *leftover)
+        })
+    })
+}
+
+/// First `<destination>-N` that doesn't exist yet.
+fn fresh_destination(destination: &str, base: &Path) -> String {
+    (1..)
+        .map(|suffix| format!("{destination}-{suffix}"))
+        .find(|candidate| !base.join(candidate).exists())
+        .expect("Some suffix is always free")
+}
+
+pub fn is_match(command: &Command) -> bool {
+    command.parts().len() >= 3
+        && command.parts()[0] == "git"
+        && command.parts()[1] == "clone"
+        && get_destination(command).is_some()
+}
+
+pub fn fix(command: &Command) -> String {
+    let Some(destination) = get_destination(command) else {
+        return command.command().to_string();
+    };
+    if is_leftover(&command.cwd().join(&destination)) {
+        return format!(
+            "rm -rf {} && {}",
+            shell_words::quote(&destination),
+            command.command()
+        );
+    }
+    let fresh = shell_words::quote(&fresh_destination(&destination, command.cwd())).into_owned();
+    if command.parts().last() == Some(&destination) {
+        misc::replace_argument(command.command(), &shell_words::quote(&destination), &fresh)
+    } else {
+        format!("{} {fresh}", command.command())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fix::structs::CommandOutput;
+
+    fn clone_command(cmd: &str, destination: &str, cwd: &Path) -> Command {
+        Command::new(
+            cmd.to_string(),
+            CommandOutput::new(
+                String::new(),
+                format!(
+                    "fatal: destination path '{destination}' already exists and is not an empty directory."
+                ),
+            ),
+        )
+        .with_cwd(cwd.to_path_buf())
+    }
+
+    fn create_dir_with(root: &Path, name: &str, files: &[&str]) {
+        fs::create_dir_all(root.join(name)).expect("Failed to create dir");
+        for file in files {
+            fs::write(root.join(name).join(file), "").expect("Failed to write file");
+        }
+    }
+
+    #[test]
+    fn test_is_match() {
+        let temp = tempfile::tempdir().expect("Failed to create temp dir");
+        let command = clone_command(
+            "git clone https://example.com/repo.git",
+            "repo",
+            temp.path(),
+        );
+        assert!(is_match(&command));
+    }
+
+    #[test]
+    fn test_is_match_other_error() {
+        let command = Command::new(
+            "git clone https://example.com/repo.git".to_string(),
+            CommandOutput::new(
+                String::new(),
+                "fatal: repository 'https://example.com/repo.git/' not found".to_string(),
+            ),
+        );
+        assert!(!is_match(&command));
+    }
+
+    #[test]
+    fn test_fix_appends_fresh_destination() {
+        let temp = tempfile::tempdir().expect("Failed to create temp dir");
+        create_dir_with(temp.path(), "repo", &["README.md"]);
+        create_dir_with(temp.path(), "repo-1", &["README.md"]);
+        let command = clone_command(
+            "git clone https://example.com/repo.git",
+            "repo",
+            temp.path(),
+        );
+        assert_eq!(
+            fix(&command),
+            "git clone https://example.com/repo.git repo-2"
+        );
+    }
+
+    #[test]
+    fn test_fix_replaces_explicit_destination() {
+        let temp = tempfile::tempdir().expect("Failed to create temp dir");
+        create_dir_with(temp.path(), "work", &["notes.txt"]);
+        let command = clone_command(
+            "git clone https://example.com/repo.git work",
+            "work",
+            temp.path(),
+        );
+        assert_eq!(
+            fix(&command),
+            "git clone https://example.com/repo.git work-1"
+        );
+    }
+
+    #[test]
+    fn test_fix_removes_leftover_directory() {
+        let temp = tempfile::tempdir().expect("Failed to create temp dir");
+        create_dir_with(temp.path(), "repo", &[".DS_Store"]);
+        let command = clone_command(
+            "git clone https://example.com/repo.git",
+            "repo",
+            temp.path(),
+        );
+        assert_eq!(
+            fix(&command),
+            "rm -rf repo && git clone https://example.com/repo.git"
+        );
+    }
+}
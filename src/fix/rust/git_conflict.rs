@@ -0,0 +1,110 @@
+use crate::fix::structs::Command;
+
+pub const DESCRIPTION: &str = "Aborts an unfinished rebase or merge. Detects \"rebase in \
+    progress\" or unmerged paths in git errors and suggests \"git rebase --abort\" or \"git \
+    merge --abort\".";
+
+static REBASE_PATTERNS: &[&str] = &[
+    "rebase in progress",
+    "you are currently rebasing",
+    "already a rebase-merge directory",
+    "already a rebase-apply directory",
+];
+
+static MERGE_PATTERNS: &[&str] = &[
+    "you have unmerged paths",
+    "because you have unmerged files",
+    "you have not concluded your merge",
+    "merge_head exists",
+];
+
+fn contains_any(output: &str, patterns: &[&str]) -> bool {
+    patterns.iter().any(|pattern| output.contains(pattern))
+}
+
+pub fn is_match(command: &Command) -> bool {
+    if command.parts().is_empty() || command.parts()[0] != "git" {
+        return false;
+    }
+    let stderr = command.output().stderr().to_lowercase();
+    contains_any(&stderr, REBASE_PATTERNS) || contains_any(&stderr, MERGE_PATTERNS)
+}
+
+pub fn fix(command: &Command) -> String {
+    let stderr = command.output().stderr().to_lowercase();
+    if contains_any(&stderr, REBASE_PATTERNS) {
+        "git rebase --abort".to_string()
+    } else {
+        "git merge --abort".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fix::structs::{Command, CommandOutput};
+
+    fn rebase_command() -> Command {
+        Command::new(
+            "git pull".to_string(),
+            CommandOutput::new(
+                String::new(),
+                "error: Pulling is not possible because you have unmerged files.\n\
+                 hint: Fix them up in the work tree, and then use 'git add/rm <file>'\n\
+                 interactive rebase in progress; onto 1a2b3c4"
+                    .to_string(),
+            ),
+        )
+    }
+
+    fn merge_command() -> Command {
+        Command::new(
+            "git pull".to_string(),
+            CommandOutput::new(
+                String::new(),
+                "error: You have not concluded your merge (MERGE_HEAD exists).\n\
+                 hint: Please, commit your changes before merging.\n\
+                 fatal: Exiting because of unfinished merge."
+                    .to_string(),
+            ),
+        )
+    }
+
+    #[test]
+    fn test_is_match_rebase() {
+        assert!(is_match(&rebase_command()));
+    }
+
+    #[test]
+    fn test_is_match_merge() {
+        assert!(is_match(&merge_command()));
+    }
+
+    #[test]
+    fn test_is_match_without_conflict() {
+        let command = Command::new(
+            "git pull".to_string(),
+            CommandOutput::new(String::new(), "Already up to date.".to_string()),
+        );
+        assert!(!is_match(&command));
+    }
+
+    #[test]
+    fn test_is_match_without_git() {
+        let command = Command::new(
+            "hg pull".to_string(),
+            CommandOutput::new(String::new(), "You have unmerged paths.".to_string()),
+        );
+        assert!(!is_match(&command));
+    }
+
+    #[test]
+    fn test_fix_rebase() {
+        assert_eq!(fix(&rebase_command()), "git rebase --abort");
+    }
+
+    #[test]
+    fn test_fix_merge() {
+        assert_eq!(fix(&merge_command()), "git merge --abort");
+    }
+}
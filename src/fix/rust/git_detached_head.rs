@@ -0,0 +1,91 @@
+use crate::fix::structs::Command;
+use regex::Regex;
+
+pub const DESCRIPTION: &str = "Keeps commits made on a detached HEAD. When \"git commit\" \
+    reports a detached HEAD, creates a branch at the new commit with \"git switch -c\" so \
+    the work is not lost.";
+
+const FALLBACK_BRANCH: &str = "rescued-commit";
+
+fn get_branch_name(command: &Command) -> String {
+    let commit_regex = Regex::new(r"\[detached HEAD ([0-9a-f]{4,40})\]")
+        .expect("Hardcoded regex pattern should be valid");
+    let output = command.output().combined();
+    commit_regex
+        .captures(&output)
+        .map(|caps| format!("detached-{}", &caps[1]))
+        .unwrap_or_else(|| FALLBACK_BRANCH.to_string())
+}
+
+pub fn is_match(command: &Command) -> bool {
+    if command.parts().len() < 2 || command.parts()[0] != "git" || command.parts()[1] != "commit" {
+        return false;
+    }
+    command.output().combined().contains("detached HEAD")
+}
+
+pub fn fix(command: &Command) -> String {
+    format!("git switch -c {}", get_branch_name(command))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fix::structs::{Command, CommandOutput};
+
+    fn commit_command(stdout: &str, stderr: &str) -> Command {
+        Command::new(
+            "git commit -m 'try things'".to_string(),
+            CommandOutput::new(stdout.to_string(), stderr.to_string()),
+        )
+    }
+
+    #[test]
+    fn test_is_match_commit_output() {
+        let command = commit_command(
+            "[detached HEAD 3f2a9c1] try things\n 1 file changed, 2 insertions(+)",
+            "",
+        );
+        assert!(is_match(&command));
+    }
+
+    #[test]
+    fn test_is_match_advice() {
+        let command = commit_command(
+            "",
+            "Warning: you are leaving 1 commit behind, not connected to\n\
+             any of your branches:\n\n  3f2a9c1 try things\n\n\
+             If you want to keep it by creating a new branch, this may be a good time\n\
+             to do so with:\n\n git branch <new-branch-name> 3f2a9c1\n\n\
+             You are in 'detached HEAD' state.",
+        );
+        assert!(is_match(&command));
+    }
+
+    #[test]
+    fn test_is_match_on_branch() {
+        let command = commit_command("[main 3f2a9c1] try things", "");
+        assert!(!is_match(&command));
+    }
+
+    #[test]
+    fn test_is_match_not_commit() {
+        let command = Command::new(
+            "git status".to_string(),
+            CommandOutput::new("HEAD detached at 3f2a9c1".to_string(), String::new()),
+        );
+        assert!(!is_match(&command));
+    }
+
+    #[test]
+    fn test_fix_uses_commit_hash() {
+        let command = commit_command("[detached HEAD 3f2a9c1] try things", "");
+        assert_eq!(fix(&command), "git switch -c detached-3f2a9c1");
+    }
+
+    #[test]
+    fn test_fix_fallback_branch() {
+        let command = commit_command("", "You are in 'detached HEAD' state.");
+        assert_eq!(fix(&command), "git switch -c rescued-commit");
+    }
+}
@@ -0,0 +1,101 @@
+use crate::fix::structs::Command;
+
+pub const DESCRIPTION: &str = "Sets up a missing git identity. When git asks \"Please tell me \
+    who you are\", runs the \"git config\" commands it suggested before the original \
+    command.";
+
+static FALLBACK_CONFIG_COMMANDS: &[&str] = &[
+    "git config --global user.email \"you@example.com\"",
+    "git config --global user.name \"Your Name\"",
+];
+
+pub fn is_match(command: &Command) -> bool {
+    !command.parts().is_empty()
+        && command.parts()[0] == "git"
+        && command
+            .output()
+            .stderr()
+            .contains("Please tell me who you are")
+}
+
+pub fn fix(command: &Command) -> String {
+    let mut config_commands: Vec<&str> = command
+        .output()
+        .stderr()
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with("git config"))
+        .collect();
+    if config_commands.is_empty() {
+        config_commands = FALLBACK_CONFIG_COMMANDS.to_vec();
+    }
+    config_commands.push(command.command());
+    config_commands.join(" && ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fix::structs::{Command, CommandOutput};
+
+    static IDENTITY_ERROR: &str = "Author identity unknown
+
+*** Please tell me who you are.
+
+Run
+
+  git config --global user.email \"you@example.com\"
+  git config --global user.name \"Your Name\"
+
+to set your account's default identity.
+Omit --global to set the identity only in this repository.
+
+fatal: unable to auto-detect email address (got 'root@host.(none)')";
+
+    #[test]
+    fn test_is_match_true() {
+        let command = Command::new(
+            "git commit -m 'init'".to_string(),
+            CommandOutput::new(String::new(), IDENTITY_ERROR.to_string()),
+        );
+        assert!(is_match(&command));
+    }
+
+    #[test]
+    fn test_is_match_without_error() {
+        let command = Command::new(
+            "git commit -m 'init'".to_string(),
+            CommandOutput::new(String::new(), "nothing to commit".to_string()),
+        );
+        assert!(!is_match(&command));
+    }
+
+    #[test]
+    fn test_fix_uses_suggested_commands() {
+        let command = Command::new(
+            "git commit -m 'init'".to_string(),
+            CommandOutput::new(
+                String::new(),
+                IDENTITY_ERROR.replace("you@example.com", "me@work.dev"),
+            ),
+        );
+        assert_eq!(
+            fix(&command),
+            "git config --global user.email \"me@work.dev\" && \
+             git config --global user.name \"Your Name\" && git commit -m 'init'"
+        );
+    }
+
+    #[test]
+    fn test_fix_without_suggested_commands() {
+        let command = Command::new(
+            "git commit".to_string(),
+            CommandOutput::new(String::new(), "*** Please tell me who you are.".to_string()),
+        );
+        assert_eq!(
+            fix(&command),
+            "git config --global user.email \"you@example.com\" && \
+             git config --global user.name \"Your Name\" && git commit"
+        );
+    }
+}
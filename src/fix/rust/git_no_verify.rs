@@ -0,0 +1,86 @@
+use crate::fix::structs::Command;
+
+pub const DESCRIPTION: &str = "Skips failing git hooks. When a pre-commit or commit-msg hook \
+    blocks \"git commit\", suggests the same commit with --no-verify. Bypassing hooks is \
+    risky, so this fix is never run automatically: auto mode asks first.";
+
+/// Messages from git itself and the common hook managers (pre-commit, husky, lefthook).
+static PATTERNS: &[&str] = &[
+    "pre-commit hook",
+    "commit-msg hook",
+    "pre-commit script failed",
+    "commit-msg script failed",
+    "- hook id:",
+    "husky - ",
+    "lefthook",
+];
+
+const FLAG: &str = "--no-verify";
+
+pub fn is_match(command: &Command) -> bool {
+    let parts = command.parts();
+    let output = command.output().combined();
+    parts.first().is_some_and(|binary| binary == "git")
+        && parts
+            .get(1)
+            .is_some_and(|subcommand| subcommand == "commit")
+        && !parts.iter().any(|part| part == FLAG || part == "-n")
+        && command.output().exit_code() != Some(0)
+        && PATTERNS.iter().any(|pattern| output.contains(pattern))
+}
+
+pub fn fix(command: &Command) -> String {
+    format!("{} {FLAG}", command.command().trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fix::structs::CommandOutput;
+
+    fn git_command(cmd: &str, stdout: &str, stderr: &str) -> Command {
+        Command::new(
+            cmd.to_string(),
+            CommandOutput::new(stdout.to_string(), stderr.to_string()).with_exit_code(1),
+        )
+    }
+
+    #[test]
+    fn test_pre_commit_framework_failure() {
+        let command = git_command(
+            "git commit -m 'Add parser'",
+            "black....................................................................Failed\n- hook id: black\n- files were modified by this hook\n",
+            "",
+        );
+        assert!(is_match(&command));
+        assert_eq!(fix(&command), "git commit -m 'Add parser' --no-verify");
+    }
+
+    #[test]
+    fn test_husky_failure() {
+        let command = git_command(
+            "git commit",
+            "",
+            "husky - pre-commit script failed (code 1)",
+        );
+        assert!(is_match(&command));
+    }
+
+    #[test]
+    fn test_no_match() {
+        let command = git_command(
+            "git commit -m x",
+            "",
+            "nothing to commit, working tree clean",
+        );
+        assert!(!is_match(&command));
+        let command = git_command(
+            "git commit --no-verify",
+            "",
+            "husky - pre-commit script failed (code 1)",
+        );
+        assert!(!is_match(&command));
+        let command = git_command("git push", "", "husky - pre-push script failed (code 1)");
+        assert!(!is_match(&command));
+    }
+}
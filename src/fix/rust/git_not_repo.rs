@@ -0,0 +1,56 @@
+use crate::fix::structs::Command;
+
+pub const DESCRIPTION: &str = "Initializes a repository before running git. When a git \
+    command fails with \"not a git repository\", suggests \"git init\" before rerunning it, \
+    except for commands like git clone that never need one.";
+
+/// Subcommands that don't need an existing repository, so `git init` can't be what's missing.
+static SKIPPED_SUBCOMMANDS: &[&str] = &["clone", "init"];
+
+pub fn is_match(command: &Command) -> bool {
+    command.parts().len() >= 2
+        && command.parts()[0] == "git"
+        && !SKIPPED_SUBCOMMANDS.contains(&command.parts()[1].as_str())
+        && command.output().stderr().contains("not a git repository")
+}
+
+pub fn fix(command: &Command) -> String {
+    format!("git init && {}", command.command())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOT_A_REPO: &str = "fatal: not a git repository (or any of the parent directories): .git";
+
+    #[test]
+    fn test_is_match() {
+        assert!(is_match(&Command::failed("git add .", NOT_A_REPO)));
+    }
+
+    #[test]
+    fn test_is_match_skips_clone() {
+        assert!(!is_match(&Command::failed(
+            "git clone https://example.com/repo.git",
+            NOT_A_REPO
+        )));
+        assert!(!is_match(&Command::failed("git init", NOT_A_REPO)));
+    }
+
+    #[test]
+    fn test_is_match_other_error() {
+        assert!(!is_match(&Command::failed(
+            "git add missing.txt",
+            "fatal: pathspec 'missing.txt' did not match any files"
+        )));
+    }
+
+    #[test]
+    fn test_fix() {
+        assert_eq!(
+            fix(&Command::failed("git add .", NOT_A_REPO)),
+            "git init && git add ."
+        );
+    }
+}
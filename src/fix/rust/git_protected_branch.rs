@@ -0,0 +1,82 @@
+use super::{git_branches, git_remotes};
+use crate::fix::structs::Command;
+
+pub const DESCRIPTION: &str = "Moves work off a protected branch. When a push is rejected \
+    because the branch is protected, creates a feature branch named after the last commit \
+    subject and pushes it with -u, ready for a pull request.";
+
+/// GitHub says "Protected branch update failed", GitLab "not allowed to push code to protected
+/// branches"; both are reported through the pre-receive hook.
+const PATTERN: &str = "protected branch";
+
+fn fix_with(command: &Command, subject: Option<&str>, remotes: &[String]) -> String {
+    git_branches::switch_and_push(
+        &git_branches::branch_name(subject),
+        &git_remotes::get_push_remote(command, remotes),
+    )
+}
+
+pub fn is_match(command: &Command) -> bool {
+    command.parts().len() >= 2
+        && command.parts()[0] == "git"
+        && command.parts()[1] == "push"
+        && command.output().stderr().to_lowercase().contains(PATTERN)
+}
+
+pub fn fix(command: &Command) -> String {
+    fix_with(
+        command,
+        git_branches::get_last_subject(command.cwd()).as_deref(),
+        &git_remotes::get_remotes(command.cwd()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GITHUB_REJECTION: &str = "remote: error: GH006: Protected branch update failed for refs/heads/main.\n\
+        remote: error: Changes must be made through a pull request.\n\
+        To github.com:acme/app.git\n \
+        ! [remote rejected] main -> main (protected branch hook declined)\n\
+        error: failed to push some refs to 'github.com:acme/app.git'";
+
+    #[test]
+    fn test_github_rejection() {
+        let command = Command::failed("git push", GITHUB_REJECTION);
+        assert!(is_match(&command));
+        assert_eq!(
+            fix_with(
+                &command,
+                Some("Add dark mode toggle"),
+                &["origin".to_string()]
+            ),
+            "git switch -c add-dark-mode-toggle && git push -u origin add-dark-mode-toggle"
+        );
+    }
+
+    #[test]
+    fn test_gitlab_rejection_keeps_named_remote() {
+        let command = Command::failed(
+            "git push upstream main",
+            "remote: GitLab: You are not allowed to push code to protected branches on this project.\n\
+             ! [remote rejected] main -> main (pre-receive hook declined)",
+        );
+        assert!(is_match(&command));
+        assert_eq!(
+            fix_with(&command, None, &[]),
+            "git switch -c feature && git push -u upstream feature"
+        );
+    }
+
+    #[test]
+    fn test_no_match() {
+        let command = Command::failed(
+            "git push",
+            "! [rejected] main -> main (fetch first)\nerror: failed to push some refs",
+        );
+        assert!(!is_match(&command));
+        let command = Command::failed("git pull", GITHUB_REJECTION);
+        assert!(!is_match(&command));
+    }
+}
@@ -0,0 +1,131 @@
+use super::git_remotes;
+use crate::fix::structs::Command;
+use regex::Regex;
+
+pub const DESCRIPTION: &str = "Sets the upstream on the first push of a branch. When git push \
+    fails because the branch has no upstream, pushes with --set-upstream to the repository's \
+    only remote, or to origin when there are several.";
+
+fn get_branch(command: &Command) -> Option<String> {
+    let branch_regex = Regex::new(r"git push --set-upstream \S+ (\S+)")
+        .expect("Hardcoded regex pattern should be valid");
+    branch_regex
+        .captures(command.output().stderr())
+        .map(|caps| caps[1].to_string())
+}
+
+/// Builds the push with upstream set on `remote`, keeping the flags of the original push.
+fn build_fix(command: &Command, remote: &str, branch: &str) -> String {
+    let flags = command
+        .parts()
+        .iter()
+        .skip(2)
+        .filter(|part| part.starts_with('-'))
+        .map(|flag| shell_words::quote(flag).into_owned());
+    ["git", "push"]
+        .into_iter()
+        .map(str::to_string)
+        .chain(flags)
+        .chain([
+            "--set-upstream".to_string(),
+            remote.to_string(),
+            branch.to_string(),
+        ])
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+pub fn is_match(command: &Command) -> bool {
+    command.parts().len() >= 2
+        && command.parts()[0] == "git"
+        && command.parts()[1] == "push"
+        && command.output().stderr().contains("has no upstream branch")
+        && get_branch(command).is_some()
+}
+
+pub fn fix(command: &Command) -> String {
+    let Some(branch) = get_branch(command) else {
+        return command.command().to_string();
+    };
+    let remotes = git_remotes::get_remotes(command.cwd());
+    build_fix(command, git_remotes::pick_remote(&remotes), &branch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fix::structs::CommandOutput;
+    use std::path::Path;
+    use std::process;
+
+    fn push_command(cmd: &str, cwd: &Path) -> Command {
+        Command::new(
+            cmd.to_string(),
+            CommandOutput::new(
+                String::new(),
+                "fatal: The current branch feature has no upstream branch.\n\
+                 To push the current branch and set the remote as upstream, use\n\n    \
+                 git push --set-upstream origin feature\n"
+                    .to_string(),
+            ),
+        )
+        .with_cwd(cwd.to_path_buf())
+    }
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = process::Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .expect("Failed to run git")
+            .status;
+        assert!(status.success(), "git {args:?} failed");
+    }
+
+    #[test]
+    fn test_is_match() {
+        let temp = tempfile::tempdir().expect("Failed to create temp dir");
+        assert!(is_match(&push_command("git push", temp.path())));
+    }
+
+    #[test]
+    fn test_is_match_other_error() {
+        let command = Command::new(
+            "git push".to_string(),
+            CommandOutput::new(String::new(), "Everything up-to-date".to_string()),
+        );
+        assert!(!is_match(&command));
+    }
+
+    #[test]
+    fn test_fix_uses_sole_remote() {
+        let repo = tempfile::tempdir().expect("Failed to create temp dir");
+        git(repo.path(), &["init", "--quiet"]);
+        git(
+            repo.path(),
+            &["remote", "add", "upstream", "https://example.com/repo.git"],
+        );
+        assert_eq!(
+            fix(&push_command("git push -f", repo.path())),
+            "git push -f --set-upstream upstream feature"
+        );
+    }
+
+    #[test]
+    fn test_fix_falls_back_to_origin() {
+        let repo = tempfile::tempdir().expect("Failed to create temp dir");
+        git(repo.path(), &["init", "--quiet"]);
+        git(
+            repo.path(),
+            &["remote", "add", "fork", "https://example.com/fork.git"],
+        );
+        git(
+            repo.path(),
+            &["remote", "add", "upstream", "https://example.com/repo.git"],
+        );
+        assert_eq!(
+            fix(&push_command("git push", repo.path())),
+            "git push --set-upstream origin feature"
+        );
+    }
+}
@@ -0,0 +1,105 @@
+use super::{git_branches, git_remotes};
+use crate::fix::structs::Command;
+
+pub const DESCRIPTION: &str = "Puts a detached HEAD on a branch before pushing it. When git \
+    push fails because no branch is checked out, creates a branch named after the last \
+    commit subject and pushes it with -u.";
+
+/// `git push` without a branch checked out.
+const NOT_ON_BRANCH: &str = "You are not currently on a branch";
+
+/// `git push origin HEAD` on a detached HEAD: git can't tell which remote branch is meant.
+const NOT_FULL_REFNAME: &str = "is not a full refname";
+
+fn is_detached_push(command: &Command) -> bool {
+    let parts = command.parts();
+    if parts.len() < 2 || parts[0] != "git" || parts[1] != "push" {
+        return false;
+    }
+    let stderr = command.output().stderr();
+    stderr.contains(NOT_ON_BRANCH)
+        || (stderr.contains(NOT_FULL_REFNAME) && parts.iter().any(|part| part == "HEAD"))
+}
+
+fn fix_with(command: &Command, subject: Option<&str>, remotes: &[String]) -> String {
+    git_branches::switch_and_push(
+        &git_branches::branch_name(subject),
+        &git_remotes::get_push_remote(command, remotes),
+    )
+}
+
+pub fn is_match(command: &Command) -> bool {
+    is_detached_push(command)
+}
+
+pub fn fix(command: &Command) -> String {
+    fix_with(
+        command,
+        git_branches::get_last_subject(command.cwd()).as_deref(),
+        &git_remotes::get_remotes(command.cwd()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOT_ON_BRANCH_ERROR: &str = "fatal: You are not currently on a branch.
+To push the history leading to the current (detached HEAD)
+state now, use
+
+    git push origin HEAD:<name-of-remote-branch>
+";
+
+    const HEAD_REFNAME_ERROR: &str =
+        "error: The destination you provided is not a full refname (i.e.,
+starting with \"refs/\"). We tried to guess what you meant by:
+
+- Looking for a ref that matches 'HEAD' on the remote side.
+- Checking if the <src> being pushed ('HEAD')
+  is a ref in \"refs/{heads,tags}/\". If so we add a corresponding
+  refs/{heads,tags}/ prefix on the remote side.
+
+Neither worked, so we gave up. You must fully qualify the ref.
+error: failed to push some refs to 'github.com:acme/app.git'";
+
+    #[test]
+    fn test_is_match() {
+        assert!(is_match(&Command::failed("git push", NOT_ON_BRANCH_ERROR)));
+        assert!(is_match(&Command::failed(
+            "git push origin HEAD",
+            HEAD_REFNAME_ERROR
+        )));
+        assert!(!is_match(&Command::failed(
+            "git push origin main:develop",
+            HEAD_REFNAME_ERROR.replace("'HEAD'", "'develop'").as_str()
+        )));
+        assert!(!is_match(&Command::failed(
+            "git push",
+            "Everything up-to-date"
+        )));
+        assert!(!is_match(&Command::failed("git pull", NOT_ON_BRANCH_ERROR)));
+    }
+
+    #[test]
+    fn test_fix_names_branch_after_last_commit() {
+        let command = Command::failed("git push origin HEAD", HEAD_REFNAME_ERROR);
+        assert_eq!(
+            fix_with(
+                &command,
+                Some("Try the new parser"),
+                &["origin".to_string()]
+            ),
+            "git switch -c try-the-new-parser && git push -u origin try-the-new-parser"
+        );
+    }
+
+    #[test]
+    fn test_fix_uses_only_remote_and_fallback_name() {
+        let command = Command::failed("git push", NOT_ON_BRANCH_ERROR);
+        assert_eq!(
+            fix_with(&command, None, &["upstream".to_string()]),
+            "git switch -c feature && git push -u upstream feature"
+        );
+    }
+}
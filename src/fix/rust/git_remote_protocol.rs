@@ -0,0 +1,188 @@
+use super::git_remotes;
+use crate::fix::structs::Command;
+use regex::Regex;
+
+pub const DESCRIPTION: &str = "Switches a remote between ssh and https. When a push, pull or \
+    fetch fails with \"Permission denied (publickey)\", points the remote at the https form \
+    of its URL; when https authentication fails, points it at the ssh form. Then reruns the \
+    command.";
+
+static SUBCOMMANDS: &[&str] = &["push", "pull", "fetch"];
+
+const SSH_FAILURE: &str = "Permission denied (publickey)";
+
+static HTTPS_FAILURES: &[&str] = &[
+    "Authentication failed for 'https://",
+    "could not read Username for 'https://",
+    "Invalid username or password",
+    "Support for password authentication was removed",
+];
+
+/// `git@host:owner/repo.git` or `ssh://git@host[:port]/owner/repo.git` to
+/// `https://host/owner/repo.git`.
+fn ssh_to_https(url: &str) -> Option<String> {
+    let scp_like =
+        Regex::new(r"^[\w.-]+@([\w.-]+):/?(.+)$").expect("Hardcoded regex pattern should be valid");
+    let ssh_scheme = Regex::new(r"^ssh://(?:[\w.-]+@)?([\w.-]+)(?::\d+)?/(.+)$")
+        .expect("Hardcoded regex pattern should be valid");
+    let caps = ssh_scheme
+        .captures(url)
+        .or_else(|| scp_like.captures(url))?;
+    Some(format!("https://{}/{}", &caps[1], &caps[2]))
+}
+
+/// `https://[user@]host/owner/repo.git` to `git@host:owner/repo.git`.
+fn https_to_ssh(url: &str) -> Option<String> {
+    let https = Regex::new(r"^https?://(?:[^@/]+@)?([\w.-]+)(?::\d+)?/(.+?)/?$")
+        .expect("Hardcoded regex pattern should be valid");
+    let caps = https.captures(url)?;
+    Some(format!("git@{}:{}", &caps[1], &caps[2]))
+}
+
+/// The remote the command talked to: the one it names, otherwise the default pick.
+fn get_remote<'a>(
+    command: &Command,
+    remotes: &'a [(String, String)],
+) -> Option<&'a (String, String)> {
+    let named = command
+        .parts()
+        .iter()
+        .skip(2)
+        .find(|part| !part.starts_with('-'))
+        .and_then(|name| remotes.iter().find(|(remote, _)| remote == name));
+    named.or_else(|| {
+        let names: Vec<String> = remotes.iter().map(|(name, _)| name.clone()).collect();
+        let picked = git_remotes::pick_remote(&names);
+        remotes.iter().find(|(name, _)| name == picked)
+    })
+}
+
+fn switched_url(command: &Command, url: &str) -> Option<String> {
+    let output = command.output().combined();
+    if output.contains(SSH_FAILURE) {
+        ssh_to_https(url)
+    } else if HTTPS_FAILURES
+        .iter()
+        .any(|failure| output.contains(failure))
+    {
+        https_to_ssh(url)
+    } else {
+        None
+    }
+}
+
+fn is_git_sync(command: &Command) -> bool {
+    let parts = command.parts();
+    parts.first().is_some_and(|binary| binary == "git")
+        && parts
+            .get(1)
+            .is_some_and(|subcommand| SUBCOMMANDS.contains(&subcommand.as_str()))
+}
+
+fn is_match_with(command: &Command, remotes: &[(String, String)]) -> bool {
+    is_git_sync(command)
+        && get_remote(command, remotes).is_some_and(|(_, url)| switched_url(command, url).is_some())
+}
+
+fn fix_with(command: &Command, remotes: &[(String, String)]) -> String {
+    match get_remote(command, remotes)
+        .and_then(|(name, url)| Some((name, switched_url(command, url)?)))
+    {
+        Some((name, url)) => format!(
+            "git remote set-url {name} {} && {}",
+            shell_words::quote(&url),
+            command.command()
+        ),
+        None => command.command().to_string(),
+    }
+}
+
+pub fn is_match(command: &Command) -> bool {
+    is_git_sync(command) && is_match_with(command, &git_remotes::get_remote_urls(command.cwd()))
+}
+
+pub fn fix(command: &Command) -> String {
+    fix_with(command, &git_remotes::get_remote_urls(command.cwd()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn remotes(urls: &[(&str, &str)]) -> Vec<(String, String)> {
+        urls.iter()
+            .map(|(name, url)| (name.to_string(), url.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_ssh_to_https() {
+        assert_eq!(
+            ssh_to_https("git@github.com:me/app.git").as_deref(),
+            Some("https://github.com/me/app.git")
+        );
+        assert_eq!(
+            ssh_to_https("ssh://git@gitlab.example.com:2222/group/app.git").as_deref(),
+            Some("https://gitlab.example.com/group/app.git")
+        );
+        assert_eq!(ssh_to_https("https://github.com/me/app.git"), None);
+    }
+
+    #[test]
+    fn test_https_to_ssh() {
+        assert_eq!(
+            https_to_ssh("https://github.com/me/app.git").as_deref(),
+            Some("git@github.com:me/app.git")
+        );
+        assert_eq!(
+            https_to_ssh("https://me@bitbucket.org/team/app").as_deref(),
+            Some("git@bitbucket.org:team/app")
+        );
+        assert_eq!(https_to_ssh("git@github.com:me/app.git"), None);
+    }
+
+    #[test]
+    fn test_publickey_failure_switches_to_https() {
+        let command = Command::failed(
+            "git push",
+            "git@github.com: Permission denied (publickey).\nfatal: Could not read from remote repository.",
+        );
+        let remotes = remotes(&[("origin", "git@github.com:me/app.git")]);
+        assert!(is_match_with(&command, &remotes));
+        assert_eq!(
+            fix_with(&command, &remotes),
+            "git remote set-url origin https://github.com/me/app.git && git push"
+        );
+    }
+
+    #[test]
+    fn test_https_auth_failure_switches_to_ssh() {
+        let command = Command::failed(
+            "git pull upstream main",
+            "remote: Support for password authentication was removed on August 13, 2021.\nfatal: Authentication failed for 'https://github.com/org/app.git/'",
+        );
+        let remotes = remotes(&[
+            ("origin", "git@github.com:me/app.git"),
+            ("upstream", "https://github.com/org/app.git"),
+        ]);
+        assert!(is_match_with(&command, &remotes));
+        assert_eq!(
+            fix_with(&command, &remotes),
+            "git remote set-url upstream git@github.com:org/app.git && git pull upstream main"
+        );
+    }
+
+    #[test]
+    fn test_no_match() {
+        let remotes = remotes(&[("origin", "https://github.com/me/app.git")]);
+        let command = Command::failed("git push", "git@github.com: Permission denied (publickey).");
+        assert!(!is_match_with(&command, &remotes));
+        let command = Command::failed("git push", "Everything up-to-date");
+        assert!(!is_match_with(&command, &remotes));
+        let command = Command::failed(
+            "git clone git@github.com:me/app.git",
+            "git@github.com: Permission denied (publickey).",
+        );
+        assert!(!is_match_with(&command, &remotes));
+    }
+}
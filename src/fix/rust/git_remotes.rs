@@ -0,0 +1,131 @@
+//! Reads the remotes of the repository a git command ran in, for the rules that push or fetch.
+use crate::fix::structs::Command;
+use std::path::Path;
+use std::process;
+
+const DEFAULT_REMOTE: &str = "origin";
+
+pub fn parse_remotes(raw: &str) -> Vec<String> {
+    raw.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Remote names as listed by `git remote` in `cwd`; empty outside a repository.
+pub fn get_remotes(cwd: &Path) -> Vec<String> {
+    process::Command::new("git")
+        .arg("remote")
+        .current_dir(cwd)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| parse_remotes(&String::from_utf8_lossy(&output.stdout)))
+        .unwrap_or_default()
+}
+
+/// Parses `git remote -v` into `(name, url)` pairs, one per remote, using the fetch URL.
+pub fn parse_remote_urls(raw: &str) -> Vec<(String, String)> {
+    let mut urls: Vec<(String, String)> = vec![];
+    for line in raw.lines() {
+        let mut fields = line.split_whitespace();
+        let (Some(name), Some(url)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+        if !urls.iter().any(|(known, _)| known == name) {
+            urls.push((name.to_string(), url.to_string()));
+        }
+    }
+    urls
+}
+
+/// Remote names with their URLs as listed by `git remote -v` in `cwd`.
+pub fn get_remote_urls(cwd: &Path) -> Vec<(String, String)> {
+    process::Command::new("git")
+        .args(["remote", "-v"])
+        .current_dir(cwd)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| parse_remote_urls(&String::from_utf8_lossy(&output.stdout)))
+        .unwrap_or_default()
+}
+
+/// The sole remote if there's just one, otherwise `origin`.
+pub fn pick_remote(remotes: &[String]) -> &str {
+    match remotes {
+        [remote] => remote,
+        _ => DEFAULT_REMOTE,
+    }
+}
+
+/// The remote a push went to: the one named in the command, otherwise the default pick.
+pub fn get_push_remote(command: &Command, remotes: &[String]) -> String {
+    command
+        .parts()
+        .iter()
+        .skip(2)
+        .find(|part| !part.starts_with('-') && !part.starts_with("HEAD"))
+        .cloned()
+        .unwrap_or_else(|| pick_remote(remotes).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_remotes() {
+        assert_eq!(
+            parse_remotes("origin\nupstream\n"),
+            vec!["origin", "upstream"]
+        );
+        assert!(parse_remotes("").is_empty());
+    }
+
+    #[test]
+    fn test_parse_remote_urls() {
+        let urls = parse_remote_urls(
+            "origin\tgit@github.com:me/app.git (fetch)\norigin\tgit@github.com:me/app.git (push)\nupstream\thttps://github.com/org/app.git (fetch)\n",
+        );
+        assert_eq!(
+            urls,
+            vec![
+                (
+                    "origin".to_string(),
+                    "git@github.com:me/app.git".to_string()
+                ),
+                (
+                    "upstream".to_string(),
+                    "https://github.com/org/app.git".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pick_remote() {
+        assert_eq!(pick_remote(&["upstream".to_string()]), "upstream");
+        assert_eq!(
+            pick_remote(&["fork".to_string(), "upstream".to_string()]),
+            "origin"
+        );
+        assert_eq!(pick_remote(&[]), "origin");
+    }
+
+    #[test]
+    fn test_get_push_remote() {
+        let push = |cmd: &str| Command::failed(cmd, "");
+        let remotes = ["upstream".to_string()];
+        assert_eq!(
+            get_push_remote(&push("git push -f fork main"), &remotes),
+            "fork"
+        );
+        assert_eq!(
+            get_push_remote(&push("git push HEAD:main"), &remotes),
+            "upstream"
+        );
+        assert_eq!(get_push_remote(&push("git push"), &[]), "origin");
+    }
+}
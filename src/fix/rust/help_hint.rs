@@ -0,0 +1,77 @@
+use crate::fix::structs::{Command, Failure};
+
+pub const DESCRIPTION: &str = "Points at the help of a command called wrong. When nothing \
+    else matches a command that was not found or failed with a usage error, suggests running \
+    it with --help. Ranked below every other fix and dropped whenever there is one.";
+
+/// Messages tools print when they were called wrong rather than failed at their job.
+static USAGE_PATTERNS: &[&str] = &[
+    "usage:",
+    "command not found",
+    "invalid option",
+    "unrecognized option",
+    "unknown option",
+    "missing operand",
+    "try '--help'",
+];
+
+/// Exit code most argument parsers use for a usage error.
+const USAGE_EXIT_CODE: i32 = 2;
+
+pub fn is_match(command: &Command) -> bool {
+    let Some(binary) = command.parts().first() else {
+        return false;
+    };
+    if command
+        .parts()
+        .iter()
+        .any(|part| part == "--help" || part == "-h")
+    {
+        return false;
+    }
+    let output = command.output().combined().to_lowercase();
+    !binary.is_empty()
+        && (USAGE_PATTERNS
+            .iter()
+            .any(|pattern| output.contains(pattern))
+            || command.output().failed_with(Failure::CommandNotFound)
+            || command.output().exit_code() == Some(USAGE_EXIT_CODE))
+}
+
+pub fn fix(command: &Command) -> String {
+    format!("{} --help", shell_words::quote(&command.parts()[0]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fix::structs::CommandOutput;
+
+    #[test]
+    fn test_usage_error() {
+        let command = Command::failed(
+            "rsync -Z src dst",
+            "rsync: -Z: unknown option\nrsync error: syntax or usage error (code 1)",
+        );
+        assert!(is_match(&command));
+        assert_eq!(fix(&command), "rsync --help");
+    }
+
+    #[test]
+    fn test_usage_exit_code() {
+        let command = Command::new(
+            "grep".to_string(),
+            CommandOutput::new(String::new(), String::new()).with_exit_code(2),
+        );
+        assert!(is_match(&command));
+        assert_eq!(fix(&command), "grep --help");
+    }
+
+    #[test]
+    fn test_no_match() {
+        let command = Command::failed("ls /missing", "ls: cannot access '/missing': No such file");
+        assert!(!is_match(&command));
+        let command = Command::failed("tar --help", "Usage: tar [OPTION...] [FILE]...");
+        assert!(!is_match(&command));
+    }
+}
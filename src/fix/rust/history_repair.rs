@@ -0,0 +1,120 @@
+use crate::fix::structs::Command;
+use crate::misc;
+
+pub const DESCRIPTION: &str = "Reuses a working variant from your shell history. When a \
+    command fails and SH_HISTORY shows it previously ran with sudo, or a close variant ran \
+    without being retried, suggests that variant.";
+
+const MIN_SIMILARITY: f64 = 0.75;
+
+fn is_variant(command: &str, candidate: &str) -> bool {
+    if candidate == command {
+        return false;
+    }
+    let binary = |command: &str| {
+        command
+            .split_whitespace()
+            .find(|part| *part != "sudo")
+            .map(str::to_string)
+    };
+    binary(command) == binary(candidate)
+        && misc::string_similarity(command, candidate) >= MIN_SIMILARITY
+}
+
+/// Finds the most recent variant of `command` in `history` (newest first, as in `SH_HISTORY`)
+/// that looks like it worked: an exact `sudo` rerun wins, otherwise the first variant that
+/// wasn't immediately retried with another variant.
+fn find_successful_variant(command: &str, history: &[String]) -> Option<String> {
+    let sudo_command = format!("sudo {command}");
+    if history.contains(&sudo_command) {
+        return Some(sudo_command);
+    }
+    history
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| is_variant(command, entry))
+        .find(|(index, entry)| {
+            // History is newest first, so the command run right after `entry` is one index lower.
+            index
+                .checked_sub(1)
+                .and_then(|next| history.get(next))
+                .is_none_or(|next| !is_variant(entry, next) || next == command)
+        })
+        .map(|(_, entry)| entry.clone())
+}
+
+pub fn is_match(command: &Command) -> bool {
+    let failed = command
+        .output()
+        .exit_code()
+        .map_or_else(|| !command.output().stderr().is_empty(), |code| code != 0);
+    failed && find_successful_variant(command.command(), &misc::previous_commands()).is_some()
+}
+
+pub fn fix(command: &Command) -> String {
+    find_successful_variant(command.command(), &misc::previous_commands())
+        .unwrap_or_else(|| command.command().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fix::structs::CommandOutput;
+
+    fn entries(items: &[&str]) -> Vec<String> {
+        items.iter().map(|item| item.to_string()).collect()
+    }
+
+    #[test]
+    fn test_prefers_previous_sudo_run() {
+        let history = entries(&["ls", "sudo systemctl restart nginx", "cd /etc"]);
+        assert_eq!(
+            find_successful_variant("systemctl restart nginx", &history),
+            Some("sudo systemctl restart nginx".to_string())
+        );
+    }
+
+    #[test]
+    fn test_finds_successful_variant() {
+        let history = entries(&["git status", "docker compose up -d", "ls"]);
+        assert_eq!(
+            find_successful_variant("docker compose up", &history),
+            Some("docker compose up -d".to_string())
+        );
+    }
+
+    #[test]
+    fn test_skips_variant_that_was_retried() {
+        // Newest first: `npm run buidl` failed and was retried as `npm run build`.
+        let history = entries(&["npm run build", "npm run buidl"]);
+        assert_eq!(
+            find_successful_variant("npm run biuld", &history),
+            Some("npm run build".to_string())
+        );
+
+        let history = entries(&["npm run buidl2", "npm run buidl"]);
+        assert_eq!(
+            find_successful_variant("npm run biuld", &history),
+            Some("npm run buidl2".to_string())
+        );
+    }
+
+    #[test]
+    fn test_ignores_other_programs_and_duplicates() {
+        let history = entries(&["systemctl restart nginx", "journalctl -u nginx"]);
+        assert_eq!(
+            find_successful_variant("systemctl restart nginx", &history),
+            None
+        );
+        assert_eq!(find_successful_variant("make", &history), None);
+    }
+
+    #[test]
+    fn test_is_match_requires_failure() {
+        let command = Command::new(
+            "systemctl restart nginx".to_string(),
+            CommandOutput::new(String::new(), String::new()).with_exit_code(0),
+        );
+        assert!(!is_match(&command));
+    }
+}
@@ -0,0 +1,158 @@
+use crate::fix::structs::Command;
+use std::process;
+
+pub const DESCRIPTION: &str = "Switches kubectl to another context. When kubectl cannot reach \
+    the server or the configured context is missing, suggests \"kubectl config use-context\" \
+    with the first other context from \"kubectl config get-contexts\" and then reruns the \
+    command.";
+
+static PATTERNS: &[&str] = &[
+    "was refused - did you specify the right host or port?",
+    "Unable to connect to the server",
+    "current-context is not set",
+    "no context exists with the name",
+    "context was not found for specified context",
+];
+
+struct Context {
+    name: String,
+    current: bool,
+}
+
+/// Parses the table printed by `kubectl config get-contexts`, where the current context is
+/// marked with `*` in the first column.
+fn parse_contexts(raw: &str) -> Vec<Context> {
+    raw.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let current = line.starts_with('*');
+            let name = line.trim_start_matches('*').split_whitespace().next()?;
+            Some(Context {
+                name: name.to_string(),
+                current,
+            })
+        })
+        .collect()
+}
+
+fn get_contexts() -> String {
+    process::Command::new("kubectl")
+        .args(["config", "get-contexts"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).into_owned())
+        .unwrap_or_default()
+}
+
+/// The first context that isn't the one that just failed.
+fn pick_context(raw_contexts: &str) -> Option<String> {
+    parse_contexts(raw_contexts)
+        .into_iter()
+        .find(|context| !context.current)
+        .map(|context| context.name)
+}
+
+fn is_context_error(command: &Command) -> bool {
+    let output = command.output().combined();
+    command
+        .parts()
+        .first()
+        .is_some_and(|part| part == "kubectl")
+        && PATTERNS.iter().any(|pattern| output.contains(pattern))
+}
+
+fn is_match_with(command: &Command, raw_contexts: &str) -> bool {
+    is_context_error(command) && pick_context(raw_contexts).is_some()
+}
+
+fn fix_with(command: &Command, raw_contexts: &str) -> String {
+    match pick_context(raw_contexts) {
+        Some(context) => format!(
+            "kubectl config use-context {} && {}",
+            shell_words::quote(&context),
+            command.command()
+        ),
+        None => command.command().to_string(),
+    }
+}
+
+pub fn is_match(command: &Command) -> bool {
+    is_context_error(command) && is_match_with(command, &get_contexts())
+}
+
+pub fn fix(command: &Command) -> String {
+    fix_with(command, &get_contexts())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTEXTS: &str = "\
+CURRENT   NAME             CLUSTER          AUTHINFO         NAMESPACE
+*         kind-dev         kind-dev         kind-dev
+          prod-eu          prod-eu          admin            default
+          staging          staging          admin
+";
+
+    #[test]
+    fn test_parse_contexts() {
+        let contexts = parse_contexts(CONTEXTS);
+        let names: Vec<&str> = contexts.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["kind-dev", "prod-eu", "staging"]);
+        assert!(contexts[0].current);
+        assert!(!contexts[1].current);
+    }
+
+    #[test]
+    fn test_connection_refused() {
+        let command = Command::failed(
+            "kubectl get pods",
+            "The connection to the server 127.0.0.1:6443 was refused - did you specify the right host or port?",
+        );
+        assert!(is_match_with(&command, CONTEXTS));
+        assert_eq!(
+            fix_with(&command, CONTEXTS),
+            "kubectl config use-context prod-eu && kubectl get pods"
+        );
+    }
+
+    #[test]
+    fn test_missing_context() {
+        let command = Command::failed(
+            "kubectl get nodes",
+            "error: context was not found for specified context: old",
+        );
+        let contexts =
+            "CURRENT   NAME   CLUSTER   AUTHINFO   NAMESPACE\n          dev    dev       dev\n";
+        assert!(is_match_with(&command, contexts));
+        assert_eq!(
+            fix_with(&command, contexts),
+            "kubectl config use-context dev && kubectl get nodes"
+        );
+    }
+
+    #[test]
+    fn test_no_other_context() {
+        let command = Command::failed(
+            "kubectl get pods",
+            "Unable to connect to the server: dial tcp: lookup example.com: no such host",
+        );
+        let contexts =
+            "CURRENT   NAME   CLUSTER   AUTHINFO   NAMESPACE\n*         dev    dev       dev\n";
+        assert!(!is_match_with(&command, contexts));
+        assert!(!is_match_with(&command, ""));
+    }
+
+    #[test]
+    fn test_other_errors() {
+        let command = Command::failed(
+            "kubectl get pods",
+            "Error from server (NotFound): pods \"x\" not found",
+        );
+        assert!(!is_match_with(&command, CONTEXTS));
+        let command = Command::failed("curl localhost", "Unable to connect to the server");
+        assert!(!is_match_with(&command, CONTEXTS));
+    }
+}
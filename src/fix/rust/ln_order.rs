@@ -0,0 +1,121 @@
+use crate::fix::structs::Command;
+use crate::misc;
+use std::path::Path;
+
+pub const DESCRIPTION: &str = "Swaps the operands of ln -s. When creating a symbolic link \
+    fails with \"File exists\" because the link name exists while the target does not, \
+    suggests the command with target and link name swapped.";
+
+const PATTERN: &str = "File exists";
+
+/// Positions of the two operands of `ln -s target link_name`; any other shape is left alone.
+fn get_operands(command: &Command) -> Option<(usize, usize)> {
+    let parts = command.parts();
+    if parts.first().is_none_or(|binary| binary != "ln") {
+        return None;
+    }
+    let is_symbolic = parts.iter().skip(1).any(|part| {
+        part == "--symbolic"
+            || (part.starts_with('-') && !part.starts_with("--") && part.contains('s'))
+    });
+    let operands: Vec<usize> = (1..parts.len())
+        .filter(|index| !parts[*index].starts_with('-'))
+        .collect();
+    match operands.as_slice() {
+        [target, link_name] if is_symbolic => Some((*target, *link_name)),
+        _ => None,
+    }
+}
+
+fn exists(cwd: &Path, path: &str) -> bool {
+    cwd.join(misc::expand_path(path)).symlink_metadata().is_ok()
+}
+
+/// The link name already exists and the target doesn't, so the two were most likely swapped.
+pub fn is_match(command: &Command) -> bool {
+    let Some((target, link_name)) = get_operands(command) else {
+        return false;
+    };
+    let parts = command.parts();
+    command.output().stderr().contains(PATTERN)
+        && exists(command.cwd(), &parts[link_name])
+        && !exists(command.cwd(), &parts[target])
+}
+
+pub fn fix(command: &Command) -> String {
+    match get_operands(command) {
+        Some((target, link_name)) => {
+            let mut parts = command.parts().to_vec();
+            parts.swap(target, link_name);
+            shell_words::join(parts)
+        }
+        None => command.command().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fix::structs::CommandOutput;
+    use std::fs;
+
+    fn failed_command(cmd: &str, stderr: &str, cwd: &Path) -> Command {
+        Command::new(
+            cmd.to_string(),
+            CommandOutput::new(String::new(), stderr.to_string()),
+        )
+        .with_cwd(cwd.to_path_buf())
+    }
+
+    #[test]
+    fn test_swapped_operands() {
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        fs::write(dir.path().join("config.yml"), "").expect("Failed to write file");
+        let command = failed_command(
+            "ln -sf current.yml config.yml",
+            "ln: failed to create symbolic link 'config.yml': File exists",
+            dir.path(),
+        );
+        assert!(is_match(&command));
+        assert_eq!(fix(&command), "ln -sf config.yml current.yml");
+    }
+
+    #[test]
+    fn test_swapped_operands_with_long_option() {
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        fs::create_dir(dir.path().join("releases")).expect("Failed to create dir");
+        let command = failed_command(
+            "ln --symbolic latest releases",
+            "ln: failed to create symbolic link 'releases/latest': File exists",
+            dir.path(),
+        );
+        assert!(is_match(&command));
+        assert_eq!(fix(&command), "ln --symbolic releases latest");
+    }
+
+    #[test]
+    fn test_no_match() {
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        fs::write(dir.path().join("a"), "").expect("Failed to write file");
+        fs::write(dir.path().join("b"), "").expect("Failed to write file");
+        let stderr = "ln: failed to create symbolic link 'b': File exists";
+        // Both exist: the link name is simply taken.
+        assert!(!is_match(&failed_command("ln -s a b", stderr, dir.path())));
+        // Hard links and extra operands are out of scope.
+        assert!(!is_match(&failed_command(
+            "ln missing b",
+            stderr,
+            dir.path()
+        )));
+        assert!(!is_match(&failed_command(
+            "ln -s x y b",
+            stderr,
+            dir.path()
+        )));
+        assert!(!is_match(&failed_command(
+            "ln -s missing b",
+            "ln: b: Permission denied",
+            dir.path()
+        )));
+    }
+}
@@ -2,6 +2,9 @@ use crate::error::{AppError, AppResult};
 use crate::fix::structs::Command;
 use regex::Regex;
 
+pub const DESCRIPTION: &str = "Creates missing parent directories. When mkdir fails with \"No \
+    such file or directory\", adds -p so the whole path is created.";
+
 pub fn is_match(command: &Command) -> bool {
     command.parts().contains(&"mkdir".to_string())
         && !command.parts().contains(&"-p".to_string())
@@ -53,6 +56,18 @@ mod tests {
         assert!(!is_match(&command));
     }
 
+    #[test]
+    fn test_is_match_file_exists() {
+        let command = Command::new(
+            "mkdir some_directory".to_string(),
+            CommandOutput::new(
+                String::new(),
+                "mkdir: cannot create directory 'some_directory': File exists".to_string(),
+            ),
+        );
+        assert!(!is_match(&command));
+    }
+
     #[test]
     fn test_is_match_without_mkdir() {
         let command = Command::new(
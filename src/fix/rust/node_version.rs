@@ -0,0 +1,176 @@
+use crate::fix::structs::Command;
+use regex::Regex;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const DESCRIPTION: &str = "Switches to the node version a project pins. When node, npm, \
+    yarn or pnpm fail with an incompatible engine (or a syntax error with an .nvmrc present) \
+    and nvm is installed, suggests \"nvm use\" before rerunning the command.";
+
+static BINARIES: &[&str] = &["node", "npm", "npx", "yarn", "pnpm"];
+
+static ENGINE_PATTERNS: &[&str] = &[
+    "the engine \"node\" is incompatible",
+    "unsupported engine",
+    "ebadengine",
+];
+
+/// What the project asks for: `.nvmrc` is read by `nvm use` itself, `engines.node` needs the
+/// version spelled out.
+enum Pinned {
+    Nvmrc,
+    Engines(String),
+}
+
+fn find_pinned(dir: &Path) -> Option<Pinned> {
+    if dir.join(".nvmrc").is_file() {
+        return Some(Pinned::Nvmrc);
+    }
+    let package = fs::read_to_string(dir.join("package.json")).ok()?;
+    let engines_regex = Regex::new(r#""engines"\s*:\s*\{[^}]*"node"\s*:\s*"([^"]+)""#)
+        .expect("Hardcoded regex pattern should be valid");
+    let range = engines_regex.captures(&package)?;
+    let major_regex = Regex::new(r"\d+").expect("Hardcoded regex pattern should be valid");
+    major_regex
+        .find(&range[1])
+        .map(|major| Pinned::Engines(major.as_str().to_string()))
+}
+
+/// nvm is a shell function, so it's there when `$NVM_DIR/nvm.sh` is.
+fn nvm_available(nvm_dir: Option<PathBuf>) -> bool {
+    nvm_dir.is_some_and(|dir| dir.join("nvm.sh").is_file())
+}
+
+fn is_version_error(command: &Command, pinned: &Pinned) -> bool {
+    let output = command.output().combined().to_lowercase();
+    ENGINE_PATTERNS.iter().any(|pattern| output.contains(pattern))
+        // An old node chokes on newer syntax; only trust that when `.nvmrc` says which to use.
+        || matches!(pinned, Pinned::Nvmrc) && output.contains("syntaxerror")
+}
+
+fn is_match_with(command: &Command, nvm_dir: Option<PathBuf>) -> bool {
+    command
+        .parts()
+        .first()
+        .is_some_and(|binary| BINARIES.contains(&binary.as_str()))
+        && nvm_available(nvm_dir)
+        && find_pinned(command.cwd()).is_some_and(|pinned| is_version_error(command, &pinned))
+}
+
+pub fn is_match(command: &Command) -> bool {
+    is_match_with(command, env::var_os("NVM_DIR").map(PathBuf::from))
+}
+
+pub fn fix(command: &Command) -> String {
+    match find_pinned(command.cwd()) {
+        Some(Pinned::Engines(major)) => format!("nvm use {major} && {}", command.command()),
+        _ => format!("nvm use && {}", command.command()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fix::structs::CommandOutput;
+
+    const ENGINE_ERROR: &str = "error my-app@1.0.0: The engine \"node\" is incompatible with this module. Expected version \">=18.0.0\". Got \"16.20.2\"";
+
+    struct Fixture {
+        project: tempfile::TempDir,
+        nvm_dir: tempfile::TempDir,
+    }
+
+    fn fixture(files: &[(&str, &str)]) -> Fixture {
+        let project = tempfile::tempdir().expect("Failed to create temp dir");
+        for (name, contents) in files {
+            fs::write(project.path().join(name), contents).expect("Failed to write fixture");
+        }
+        let nvm_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        fs::write(nvm_dir.path().join("nvm.sh"), "").expect("Failed to write nvm.sh");
+        Fixture { project, nvm_dir }
+    }
+
+    fn node_command(cmd: &str, stderr: &str, cwd: &Path) -> Command {
+        Command::new(
+            cmd.to_string(),
+            CommandOutput::new(String::new(), stderr.to_string()),
+        )
+        .with_cwd(cwd.to_path_buf())
+    }
+
+    #[test]
+    fn test_nvmrc_engine_error() {
+        let fixture = fixture(&[(".nvmrc", "20\n")]);
+        let command = node_command("yarn install", ENGINE_ERROR, fixture.project.path());
+        assert!(is_match_with(
+            &command,
+            Some(fixture.nvm_dir.path().to_path_buf())
+        ));
+        assert_eq!(fix(&command), "nvm use && yarn install");
+    }
+
+    #[test]
+    fn test_nvmrc_syntax_error() {
+        let fixture = fixture(&[(".nvmrc", "20\n")]);
+        let command = node_command(
+            "node index.js",
+            "const x = a ?? b;\n            ^\n\nSyntaxError: Unexpected token '?'",
+            fixture.project.path(),
+        );
+        assert!(is_match_with(
+            &command,
+            Some(fixture.nvm_dir.path().to_path_buf())
+        ));
+    }
+
+    #[test]
+    fn test_package_engines() {
+        let fixture = fixture(&[(
+            "package.json",
+            r#"{"name": "my-app", "engines": {"npm": ">=9", "node": ">=18.0.0"}}"#,
+        )]);
+        let command = node_command(
+            "npm ci",
+            "npm WARN EBADENGINE Unsupported engine",
+            fixture.project.path(),
+        );
+        assert!(is_match_with(
+            &command,
+            Some(fixture.nvm_dir.path().to_path_buf())
+        ));
+        assert_eq!(fix(&command), "nvm use 18 && npm ci");
+
+        // Without .nvmrc a syntax error could be anything.
+        let command = node_command(
+            "node index.js",
+            "SyntaxError: Unexpected token '?'",
+            fixture.project.path(),
+        );
+        assert!(!is_match_with(
+            &command,
+            Some(fixture.nvm_dir.path().to_path_buf())
+        ));
+    }
+
+    #[test]
+    fn test_requires_nvm() {
+        let fixture = fixture(&[(".nvmrc", "20\n")]);
+        let command = node_command("yarn install", ENGINE_ERROR, fixture.project.path());
+        assert!(!is_match_with(&command, None));
+        assert!(!is_match_with(
+            &command,
+            Some(fixture.project.path().to_path_buf())
+        ));
+    }
+
+    #[test]
+    fn test_requires_pinned_version() {
+        let fixture = fixture(&[("package.json", r#"{"name": "my-app"}"#)]);
+        let command = node_command("yarn install", ENGINE_ERROR, fixture.project.path());
+        assert!(!is_match_with(
+            &command,
+            Some(fixture.nvm_dir.path().to_path_buf())
+        ));
+    }
+}
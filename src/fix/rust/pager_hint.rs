@@ -0,0 +1,73 @@
+use crate::fix::structs::Command;
+
+pub const DESCRIPTION: &str = "Offers a pager for huge output. When a command prints more \
+    than a screenful many times over, suggests running it again piped into less -R. Ranked \
+    below the fixes of other rules.";
+
+/// Ranked below the fixes of other rules: paging is a convenience, not a correction.
+pub const CONFIDENCE: u8 = 10;
+
+/// Output past either limit no longer fits a screen or scrollback comfortably.
+const MAX_LINES: usize = 500;
+const MAX_BYTES: usize = 32 * 1024;
+
+static PAGERS: &[&str] = &["less", "more", "most", "bat"];
+
+fn is_paged(command: &Command) -> bool {
+    command.command().contains('|')
+        || command
+            .parts()
+            .first()
+            .is_some_and(|binary| PAGERS.contains(&binary.as_str()))
+}
+
+pub fn is_match(command: &Command) -> bool {
+    let stdout = command.output().stdout();
+    !command.parts().is_empty()
+        && !is_paged(command)
+        && (stdout.len() > MAX_BYTES || stdout.lines().count() > MAX_LINES)
+}
+
+pub fn fix(command: &Command) -> String {
+    format!("{} | less -R", command.command())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fix::structs::CommandOutput;
+
+    fn command_with_stdout(cmd: &str, stdout: String) -> Command {
+        Command::new(cmd.to_string(), CommandOutput::new(stdout, String::new()))
+    }
+
+    fn log_lines(count: usize) -> String {
+        (0..count)
+            .map(|index| format!("commit {index:040x}\n"))
+            .collect()
+    }
+
+    #[test]
+    fn test_huge_output() {
+        let command = command_with_stdout("git log --stat", log_lines(5000));
+        assert!(is_match(&command));
+        assert_eq!(fix(&command), "git log --stat | less -R");
+    }
+
+    #[test]
+    fn test_single_huge_line() {
+        let command =
+            command_with_stdout("curl -s https://example.com/data.json", "x".repeat(100_000));
+        assert!(is_match(&command));
+    }
+
+    #[test]
+    fn test_no_match() {
+        let command = command_with_stdout("git log -3", log_lines(3));
+        assert!(!is_match(&command));
+        let command = command_with_stdout("git log | head -n 5000", log_lines(5000));
+        assert!(!is_match(&command));
+        let command = command_with_stdout("less big.log", log_lines(5000));
+        assert!(!is_match(&command));
+    }
+}
@@ -0,0 +1,95 @@
+use crate::fix::structs::{Command, Failure};
+
+pub const DESCRIPTION: &str = "Strips a prompt symbol pasted along with a command. When a \
+    command starts with a separate \"$\" or \"#\" (copied from docs) and the shell cannot \
+    find that command, removes the prompt and keeps the rest; \"$VAR\" paths are left alone.";
+
+static PROMPTS: &[&str] = &["$", "#", "%", ">"];
+
+static PATTERNS: &[&str] = &["command not found", "unknown command", "not found"];
+
+/// The prompt symbol has to be a word of its own, so `$HOME/bin/x` is never touched.
+/// Works on the raw text because shell word splitting drops a leading `#` as a comment.
+fn get_prompt(command: &Command) -> Option<&'static str> {
+    let mut words = command.command().split_whitespace();
+    let first = words.next()?;
+    words.next()?;
+    PROMPTS.iter().find(|prompt| **prompt == first).copied()
+}
+
+/// Matches on the command alone, for a command that hasn't run yet.
+pub fn is_prompt(command: &Command) -> bool {
+    get_prompt(command).is_some()
+}
+
+pub fn is_match(command: &Command) -> bool {
+    let Some(prompt) = get_prompt(command) else {
+        return false;
+    };
+    let output = command.output().combined().to_lowercase();
+    (PATTERNS.iter().any(|pattern| output.contains(pattern))
+        || command.output().failed_with(Failure::CommandNotFound))
+        && output.contains(prompt)
+}
+
+pub fn fix(command: &Command) -> String {
+    let trimmed = command.command().trim_start();
+    match get_prompt(command) {
+        Some(prompt) => trimmed[prompt.len()..].trim_start().to_string(),
+        None => command.command().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fix::structs::CommandOutput;
+
+    #[test]
+    fn test_dollar_prompt() {
+        let command = Command::failed("$ npm i", "zsh: command not found: $");
+        assert!(is_match(&command));
+        assert_eq!(fix(&command), "npm i");
+    }
+
+    #[test]
+    fn test_hash_prompt() {
+        let command = Command::failed("#  apt update", "bash: #: command not found");
+        assert!(is_match(&command));
+        assert_eq!(fix(&command), "apt update");
+    }
+
+    #[test]
+    fn test_variable_is_left_alone() {
+        let command = Command::failed(
+            "$HOME/bin/x --flag",
+            "bash: /home/user/bin/x: No such file or directory",
+        );
+        assert!(!is_match(&command));
+        let command = Command::failed("$HOME/bin/x", "zsh: command not found: $HOME/bin/x");
+        assert!(!is_match(&command));
+    }
+
+    #[test]
+    fn test_is_prompt_without_output() {
+        assert!(is_prompt(&Command::failed("$ npm i", "")));
+        assert!(!is_prompt(&Command::failed("$HOME/bin/x", "")));
+    }
+
+    #[test]
+    fn test_localized_error_with_exit_code() {
+        let command = Command::new(
+            "$ npm i".to_string(),
+            CommandOutput::new(String::new(), "zsh: Befehl nicht gefunden: $".to_string())
+                .with_exit_code(127),
+        );
+        assert!(is_match(&command));
+        assert_eq!(fix(&command), "npm i");
+    }
+
+    #[test]
+    fn test_requires_not_found_error() {
+        let command = Command::failed("$ npm i", "npm ERR! code E404");
+        assert!(!is_match(&command));
+    }
+}
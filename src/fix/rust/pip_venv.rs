@@ -0,0 +1,92 @@
+use crate::fix::structs::Command;
+
+pub const DESCRIPTION: &str = "Handles pip refusing to install into an externally managed \
+    Python. When pip reports \"externally-managed-environment\", suggests creating and \
+    activating a .venv virtualenv before the install, or rerunning it with \
+    --break-system-packages.";
+
+const VENV_DIR: &str = ".venv";
+
+fn is_pip(binary: &str) -> bool {
+    matches!(binary, "pip" | "pip3") || binary.starts_with("pip3.")
+}
+
+fn is_pip_install(command: &Command) -> bool {
+    let parts = command.parts();
+    let Some(binary) = parts.first() else {
+        return false;
+    };
+    let rest = if is_pip(binary) {
+        &parts[1..]
+    } else if binary.starts_with("python") && parts.get(1..3) == Some(&["-m".into(), "pip".into()])
+    {
+        &parts[3..]
+    } else {
+        return false;
+    };
+    rest.first()
+        .is_some_and(|subcommand| subcommand == "install")
+}
+
+pub fn is_match(command: &Command) -> bool {
+    is_pip_install(command)
+        && command
+            .output()
+            .combined()
+            .contains("externally-managed-environment")
+}
+
+/// A virtualenv first, since it leaves the system packages alone; overriding the protection
+/// is offered as the alternative.
+pub fn fix(command: &Command) -> Vec<String> {
+    vec![
+        format!(
+            "python3 -m venv {VENV_DIR} && source {VENV_DIR}/bin/activate && {}",
+            command.command()
+        ),
+        format!("{} --break-system-packages", command.command()),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXTERNALLY_MANAGED: &str = "\
+error: externally-managed-environment
+
+× This environment is externally managed
+╰─> To install Python packages system-wide, try apt install
+    python3-xyz, where xyz is the package you are trying to
+    install.";
+
+    #[test]
+    fn test_externally_managed() {
+        let command = Command::failed("pip install requests", EXTERNALLY_MANAGED);
+        assert!(is_match(&command));
+        assert_eq!(
+            fix(&command),
+            vec![
+                "python3 -m venv .venv && source .venv/bin/activate && pip install requests",
+                "pip install requests --break-system-packages",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_python_m_pip() {
+        let command = Command::failed("python3 -m pip install -U black", EXTERNALLY_MANAGED);
+        assert!(is_match(&command));
+    }
+
+    #[test]
+    fn test_no_match() {
+        let command = Command::failed("pip list", EXTERNALLY_MANAGED);
+        assert!(!is_match(&command));
+        let command = Command::failed(
+            "pip install requests",
+            "ERROR: Could not find a version that satisfies the requirement requests",
+        );
+        assert!(!is_match(&command));
+    }
+}
@@ -0,0 +1,116 @@
+use crate::fix::structs::Command;
+use crate::misc;
+use regex::Regex;
+
+pub const DESCRIPTION: &str = "Pins a pip requirement to an available version. When pip \
+    cannot find a version that satisfies a requirement but lists the versions it has, \
+    suggests installing the newest one listed.";
+
+struct Unsatisfied {
+    requirement: String,
+    newest: String,
+}
+
+fn get_unsatisfied(command: &Command) -> Option<Unsatisfied> {
+    let requirement_regex = Regex::new(
+        r"Could not find a version that satisfies the requirement (\S+) \(from versions: ([^)]*)\)",
+    )
+    .expect("Hardcoded regex pattern should be valid");
+    let caps = requirement_regex.captures(command.output().stderr())?;
+    // pip lists versions oldest first; "none" means there's nothing to suggest.
+    let newest = caps[2]
+        .split(',')
+        .map(str::trim)
+        .rfind(|version| !version.is_empty() && *version != "none")?;
+    Some(Unsatisfied {
+        requirement: caps[1].to_string(),
+        newest: newest.to_string(),
+    })
+}
+
+fn package_name(requirement: &str) -> &str {
+    requirement
+        .split(['=', '<', '>', '!', '~', '[', ';'])
+        .next()
+        .unwrap_or(requirement)
+}
+
+pub fn is_match(command: &Command) -> bool {
+    command
+        .parts()
+        .first()
+        .is_some_and(|binary| binary.starts_with("pip"))
+        && command.parts().contains(&"install".to_string())
+        && get_unsatisfied(command).is_some_and(|unsatisfied| {
+            command
+                .parts()
+                .iter()
+                .any(|part| part == &unsatisfied.requirement)
+        })
+}
+
+pub fn fix(command: &Command) -> String {
+    let Some(unsatisfied) = get_unsatisfied(command) else {
+        return command.command().to_string();
+    };
+    let pinned = format!(
+        "{}=={}",
+        package_name(&unsatisfied.requirement),
+        unsatisfied.newest
+    );
+    // The requirement may have been typed quoted, e.g. `'django>=9'`.
+    let quoted = shell_words::quote(&unsatisfied.requirement);
+    let typed = if command.command().contains(quoted.as_ref()) {
+        quoted.as_ref()
+    } else {
+        unsatisfied.requirement.as_str()
+    };
+    misc::replace_argument(command.command(), typed, &pinned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NO_MATCHING_VERSION: &str = "\
+ERROR: Could not find a version that satisfies the requirement django==9.0 (from versions: 1.1.3, 1.1.4, 4.2.7, 5.0a1, 5.0)
+ERROR: No matching distribution found for django==9.0";
+
+    #[test]
+    fn test_is_match() {
+        let command = Command::failed("pip install django==9.0", NO_MATCHING_VERSION);
+        assert!(is_match(&command));
+    }
+
+    #[test]
+    fn test_is_match_without_versions() {
+        let command = Command::failed(
+            "pip install djagno",
+            "ERROR: Could not find a version that satisfies the requirement djagno (from versions: none)\n\
+             ERROR: No matching distribution found for djagno",
+        );
+        assert!(!is_match(&command));
+    }
+
+    #[test]
+    fn test_is_match_not_pip() {
+        let command = Command::failed("poetry add django==9.0", NO_MATCHING_VERSION);
+        assert!(!is_match(&command));
+    }
+
+    #[test]
+    fn test_fix() {
+        let command = Command::failed("pip3 install -U django==9.0 requests", NO_MATCHING_VERSION);
+        assert_eq!(fix(&command), "pip3 install -U django==5.0 requests");
+    }
+
+    #[test]
+    fn test_fix_quoted_requirement() {
+        let command = Command::failed(
+            "pip install 'django>=9'",
+            "ERROR: Could not find a version that satisfies the requirement django>=9 (from versions: 4.2.7, 5.0)",
+        );
+        assert!(is_match(&command));
+        assert_eq!(fix(&command), "pip install django==5.0");
+    }
+}
@@ -0,0 +1,152 @@
+use crate::fix::structs::Command;
+use crate::misc;
+use regex::Regex;
+use std::process;
+
+pub const DESCRIPTION: &str = "Corrects a mistyped module run with python -m. When Python \
+    reports \"No module named\" for it, replaces the module with the closest of the common \
+    ones (pytest, pip, venv, http.server, ...) or of the modules that interpreter has \
+    installed.";
+
+const MIN_SIMILARITY: f64 = 0.6;
+
+/// Modules commonly run with `-m`, checked before asking the interpreter what is installed.
+static KNOWN_MODULES: &[&str] = &[
+    "pytest",
+    "pip",
+    "venv",
+    "http.server",
+    "json.tool",
+    "unittest",
+    "timeit",
+    "cProfile",
+    "pdb",
+    "ensurepip",
+];
+
+fn is_python(binary: &str) -> bool {
+    let python_regex = Regex::new(r"^(?:.*/)?(?:python[23]?(?:\.\d+)?|py)$")
+        .expect("Hardcoded regex pattern should be valid");
+    python_regex.is_match(binary)
+}
+
+/// The module given to `-m`, as `-m mod` or `-mmod`.
+fn get_module(command: &Command) -> Option<&str> {
+    let parts = command.parts();
+    parts.iter().enumerate().skip(1).find_map(|(index, part)| {
+        if part == "-m" {
+            parts.get(index + 1).map(String::as_str)
+        } else {
+            part.strip_prefix("-m").filter(|module| !module.is_empty())
+        }
+    })
+}
+
+fn is_missing_module(command: &Command, module: &str) -> bool {
+    command
+        .output()
+        .stderr()
+        .contains(&format!("No module named {module}"))
+}
+
+/// Top-level modules the interpreter that failed can import.
+fn get_installed_modules(python: &str) -> Vec<String> {
+    process::Command::new(python)
+        .args([
+            "-c",
+            "import pkgutil; print('\\n'.join(m.name for m in pkgutil.iter_modules()))",
+        ])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn correct_module<'a>(module: &str, candidates: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    candidates
+        .filter(|candidate| *candidate != module)
+        .map(|candidate| (misc::string_similarity(module, candidate), candidate))
+        .filter(|(similarity, _)| *similarity >= MIN_SIMILARITY)
+        .max_by(|(a, _), (b, _)| a.total_cmp(b))
+        .map(|(_, candidate)| candidate)
+}
+
+fn fix_with(command: &Command, installed: &[String]) -> Option<String> {
+    let module = get_module(command)?;
+    let candidates = KNOWN_MODULES
+        .iter()
+        .copied()
+        .chain(installed.iter().map(String::as_str));
+    let corrected = correct_module(module, candidates)?;
+    Some(misc::replace_argument(command.command(), module, corrected))
+}
+
+/// Tries the common modules first, so the interpreter is only asked when they don't help.
+fn get_fix(command: &Command) -> Option<String> {
+    fix_with(command, &[])
+        .or_else(|| fix_with(command, &get_installed_modules(&command.parts()[0])))
+}
+
+pub fn is_match(command: &Command) -> bool {
+    command
+        .parts()
+        .first()
+        .is_some_and(|binary| is_python(binary))
+        && get_module(command).is_some_and(|module| is_missing_module(command, module))
+        && get_fix(command).is_some()
+}
+
+pub fn fix(command: &Command) -> String {
+    get_fix(command).unwrap_or_else(|| command.command().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_module_typo() {
+        let command = Command::failed(
+            "python -m pytst -x tests/",
+            "/usr/bin/python: No module named pytst",
+        );
+        assert!(is_match(&command));
+        assert_eq!(fix(&command), "python -m pytest -x tests/");
+    }
+
+    #[test]
+    fn test_dotted_module_typo() {
+        let command = Command::failed(
+            "python3.12 -m http.sever 8000",
+            "/usr/bin/python3.12: No module named http.sever",
+        );
+        assert!(is_match(&command));
+        assert_eq!(fix(&command), "python3.12 -m http.server 8000");
+    }
+
+    #[test]
+    fn test_installed_module_typo() {
+        let command = Command::failed("python3 -m blakc .", "No module named blakc");
+        let installed = vec!["black".to_string(), "requests".to_string()];
+        assert_eq!(
+            fix_with(&command, &installed).as_deref(),
+            Some("python3 -m black .")
+        );
+    }
+
+    #[test]
+    fn test_no_match() {
+        let command = Command::failed("python script.py", "No module named requests");
+        assert!(!is_match(&command));
+        let command = Command::failed("python -m pytest", "1 passed in 0.01s");
+        assert!(!is_match(&command));
+        let command = Command::failed("node -m pytst", "No module named pytst");
+        assert!(!is_match(&command));
+    }
+}
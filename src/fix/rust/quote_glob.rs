@@ -0,0 +1,111 @@
+use crate::fix::structs::Command;
+
+pub const DESCRIPTION: &str = "Quotes a glob the shell expanded too early. When a \"find \
+    -name\" pattern or a git pathspec after \"--\" is an unquoted glob and the command \
+    fails, wraps it in single quotes so the tool sees the literal pattern.";
+
+/// `find` predicates whose argument is a pattern `find` matches itself.
+static FIND_PATTERN_FLAGS: &[&str] = &["-name", "-iname", "-path", "-ipath", "-wholename"];
+
+static PATTERNS: &[&str] = &[
+    "paths must precede expression",
+    "unknown primary or operator",
+    "no matches found",
+    "did not match any file",
+    "ambiguous argument",
+];
+
+fn is_unquoted_glob(word: &str) -> bool {
+    !word.starts_with(['\'', '"']) && word.contains(['*', '?', '['])
+}
+
+/// Indexes (in the raw, space-separated command) of globs meant as literal patterns.
+fn glob_positions(words: &[&str]) -> Vec<usize> {
+    match words.first() {
+        Some(&"find") => words
+            .windows(2)
+            .enumerate()
+            .filter(|(_, pair)| FIND_PATTERN_FLAGS.contains(&pair[0]) && is_unquoted_glob(pair[1]))
+            .map(|(index, _)| index + 1)
+            .collect(),
+        Some(&"git") => match words.iter().position(|word| *word == "--") {
+            Some(separator) => (separator + 1..words.len())
+                .filter(|index| is_unquoted_glob(words[*index]))
+                .collect(),
+            None => vec![],
+        },
+        _ => vec![],
+    }
+}
+
+/// Matches on the command alone, for a command that hasn't run yet.
+pub fn has_literal_glob(command: &Command) -> bool {
+    let words: Vec<&str> = command.command().split(' ').collect();
+    !glob_positions(&words).is_empty()
+}
+
+pub fn is_match(command: &Command) -> bool {
+    let output = command.output().combined();
+    has_literal_glob(command) && PATTERNS.iter().any(|pattern| output.contains(pattern))
+}
+
+pub fn fix(command: &Command) -> String {
+    let mut words: Vec<String> = command.command().split(' ').map(str::to_string).collect();
+    let positions = glob_positions(&words.iter().map(String::as_str).collect::<Vec<_>>());
+    for index in positions {
+        words[index] = format!("'{}'", words[index]);
+    }
+    words.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_name_expanded() {
+        let command = Command::failed(
+            "find . -name *.rs",
+            "find: paths must precede expression: `main.rs'",
+        );
+        assert!(is_match(&command));
+        assert_eq!(fix(&command), "find . -name '*.rs'");
+    }
+
+    #[test]
+    fn test_find_iname_no_matches() {
+        let command = Command::failed(
+            "find src -type f -iname *.TOML",
+            "zsh: no matches found: *.TOML",
+        );
+        assert!(is_match(&command));
+        assert_eq!(fix(&command), "find src -type f -iname '*.TOML'");
+    }
+
+    #[test]
+    fn test_git_pathspec() {
+        let command = Command::failed(
+            "git show HEAD~2..HEAD -- *.rs",
+            "zsh: no matches found: *.rs",
+        );
+        assert!(is_match(&command));
+        assert_eq!(fix(&command), "git show HEAD~2..HEAD -- '*.rs'");
+    }
+
+    #[test]
+    fn test_quoted_glob_is_left_alone() {
+        let command = Command::failed(
+            "find . -name '*.rs'",
+            "find: paths must precede expression: `foo'",
+        );
+        assert!(!is_match(&command));
+    }
+
+    #[test]
+    fn test_intended_expansion_is_left_alone() {
+        let command = Command::failed("ls *.rs", "zsh: no matches found: *.rs");
+        assert!(!is_match(&command));
+        let command = Command::failed("find *.rs -type f", "zsh: no matches found: *.rs");
+        assert!(!is_match(&command));
+    }
+}
@@ -0,0 +1,83 @@
+use crate::fix::structs::Command;
+
+pub const DESCRIPTION: &str = "Re-enables a legacy host key type for ssh, scp and sftp. When \
+    the connection fails with \"no matching host key type found\", adds -o HostKeyAlgorithms \
+    and -o PubkeyAcceptedAlgorithms options accepting the types the server offered.";
+
+static BINARIES: &[&str] = &["ssh", "scp", "sftp"];
+
+const PATTERN: &str = "no matching host key type found. Their offer: ";
+
+/// The algorithms the server offered, e.g. `ssh-rsa` or `ssh-rsa,ssh-dss`.
+fn get_offered_types(command: &Command) -> Option<&str> {
+    command.output().stderr().lines().find_map(|line| {
+        let (_, offer) = line.split_once(PATTERN)?;
+        let offer = offer.trim();
+        (!offer.is_empty()).then_some(offer)
+    })
+}
+
+pub fn is_match(command: &Command) -> bool {
+    command
+        .parts()
+        .first()
+        .is_some_and(|binary| BINARIES.contains(&binary.as_str()))
+        && get_offered_types(command).is_some()
+}
+
+pub fn fix(command: &Command) -> String {
+    let Some(offer) = get_offered_types(command) else {
+        return command.command().to_string();
+    };
+    let options = format!("-o HostKeyAlgorithms=+{offer} -o PubkeyAcceptedAlgorithms=+{offer}");
+    // Options have to precede the destination, otherwise ssh runs them as the remote command.
+    match command.command().split_once(' ') {
+        Some((binary, rest)) => format!("{binary} {options} {}", rest.trim_start()),
+        None => format!("{} {options}", command.command()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ssh_rsa_offer() {
+        let command = Command::failed(
+            "ssh admin@192.168.1.1",
+            "Unable to negotiate with 192.168.1.1 port 22: no matching host key type found. Their offer: ssh-rsa",
+        );
+        assert!(is_match(&command));
+        assert_eq!(
+            fix(&command),
+            "ssh -o HostKeyAlgorithms=+ssh-rsa -o PubkeyAcceptedAlgorithms=+ssh-rsa admin@192.168.1.1"
+        );
+    }
+
+    #[test]
+    fn test_scp_with_several_offered_types() {
+        let command = Command::failed(
+            "scp backup.tar router:/tmp",
+            "Unable to negotiate with 10.0.0.1 port 22: no matching host key type found. Their offer: ssh-rsa,ssh-dss\nlost connection",
+        );
+        assert!(is_match(&command));
+        assert_eq!(
+            fix(&command),
+            "scp -o HostKeyAlgorithms=+ssh-rsa,ssh-dss -o PubkeyAcceptedAlgorithms=+ssh-rsa,ssh-dss backup.tar router:/tmp"
+        );
+    }
+
+    #[test]
+    fn test_no_match() {
+        let command = Command::failed(
+            "ssh admin@192.168.1.1",
+            "admin@192.168.1.1: Permission denied (publickey).",
+        );
+        assert!(!is_match(&command));
+        let command = Command::failed(
+            "git fetch",
+            "Unable to negotiate with 10.0.0.1 port 22: no matching host key type found. Their offer: ssh-rsa",
+        );
+        assert!(!is_match(&command));
+    }
+}
@@ -1,4 +1,9 @@
-use crate::fix::structs::Command;
+use crate::fix::structs::{Command, Failure};
+
+pub const DESCRIPTION: &str = "Adds sudo to commands that failed with permission errors. \
+    Analyzes output for patterns like \"permission denied\", \"must be root\" and prepends \
+    sudo while handling && operators and redirections. In a pipeline, only the segment that \
+    reported the error gets sudo.";
 
 static PATTERNS: &[&str] = &[
     "permission denied",
@@ -30,27 +35,81 @@ static PATTERNS: &[&str] = &[
     "error: insufficient privileges",
     "updatedb: can not open a temporary file",
 ];
+
+fn starts_with_sudo(command: &Command) -> bool {
+    command.parts().first().is_some_and(|part| part == "sudo")
+}
+
 pub fn is_match(command: &Command) -> bool {
-    if !command.parts().is_empty()
-        && !command.parts().contains(&"&&".to_string())
-        && command.parts()[0] == "sudo"
-    {
+    // Whatever failed already ran with sudo, so another one can't help.
+    if starts_with_sudo(command) {
         return false;
     }
 
-    for pattern in PATTERNS {
-        if command.output().stdout().to_lowercase().contains(pattern)
-            || command.output().stderr().to_lowercase().contains(pattern)
-        {
-            return true;
+    let output = command.output().combined().to_lowercase();
+    PATTERNS.iter().any(|pattern| output.contains(pattern))
+        || command.output().failed_with(Failure::PermissionDenied)
+}
+
+/// Splits `command` on pipes outside quotes, keeping the surrounding whitespace so the segments
+/// join back into the original with `|`. Returns a single segment for `||` chains.
+fn split_pipeline(command: &str) -> Vec<&str> {
+    let mut segments = vec![];
+    let mut quote = None;
+    let mut start = 0;
+    let bytes = command.as_bytes();
+    for (index, &byte) in bytes.iter().enumerate() {
+        match (quote, byte) {
+            (Some(open), _) if byte == open => quote = None,
+            (Some(_), _) => {}
+            (None, b'\'' | b'"') => quote = Some(byte),
+            (None, b'|') => {
+                if bytes.get(index + 1) == Some(&b'|') || index > 0 && bytes[index - 1] == b'|' {
+                    return vec![command];
+                }
+                segments.push(&command[start..index]);
+                start = index + 1;
+            }
+            _ => {}
         }
     }
-    false
+    segments.push(&command[start..]);
+    segments
+}
+
+/// Prefixes sudo on the pipeline segment whose program reported the error (`cat: ...`), or
+/// on the first one when the output doesn't say.
+fn fix_pipeline(segments: &[&str], output: &str) -> String {
+    let failing = segments
+        .iter()
+        .position(|segment| {
+            segment
+                .split_whitespace()
+                .next()
+                .is_some_and(|binary| output.contains(&format!("{binary}:")))
+        })
+        .unwrap_or(0);
+    segments
+        .iter()
+        .enumerate()
+        .map(|(index, segment)| {
+            let body = segment.trim_start();
+            if index != failing || body.starts_with("sudo ") {
+                segment.to_string()
+            } else {
+                format!("{}sudo {body}", &segment[..segment.len() - body.len()])
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("|")
 }
 
 pub fn fix(command: &Command) -> String {
+    let segments = split_pipeline(command.command());
     if command.command().contains("&&") {
         format!("sudo sh -c '{}'", command.command().replace("sudo", ""))
+    } else if segments.len() > 1 && !command.command().contains('>') {
+        fix_pipeline(&segments, command.output().combined().as_str())
     } else if command.command().contains('>') {
         format!("sudo sh -c \"{}\"", command.command().replace("\"", "\\\""))
     } else {
@@ -75,6 +134,30 @@ mod tests {
         assert!(is_match(&command));
     }
 
+    #[test]
+    fn test_is_match_message_on_stdout() {
+        let command = Command::new(
+            "pacman -Syu".to_string(),
+            CommandOutput::new(
+                "error: you cannot perform this operation unless you are root.".to_string(),
+                String::new(),
+            ),
+        );
+        assert!(is_match(&command));
+    }
+
+    #[test]
+    fn test_is_match_localized_message_with_errno() {
+        let command = Command::new(
+            "npm install -g typescript".to_string(),
+            CommandOutput::new(
+                String::new(),
+                "npm ERR! code EPERM\nnpm ERR! Vorgang nicht zulässig".to_string(),
+            ),
+        );
+        assert!(is_match(&command));
+    }
+
     #[test]
     fn test_is_match_with_sudo() {
         let command = Command::new(
@@ -84,6 +167,31 @@ mod tests {
         assert!(!is_match(&command));
     }
 
+    #[test]
+    fn test_is_match_with_sudo_and_permission_denied() {
+        let command = Command::new(
+            "sudo some_command".to_string(),
+            CommandOutput::new(String::new(), "sudo: permission denied".to_string()),
+        );
+        assert!(!is_match(&command));
+    }
+
+    #[test]
+    fn test_never_produces_double_sudo() {
+        for raw in [
+            "sudo some_command",
+            "sudo -u root some_command",
+            "sudo some_command > output.txt",
+            "sudo some_command && another_command",
+        ] {
+            let command = Command::new(
+                raw.to_string(),
+                CommandOutput::new(String::new(), "permission denied".to_string()),
+            );
+            assert!(!is_match(&command), "{raw}");
+        }
+    }
+
     #[test]
     fn test_is_match_without_error() {
         let command = Command::new(
@@ -133,4 +241,45 @@ mod tests {
             "sudo sh -c \"some_command > output.txt\""
         );
     }
+
+    #[test]
+    fn test_split_pipeline() {
+        assert_eq!(split_pipeline("cat a | grep x"), vec!["cat a ", " grep x"]);
+        assert_eq!(split_pipeline("grep 'a|b' f"), vec!["grep 'a|b' f"]);
+        assert_eq!(split_pipeline("ls || echo no"), vec!["ls || echo no"]);
+    }
+
+    #[test]
+    fn test_fix_pipeline_first_segment() {
+        let command = Command::new(
+            "cat /root/secret | grep x".to_string(),
+            CommandOutput::new(
+                String::new(),
+                "cat: /root/secret: Permission denied".to_string(),
+            ),
+        );
+        assert!(is_match(&command));
+        assert_eq!(fix(&command), "sudo cat /root/secret | grep x");
+    }
+
+    #[test]
+    fn test_fix_pipeline_failing_segment() {
+        let command = Command::new(
+            "echo 1 | tee /proc/sys/vm/drop_caches".to_string(),
+            CommandOutput::new(
+                String::new(),
+                "tee: /proc/sys/vm/drop_caches: Permission denied".to_string(),
+            ),
+        );
+        assert_eq!(fix(&command), "echo 1 | sudo tee /proc/sys/vm/drop_caches");
+    }
+
+    #[test]
+    fn test_fix_pipeline_unknown_segment() {
+        let command = Command::new(
+            "journalctl -u nginx | less".to_string(),
+            CommandOutput::new(String::new(), "Permission denied".to_string()),
+        );
+        assert_eq!(fix(&command), "sudo journalctl -u nginx | less");
+    }
 }
@@ -0,0 +1,85 @@
+use crate::fix::structs::Command;
+
+pub const DESCRIPTION: &str = "Fixes sudo used with a shell builtin. When sudo cannot find a \
+    builtin such as cd, export or alias, drops sudo since the builtin has to run in the \
+    current shell; for source, \".\", ulimit and umask, runs it through a root shell with \
+    \"sudo -s\".";
+
+/// Builtins that change the state of the current shell; under sudo they can't have any effect,
+/// so the fix just drops sudo.
+static SHELL_STATE_BUILTINS: &[&str] = &["cd", "pushd", "popd", "export", "unset", "alias"];
+
+/// Builtins worth running as root, which `sudo -s` does through the target user's shell.
+static ROOT_BUILTINS: &[&str] = &["source", ".", "ulimit", "umask"];
+
+fn get_builtin(command: &Command) -> Option<&str> {
+    let parts = command.parts();
+    if parts.first().is_none_or(|part| part != "sudo") {
+        return None;
+    }
+    parts
+        .get(1)
+        .map(String::as_str)
+        .filter(|builtin| SHELL_STATE_BUILTINS.contains(builtin) || ROOT_BUILTINS.contains(builtin))
+}
+
+pub fn is_match(command: &Command) -> bool {
+    let output = command.output().combined();
+    get_builtin(command).is_some_and(|builtin| {
+        output.contains(&format!("{builtin}: command not found"))
+            || output.contains(&format!("unable to execute {builtin}"))
+    })
+}
+
+pub fn fix(command: &Command) -> String {
+    let raw = command.command().trim_start();
+    let rest = raw
+        .strip_prefix("sudo")
+        .unwrap_or(raw)
+        .trim_start()
+        .to_string();
+    match get_builtin(command) {
+        Some(builtin) if ROOT_BUILTINS.contains(&builtin) => format!("sudo -s {rest}"),
+        _ => rest,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sudo_cd() {
+        let command = Command::failed("sudo cd /root", "sudo: cd: command not found");
+        assert!(is_match(&command));
+        assert_eq!(fix(&command), "cd /root");
+    }
+
+    #[test]
+    fn test_sudo_export() {
+        let command = Command::failed(
+            "sudo export PATH=/opt/bin",
+            "sudo: export: command not found",
+        );
+        assert!(is_match(&command));
+        assert_eq!(fix(&command), "export PATH=/opt/bin");
+    }
+
+    #[test]
+    fn test_sudo_source_runs_in_root_shell() {
+        let command = Command::failed(
+            "sudo source /etc/profile.d/x.sh",
+            "sudo: source: command not found",
+        );
+        assert!(is_match(&command));
+        assert_eq!(fix(&command), "sudo -s source /etc/profile.d/x.sh");
+    }
+
+    #[test]
+    fn test_no_match() {
+        let command = Command::failed("sudo cdrecord x", "sudo: cdrecord: command not found");
+        assert!(!is_match(&command));
+        let command = Command::failed("cd /root", "cd: permission denied: /root");
+        assert!(!is_match(&command));
+    }
+}
@@ -0,0 +1,177 @@
+use crate::fix::structs::Command;
+use crate::misc;
+use regex::Regex;
+use std::process;
+
+pub const DESCRIPTION: &str = "Corrects a mistyped systemd unit. When systemctl reports that \
+    a unit could not be found, replaces it with the closest unit systemctl lists, keeping \
+    the action (status, start, restart, ...).";
+
+const MIN_SIMILARITY: f64 = 0.6;
+
+/// `Unit x.service could not be found.` from `status`, `Unit x.service not found.` from the
+/// actions that change state.
+fn get_missing_unit(command: &Command) -> Option<String> {
+    let unit_regex = Regex::new(r"Unit (\S+?)\.? (?:could not be found|not found)")
+        .expect("Hardcoded regex pattern should be valid");
+    unit_regex
+        .captures(command.output().stderr())
+        .map(|caps| caps[1].to_string())
+}
+
+/// The argument naming the missing unit, typed with or without its `.service` suffix.
+fn get_typed_unit<'a>(command: &'a Command, missing: &str) -> Option<&'a str> {
+    command
+        .parts()
+        .iter()
+        .skip(1)
+        .map(String::as_str)
+        .find(|part| *part == missing || format!("{part}.service") == missing)
+}
+
+/// Unit names from the first column of `systemctl list-units`/`list-unit-files` output.
+pub fn parse_units(raw: &str) -> Vec<String> {
+    raw.lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .filter(|unit| unit.contains('.'))
+        .map(str::to_string)
+        .collect()
+}
+
+fn get_units(command: &Command) -> Vec<String> {
+    let user = command.parts().iter().any(|part| part == "--user");
+    let mut units = vec![];
+    for listing in [["list-units", "--all"], ["list-unit-files", "--all"]] {
+        let mut systemctl = process::Command::new("systemctl");
+        if user {
+            systemctl.arg("--user");
+        }
+        let output = systemctl
+            .args(listing)
+            .args(["--no-legend", "--plain", "--no-pager"])
+            .output()
+            .ok()
+            .filter(|output| output.status.success());
+        if let Some(output) = output {
+            units.extend(parse_units(&String::from_utf8_lossy(&output.stdout)));
+        }
+    }
+    units
+}
+
+fn correct_unit(typed: &str, units: &[String]) -> Option<String> {
+    // Compare like with like: `ngnix` against `nginx`, `ngnix.timer` against `nginx.timer`.
+    let candidates = units.iter().filter_map(|unit| {
+        if typed.contains('.') {
+            Some(unit.as_str())
+        } else {
+            unit.strip_suffix(".service")
+        }
+    });
+    candidates
+        .filter(|candidate| *candidate != typed)
+        .map(|candidate| (misc::string_similarity(typed, candidate), candidate))
+        .filter(|(similarity, _)| *similarity >= MIN_SIMILARITY)
+        .max_by(|(a, _), (b, _)| a.total_cmp(b))
+        .map(|(_, candidate)| candidate.to_string())
+}
+
+fn fix_with(command: &Command, units: &[String]) -> Option<String> {
+    let missing = get_missing_unit(command)?;
+    let typed = get_typed_unit(command, &missing)?;
+    let corrected = correct_unit(typed, units)?;
+    Some(misc::replace_argument(command.command(), typed, &corrected))
+}
+
+fn is_systemctl_failure(command: &Command) -> bool {
+    command
+        .parts()
+        .first()
+        .is_some_and(|binary| binary == "systemctl")
+        && get_missing_unit(command)
+            .is_some_and(|missing| get_typed_unit(command, &missing).is_some())
+}
+
+pub fn is_match(command: &Command) -> bool {
+    is_systemctl_failure(command) && fix_with(command, &get_units(command)).is_some()
+}
+
+pub fn fix(command: &Command) -> String {
+    fix_with(command, &get_units(command)).unwrap_or_else(|| command.command().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIST_UNITS: &str = "\
+nginx.service                 loaded    active   running A high performance web server and a reverse proxy server
+postgresql.service            loaded    inactive dead    PostgreSQL RDBMS
+ssh.service                   loaded    active   running OpenBSD Secure Shell server
+logrotate.timer               loaded    active   waiting Daily rotation of log files
+";
+
+    #[test]
+    fn test_parse_units() {
+        assert_eq!(
+            parse_units(LIST_UNITS),
+            vec![
+                "nginx.service",
+                "postgresql.service",
+                "ssh.service",
+                "logrotate.timer"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_status_typo() {
+        let command = Command::failed(
+            "systemctl status ngnix",
+            "Unit ngnix.service could not be found.",
+        );
+        assert!(is_systemctl_failure(&command));
+        assert_eq!(
+            fix_with(&command, &parse_units(LIST_UNITS)).as_deref(),
+            Some("systemctl status nginx")
+        );
+    }
+
+    #[test]
+    fn test_restart_typo_keeps_action_and_suffix() {
+        let command = Command::failed(
+            "systemctl restart postgresq.service",
+            "Failed to restart postgresq.service: Unit postgresq.service not found.",
+        );
+        assert!(is_systemctl_failure(&command));
+        assert_eq!(
+            fix_with(&command, &parse_units(LIST_UNITS)).as_deref(),
+            Some("systemctl restart postgresql.service")
+        );
+        let command = Command::failed(
+            "systemctl start logrotat.timer",
+            "Failed to start logrotat.timer: Unit logrotat.timer not found.",
+        );
+        assert_eq!(
+            fix_with(&command, &parse_units(LIST_UNITS)).as_deref(),
+            Some("systemctl start logrotate.timer")
+        );
+    }
+
+    #[test]
+    fn test_no_match() {
+        let units = parse_units(LIST_UNITS);
+        let command = Command::failed(
+            "systemctl status docker",
+            "Unit docker.service could not be found.",
+        );
+        assert_eq!(fix_with(&command, &units), None);
+        let command = Command::failed("systemctl status nginx", "Active: active (running)");
+        assert!(!is_systemctl_failure(&command));
+        let command = Command::failed(
+            "service ngnix status",
+            "Unit ngnix.service could not be found.",
+        );
+        assert!(!is_systemctl_failure(&command));
+    }
+}
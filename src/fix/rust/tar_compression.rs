@@ -0,0 +1,171 @@
+use crate::fix::structs::Command;
+use crate::misc;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+pub const DESCRIPTION: &str = "Corrects the compression flag of tar. When tar fails because \
+    the archive is not in the format the flag says, picks -z, -j or -J from the archive's \
+    magic bytes or, failing that, its extension.";
+
+static PATTERNS: &[&str] = &[
+    "not in gzip format",
+    "is not a bzip2 file",
+    "file format not recognized",
+    "this does not look like a tar archive",
+];
+
+static COMPRESSION_FLAGS: &[char] = &['z', 'j', 'J'];
+
+/// Leading bytes of each compressed format, with the tar flag that reads it.
+static MAGIC: &[(&[u8], char)] = &[
+    (&[0x1f, 0x8b], 'z'),
+    (b"BZh", 'j'),
+    (&[0xfd, b'7', b'z', b'X', b'Z', 0x00], 'J'),
+];
+
+static EXTENSIONS: &[(&str, char)] = &[
+    (".tar.gz", 'z'),
+    (".tgz", 'z'),
+    (".tar.bz2", 'j'),
+    (".tbz2", 'j'),
+    (".tbz", 'j'),
+    (".tar.xz", 'J'),
+    (".txz", 'J'),
+];
+
+/// The option cluster carrying the compression flag, e.g. `-xzf` or old-style `xzf`.
+fn get_flag_cluster(command: &Command) -> Option<(&str, char)> {
+    command.parts().iter().skip(1).find_map(|part| {
+        let letters = part.strip_prefix('-').unwrap_or(part);
+        if part.starts_with("--") || !letters.chars().all(|c| c.is_ascii_alphabetic()) {
+            return None;
+        }
+        let flag = letters.chars().find(|c| COMPRESSION_FLAGS.contains(c))?;
+        Some((part.as_str(), flag))
+    })
+}
+
+/// The argument of `-f`, either after a cluster ending in `f` or after `--file`.
+fn get_archive(command: &Command) -> Option<&str> {
+    let parts = command.parts();
+    parts.iter().enumerate().skip(1).find_map(|(index, part)| {
+        if let Some(file) = part.strip_prefix("--file=") {
+            return Some(file);
+        }
+        let takes_file = part == "--file"
+            || (!part.starts_with("--")
+                && part.ends_with('f')
+                && part.chars().all(|c| c == '-' || c.is_ascii_alphabetic()));
+        takes_file
+            .then(|| parts.get(index + 1).map(String::as_str))
+            .flatten()
+    })
+}
+
+/// Magic bytes win over the extension, which may be lying.
+fn detect_flag(archive: &str, magic: Option<&[u8]>) -> Option<char> {
+    magic
+        .and_then(|magic| MAGIC.iter().find(|(bytes, _)| magic.starts_with(bytes)))
+        .map(|(_, flag)| *flag)
+        .or_else(|| {
+            EXTENSIONS
+                .iter()
+                .find(|(extension, _)| archive.ends_with(extension))
+                .map(|(_, flag)| *flag)
+        })
+}
+
+fn read_magic(path: &Path) -> Option<Vec<u8>> {
+    let mut magic = Vec::with_capacity(6);
+    File::open(path)
+        .ok()?
+        .take(6)
+        .read_to_end(&mut magic)
+        .ok()?;
+    Some(magic)
+}
+
+fn get_correct_flag(command: &Command) -> Option<char> {
+    let archive = get_archive(command)?;
+    let magic = read_magic(&command.cwd().join(misc::expand_path(archive)));
+    detect_flag(archive, magic.as_deref())
+}
+
+pub fn is_match(command: &Command) -> bool {
+    if command.parts().first().is_none_or(|binary| binary != "tar") {
+        return false;
+    }
+    let output = command.output().combined().to_lowercase();
+    if !PATTERNS.iter().any(|pattern| output.contains(pattern)) {
+        return false;
+    }
+    match (get_flag_cluster(command), get_correct_flag(command)) {
+        (Some((_, used)), Some(correct)) => used != correct,
+        _ => false,
+    }
+}
+
+pub fn fix(command: &Command) -> String {
+    match (get_flag_cluster(command), get_correct_flag(command)) {
+        (Some((cluster, used)), Some(correct)) => misc::replace_argument(
+            command.command(),
+            cluster,
+            &cluster.replacen(used, &correct.to_string(), 1),
+        ),
+        _ => command.command().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_gzip_flag_on_bz2_archive() {
+        let command = Command::failed(
+            "tar -xzf backup.tar.bz2",
+            "gzip: stdin: not in gzip format\ntar: Child returned status 1\ntar: Error is not recoverable: exiting now",
+        );
+        assert!(is_match(&command));
+        assert_eq!(fix(&command), "tar -xjf backup.tar.bz2");
+    }
+
+    #[test]
+    fn test_gzip_flag_on_xz_archive() {
+        let command = Command::failed(
+            "tar xzvf logs.tar.xz -C /tmp",
+            "gzip: stdin: not in gzip format\ntar: Child returned status 1",
+        );
+        assert!(is_match(&command));
+        assert_eq!(fix(&command), "tar xJvf logs.tar.xz -C /tmp");
+    }
+
+    #[test]
+    fn test_magic_overrides_extension() {
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        fs::write(
+            dir.path().join("release.tar.gz"),
+            [0xfd, b'7', b'z', b'X', b'Z', 0x00, 0x04],
+        )
+        .expect("Failed to write archive");
+        let command = Command::failed("tar -xzf release.tar.gz", "gzip: stdin: not in gzip format")
+            .with_cwd(dir.path().to_path_buf());
+        assert!(is_match(&command));
+        assert_eq!(fix(&command), "tar -xJf release.tar.gz");
+    }
+
+    #[test]
+    fn test_no_match() {
+        let command = Command::failed(
+            "tar -xjf backup.tar.bz2",
+            "tar: backup.tar.bz2: Cannot open: No such file or directory",
+        );
+        assert!(!is_match(&command));
+        let command = Command::failed("tar -xzf notes.txt", "gzip: stdin: not in gzip format");
+        assert!(!is_match(&command));
+        let command = Command::failed("tar -xf backup.tar.bz2", "gzip: stdin: not in gzip format");
+        assert!(!is_match(&command));
+    }
+}
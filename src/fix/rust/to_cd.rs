@@ -1,15 +1,37 @@
+use crate::config::Config;
 use crate::fix::structs::Command;
-use crate::misc;
+use std::sync::OnceLock;
+
+pub const DESCRIPTION: &str = "Fixes typos in the cd command. Replaces a mistyped \"cd\" such \
+    as \"cs\" with the correct command; more typos can be added under [to_cd] typos in the \
+    config.";
+
+/// Typos of `cd` recognized without any configuration.
+const DEFAULT_TYPOS: &[&str] = &["cs"];
+
+/// Typos added through `[to_cd] typos` in the config.
+fn configured_typos() -> &'static [String] {
+    static TYPOS: OnceLock<Vec<String>> = OnceLock::new();
+    TYPOS.get_or_init(|| {
+        Config::load()
+            .map(|config| config.cd_typos)
+            .unwrap_or_default()
+    })
+}
+
+fn is_typo(binary: &str, extra_typos: &[String]) -> bool {
+    DEFAULT_TYPOS.contains(&binary) || extra_typos.iter().any(|typo| typo == binary)
+}
+
+fn is_match_with(command: &Command, extra_typos: &[String]) -> bool {
+    command
+        .parts()
+        .first()
+        .is_some_and(|binary| is_typo(binary, extra_typos))
+}
 
 pub fn is_match(command: &Command) -> bool {
-    if !command.parts().is_empty()
-        && (command.parts()[0] == "cd"
-            || command.parts()[0].len() > 3
-            || command.parts()[0].len() < 2)
-    {
-        return false;
-    }
-    misc::string_similarity(&command.parts()[0], "cd") >= 0.5
+    is_match_with(command, configured_typos())
 }
 
 pub fn fix(command: &Command) -> String {
@@ -48,6 +70,26 @@ mod tests {
         assert!(!is_match(&command));
     }
 
+    #[test]
+    fn test_is_match_configured_typo() {
+        let command = Command::new(
+            "dc /some/directory".to_string(),
+            CommandOutput::new(String::new(), String::new()),
+        );
+        assert!(!is_match_with(&command, &[]));
+        assert!(is_match_with(&command, &["dc".to_string()]));
+        assert_eq!(fix(&command), "cd /some/directory");
+    }
+
+    #[test]
+    fn test_is_match_unconfigured_typo() {
+        let command = Command::new(
+            "vd /some/directory".to_string(),
+            CommandOutput::new(String::new(), String::new()),
+        );
+        assert!(!is_match_with(&command, &["dc".to_string()]));
+    }
+
     #[test]
     fn test_fix() {
         let command = Command::new(
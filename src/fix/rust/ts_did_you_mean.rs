@@ -0,0 +1,146 @@
+use crate::error::{AppError, AppResult};
+use crate::fix::structs::Command;
+use regex::Regex;
+
+pub const DESCRIPTION: &str = "Opens the source file of a TypeScript \"Did you mean\" error. \
+    Parses the file(line,col) location printed by tsc and suggests \"$EDITOR file:line\".";
+
+static PATTERN: &str =
+    r"(?m)^(\S+?)(?:\((\d+),\d+\)|:(\d+):\d+)\s*[:-]\s*error TS\d+:.*Did you mean '([^']+)'";
+
+pub fn is_match(command: &Command) -> bool {
+    command.parts().contains(&"tsc".to_string())
+        && command.output().combined().contains("Did you mean")
+}
+
+pub fn fix(command: &Command) -> AppResult<String> {
+    let re = Regex::new(PATTERN).map_err(|e| AppError::Other(format!("Invalid regex: {}", e)))?;
+    let output = command.output().combined();
+    let caps = re
+        .captures(&output)
+        .ok_or_else(|| AppError::Other("Expected a TypeScript error location".into()))?;
+    let file = &caps[1];
+    let line = caps
+        .get(2)
+        .or_else(|| caps.get(3))
+        .map(|m| m.as_str())
+        .ok_or_else(|| AppError::Other("Expected a line number in the error location".into()))?;
+    Ok(format!(
+        "$EDITOR {}",
+        shell_words::quote(&format!("{}:{}", file, line))
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fix::structs::{Command, CommandOutput};
+
+    #[test]
+    fn test_is_match_true() {
+        let command = Command::new(
+            "npx tsc".to_string(),
+            CommandOutput::new(
+                "src/index.ts(3,13): error TS2551: Property 'lenght' does not exist on type \
+                 'string'. Did you mean 'length'?"
+                    .to_string(),
+                String::new(),
+            ),
+        );
+        assert!(is_match(&command));
+    }
+
+    #[test]
+    fn test_is_match_without_suggestion() {
+        let command = Command::new(
+            "tsc".to_string(),
+            CommandOutput::new(
+                "src/index.ts(3,13): error TS2304: Cannot find name 'foo'.".to_string(),
+                String::new(),
+            ),
+        );
+        assert!(!is_match(&command));
+    }
+
+    #[test]
+    fn test_is_match_without_tsc() {
+        let command = Command::new(
+            "node index.js".to_string(),
+            CommandOutput::new(String::new(), "Did you mean 'length'?".to_string()),
+        );
+        assert!(!is_match(&command));
+    }
+
+    #[test]
+    fn test_is_match_without_tsc_location_format() {
+        // esbuild prints its own error layout that PATTERN can't parse.
+        let command = Command::new(
+            "esbuild src/app.ts".to_string(),
+            CommandOutput::new(
+                String::new(),
+                "✘ [ERROR] Could not resolve \"./utlis\". Did you mean \"./utils\"?".to_string(),
+            ),
+        );
+        assert!(!is_match(&command));
+    }
+
+    #[test]
+    fn test_fix_parenthesized_location() {
+        let command = Command::new(
+            "tsc".to_string(),
+            CommandOutput::new(
+                "src/index.ts(3,13): error TS2551: Property 'lenght' does not exist on type \
+                 'string'. Did you mean 'length'?"
+                    .to_string(),
+                String::new(),
+            ),
+        );
+        assert_eq!(
+            fix(&command).expect("Fix should open the error location"),
+            "$EDITOR src/index.ts:3"
+        );
+    }
+
+    #[test]
+    fn test_fix_pretty_location() {
+        let command = Command::new(
+            "tsc --pretty".to_string(),
+            CommandOutput::new(
+                "src/app.ts:12:7 - error TS2551: Property 'lenght' does not exist on type \
+                 'string'. Did you mean 'length'?"
+                    .to_string(),
+                String::new(),
+            ),
+        );
+        assert_eq!(
+            fix(&command).expect("Fix should open the error location"),
+            "$EDITOR src/app.ts:12"
+        );
+    }
+
+    #[test]
+    fn test_fix_quotes_location() {
+        let command = Command::new(
+            "tsc".to_string(),
+            CommandOutput::new(
+                "src/$(id)/app.ts(3,13): error TS2551: Property 'lenght' does not exist on type \
+                 'string'. Did you mean 'length'?"
+                    .to_string(),
+                String::new(),
+            ),
+        );
+        assert_eq!(
+            fix(&command).expect("Fix should open the error location"),
+            "$EDITOR 'src/$(id)/app.ts:3'"
+        );
+    }
+
+    #[test]
+    fn test_fix_without_location() {
+        let command = Command::new(
+            "tsc".to_string(),
+            CommandOutput::new("Did you mean 'length'?".to_string(), String::new()),
+        );
+        assert!(fix(&command).is_err());
+    }
+}
@@ -1,19 +1,16 @@
 use crate::fix::structs::Command;
 
+pub const DESCRIPTION: &str = "Removes sudo from commands that refuse to run as root. When \
+    the output says the operation cannot be performed as root, drops the leading sudo.";
+
 static PATTERNS: &[&str] = &["you cannot perform this operation as root"];
 pub fn is_match(command: &Command) -> bool {
     if !command.parts().is_empty() && command.parts()[0] != "sudo" {
         return false;
     }
 
-    for pattern in PATTERNS {
-        if command.output().stdout().to_lowercase().contains(pattern)
-            || command.output().stderr().to_lowercase().contains(pattern)
-        {
-            return true;
-        }
-    }
-    false
+    let output = command.output().combined().to_lowercase();
+    PATTERNS.iter().any(|pattern| output.contains(pattern))
 }
 
 pub fn fix(command: &Command) -> String {
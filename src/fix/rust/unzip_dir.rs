@@ -0,0 +1,77 @@
+use crate::fix::structs::Command;
+
+pub const DESCRIPTION: &str = "Extracts zip archives into their own directory. Matches a bare \
+    \"unzip <file>.zip\" without -d and adds \"-d <archive name>\".";
+
+pub fn is_match(command: &Command) -> bool {
+    command.parts().len() == 2
+        && command.parts()[0] == "unzip"
+        && command.parts()[1].ends_with(".zip")
+        && !command.parts().contains(&"-d".to_string())
+}
+
+pub fn fix(command: &Command) -> String {
+    let archive = &command.parts()[1];
+    let directory = archive.strip_suffix(".zip").unwrap_or(archive);
+    format!("{} -d {}", command.command(), shell_words::quote(directory))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fix::structs::{Command, CommandOutput};
+
+    #[test]
+    fn test_is_match_true() {
+        let command = Command::new(
+            "unzip archive.zip".to_string(),
+            CommandOutput::new(String::new(), String::new()),
+        );
+        assert!(is_match(&command));
+    }
+
+    #[test]
+    fn test_is_match_with_flag_d() {
+        let command = Command::new(
+            "unzip archive.zip -d out".to_string(),
+            CommandOutput::new(String::new(), String::new()),
+        );
+        assert!(!is_match(&command));
+    }
+
+    #[test]
+    fn test_is_match_with_flag_d_before_archive() {
+        let command = Command::new(
+            "unzip -d out archive.zip".to_string(),
+            CommandOutput::new(String::new(), String::new()),
+        );
+        assert!(!is_match(&command));
+    }
+
+    #[test]
+    fn test_is_match_not_zip() {
+        let command = Command::new(
+            "unzip archive.tar".to_string(),
+            CommandOutput::new(String::new(), String::new()),
+        );
+        assert!(!is_match(&command));
+    }
+
+    #[test]
+    fn test_fix() {
+        let command = Command::new(
+            "unzip archive.zip".to_string(),
+            CommandOutput::new(String::new(), String::new()),
+        );
+        assert_eq!(fix(&command), "unzip archive.zip -d archive");
+    }
+
+    #[test]
+    fn test_fix_with_spaces() {
+        let command = Command::new(
+            "unzip 'my archive.zip'".to_string(),
+            CommandOutput::new(String::new(), String::new()),
+        );
+        assert_eq!(fix(&command), "unzip 'my archive.zip' -d 'my archive'");
+    }
+}
@@ -0,0 +1,183 @@
+//! Sees through commands run via `env`, `nice`, `nohup`, `time` or `sudo`, so rules that look
+//! at the leading word match `nice -n 10 cargo biuld` like `cargo biuld`.
+use crate::fix::structs::Command;
+
+/// Wrappers and their options that take a separate argument.
+static WRAPPERS: &[(&str, &[&str])] = &[
+    (
+        "env",
+        &["-u", "--unset", "-C", "--chdir", "-S", "--split-string"],
+    ),
+    ("nice", &["-n", "--adjustment"]),
+    ("nohup", &[]),
+    ("time", &["-f", "--format", "-o", "--output"]),
+    (
+        "sudo",
+        &[
+            "-u",
+            "--user",
+            "-g",
+            "--group",
+            "-C",
+            "--close-from",
+            "-h",
+            "--host",
+            "-p",
+            "--prompt",
+            "-r",
+            "--role",
+            "-t",
+            "--type",
+            "-U",
+            "--other-user",
+            "-D",
+            "--chdir",
+        ],
+    ),
+];
+
+/// The wrapped command: the wrapper words in front of it and the command they run.
+pub struct Wrapped {
+    prefix: String,
+    inner: Command,
+}
+
+impl Wrapped {
+    pub fn inner(&self) -> &Command {
+        &self.inner
+    }
+
+    /// Puts the wrapper back around the fixed command. When the fix still contains the inner
+    /// command (`git init && git status`), only that occurrence is wrapped.
+    pub fn rewrap(&self, fixed: &str) -> String {
+        let inner = self.inner.command();
+        match fixed.find(inner) {
+            Some(start) if !inner.is_empty() => format!(
+                "{}{} {}{}",
+                &fixed[..start],
+                self.prefix,
+                inner,
+                &fixed[start + inner.len()..]
+            ),
+            _ => format!("{} {}", self.prefix, fixed),
+        }
+    }
+}
+
+/// Number of leading words that belong to wrappers, possibly nested (`sudo nice -n 5 ...`).
+fn wrapper_len(parts: &[String], strip_sudo: bool) -> usize {
+    let mut index = 0;
+    while let Some((wrapper, arg_options)) = parts.get(index).and_then(|word| {
+        WRAPPERS
+            .iter()
+            .find(|(wrapper, _)| wrapper == word && (strip_sudo || *wrapper != "sudo"))
+    }) {
+        index += 1;
+        while let Some(word) = parts.get(index) {
+            if word == "--" {
+                index += 1;
+                break;
+            } else if word.starts_with('-') {
+                index += if arg_options.contains(&word.as_str()) {
+                    2
+                } else {
+                    1
+                };
+            } else if *wrapper == "env" && word.contains('=') {
+                index += 1;
+            } else {
+                break;
+            }
+        }
+    }
+    index
+}
+
+/// Like `shell_words::quote`, but leaves `NAME=value` assignments readable.
+fn quote_word(word: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "-_=./:,+@%".contains(c);
+    if !word.is_empty() && word.chars().all(safe) {
+        word.to_string()
+    } else {
+        shell_words::quote(word).into_owned()
+    }
+}
+
+/// Returns the command without its wrappers, or `None` if it isn't wrapped. `sudo` is only
+/// stripped when `strip_sudo` is set, since some rules are about sudo itself.
+pub fn strip(command: &Command, strip_sudo: bool) -> Option<Wrapped> {
+    let parts = command.parts();
+    let len = wrapper_len(parts, strip_sudo);
+    if len == 0 || len >= parts.len() {
+        return None;
+    }
+    Some(Wrapped {
+        prefix: parts[..len]
+            .iter()
+            .map(|word| quote_word(word))
+            .collect::<Vec<_>>()
+            .join(" "),
+        inner: Command::new(shell_words::join(&parts[len..]), command.output().clone())
+            .with_cwd(command.cwd().to_path_buf()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fix::structs::CommandOutput;
+
+    fn wrapped_command(cmd: &str) -> Command {
+        Command::new(
+            cmd.to_string(),
+            CommandOutput::new(String::new(), "error".to_string()),
+        )
+    }
+
+    #[test]
+    fn test_strip_env() {
+        let wrapped = strip(&wrapped_command("env X=1 -u HOME brnach --all"), true)
+            .expect("Command should be wrapped");
+        assert_eq!(wrapped.inner().command(), "brnach --all");
+        assert_eq!(
+            wrapped.rewrap("branch --all"),
+            "env X=1 -u HOME branch --all"
+        );
+    }
+
+    #[test]
+    fn test_strip_nested_wrappers() {
+        let wrapped = strip(&wrapped_command("sudo -u www nice -n 10 cargo biuld"), true)
+            .expect("Command should be wrapped");
+        assert_eq!(wrapped.inner().command(), "cargo biuld");
+        assert_eq!(
+            wrapped.rewrap("cargo build"),
+            "sudo -u www nice -n 10 cargo build"
+        );
+    }
+
+    #[test]
+    fn test_rewrap_keeps_chained_fix_around_inner_command() {
+        let wrapped =
+            strip(&wrapped_command("nohup git status"), true).expect("Command should be wrapped");
+        assert_eq!(
+            wrapped.rewrap("git init && git status"),
+            "git init && nohup git status"
+        );
+    }
+
+    #[test]
+    fn test_sudo_is_kept_unless_requested() {
+        assert!(strip(&wrapped_command("sudo apt install x"), false).is_none());
+        let wrapped = strip(&wrapped_command("time sudo apt install x"), false)
+            .expect("Command should be wrapped");
+        assert_eq!(wrapped.inner().command(), "sudo apt install x");
+    }
+
+    #[test]
+    fn test_unwrapped_commands() {
+        assert!(strip(&wrapped_command("git status"), true).is_none());
+        assert!(strip(&wrapped_command("env"), true).is_none());
+        assert!(strip(&wrapped_command("env X=1"), true).is_none());
+    }
+}
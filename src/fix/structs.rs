@@ -1,7 +1,29 @@
 use crate::misc;
 use crossterm::terminal;
+use std::collections::HashMap;
+use std::env;
+use std::path::{Path, PathBuf};
 use std::process::Output;
 
+/// Confidence given to fixes from rules that don't report their own.
+pub const DEFAULT_CONFIDENCE: u8 = 50;
+
+/// Confidence of fallback rules, which only speak up when no other rule has a fix.
+pub const FALLBACK_CONFIDENCE: u8 = 0;
+
+/// Confidence of a Python, Lua or TOML rule named `name`: the config has the final say over what
+/// the rule declares for itself.
+pub fn rule_confidence(name: &str, declared: Option<u8>, priorities: &HashMap<String, u8>) -> u8 {
+    priorities
+        .get(name)
+        .copied()
+        .or(declared)
+        .unwrap_or(DEFAULT_CONFIDENCE)
+}
+
+/// How many KB of the start and of the end of each output stream rules get by default.
+pub const DEFAULT_OUTPUT_KEEP_KB: usize = 64;
+
 pub struct RawModeGuard;
 
 impl RawModeGuard {
@@ -17,14 +39,48 @@ impl Drop for RawModeGuard {
     }
 }
 
+/// Why a command failed, told from its exit code or the errno names some tools print (`EACCES`)
+/// rather than from messages that change with the user's locale.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Failure {
+    /// The shell couldn't find the command (exit code 127).
+    CommandNotFound,
+    /// The command was found but couldn't be executed (exit code 126).
+    NotExecutable,
+    /// `EACCES` or `EPERM`.
+    PermissionDenied,
+    /// `ENOENT`.
+    NoSuchFile,
+}
+
+#[derive(Clone)]
 pub struct CommandOutput {
     stdout: String,
     stderr: String,
+    exit_code: Option<i32>,
 }
 
 impl CommandOutput {
     pub fn new(stdout: String, stderr: String) -> Self {
-        CommandOutput { stdout, stderr }
+        CommandOutput {
+            stdout,
+            stderr,
+            exit_code: None,
+        }
+    }
+
+    pub fn with_exit_code(mut self, exit_code: i32) -> Self {
+        self.exit_code = Some(exit_code);
+        self
+    }
+
+    /// Keeps only the first and last `keep` bytes of each stream, with a note about what was cut
+    /// in between, so a command that dumped megabytes doesn't slow every rule down. Messages
+    /// rules look for are almost always at the start or the end.
+    pub fn truncated(mut self, keep: usize) -> Self {
+        self.stdout = truncate_middle(self.stdout, keep);
+        self.stderr = truncate_middle(self.stderr, keep);
+        self
     }
 
     pub fn stdout(&self) -> &str {
@@ -34,13 +90,62 @@ impl CommandOutput {
     pub fn stderr(&self) -> &str {
         &self.stderr
     }
+
+    /// Both streams joined, for rules that don't care where a message was printed.
+    pub fn combined(&self) -> String {
+        match (self.stdout.is_empty(), self.stderr.is_empty()) {
+            (_, true) => self.stdout.clone(),
+            (true, false) => self.stderr.clone(),
+            (false, false) => format!("{}\n{}", self.stdout, self.stderr),
+        }
+    }
+
+    pub fn exit_code(&self) -> Option<i32> {
+        self.exit_code
+    }
+
+    /// Locale-independent counterpart to matching English messages; rules check both.
+    pub fn failed_with(&self, failure: Failure) -> bool {
+        match failure {
+            Failure::CommandNotFound => self.exit_code == Some(127),
+            Failure::NotExecutable => self.exit_code == Some(126),
+            Failure::PermissionDenied => self.mentions_errno(&["EACCES", "EPERM"]),
+            Failure::NoSuchFile => self.mentions_errno(&["ENOENT"]),
+        }
+    }
+
+    fn mentions_errno(&self, names: &[&str]) -> bool {
+        [&self.stdout, &self.stderr].iter().any(|stream| {
+            stream
+                .split(|c: char| !c.is_ascii_alphanumeric())
+                .any(|word| names.contains(&word))
+        })
+    }
+}
+
+fn truncate_middle(text: String, keep: usize) -> String {
+    if text.len() <= keep.saturating_mul(2) {
+        return text;
+    }
+    let head_end = text.floor_char_boundary(keep);
+    let tail_start = text.ceil_char_boundary(text.len() - keep);
+    format!(
+        "{}\n[... {} bytes truncated ...]\n{}",
+        &text[..head_end],
+        tail_start - head_end,
+        &text[tail_start..]
+    )
 }
 
 impl From<Output> for CommandOutput {
     fn from(output: Output) -> Self {
         let stdout = String::from_utf8_lossy(&output.stdout).to_string();
         let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-        CommandOutput { stdout, stderr }
+        CommandOutput {
+            stdout,
+            stderr,
+            exit_code: output.status.code(),
+        }
     }
 }
 
@@ -48,18 +153,36 @@ pub struct Command {
     command: String,
     parts: Vec<String>,
     output: CommandOutput,
+    cwd: PathBuf,
 }
 
 impl Command {
+    /// Creates a command that ran in the current directory; use [`Command::with_cwd`] when it
+    /// ran elsewhere.
     pub fn new(command: String, output: CommandOutput) -> Self {
         let parts = misc::split_command(&command);
         Command {
             command,
             parts,
             output,
+            cwd: env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
         }
     }
 
+    /// A command that printed `stderr` and nothing on stdout, for tests.
+    #[cfg(test)]
+    pub fn failed(command: &str, stderr: &str) -> Self {
+        Command::new(
+            command.to_string(),
+            CommandOutput::new(String::new(), stderr.to_string()),
+        )
+    }
+
+    pub fn with_cwd(mut self, cwd: PathBuf) -> Self {
+        self.cwd = cwd;
+        self
+    }
+
     pub fn command(&self) -> &str {
         &self.command
     }
@@ -71,6 +194,10 @@ impl Command {
     pub fn output(&self) -> &CommandOutput {
         &self.output
     }
+
+    pub fn cwd(&self) -> &Path {
+        &self.cwd
+    }
 }
 
 #[cfg(test)]
@@ -78,6 +205,42 @@ mod tests {
     use super::*;
     use crossterm::terminal;
 
+    #[test]
+    fn test_rule_confidence() {
+        let priorities = HashMap::from([("git_push".to_string(), 90)]);
+        assert_eq!(rule_confidence("git_push", Some(10), &priorities), 90);
+        assert_eq!(rule_confidence("other", Some(10), &priorities), 10);
+        assert_eq!(
+            rule_confidence("other", None, &priorities),
+            DEFAULT_CONFIDENCE
+        );
+    }
+
+    #[test]
+    fn test_failed_with_exit_codes() {
+        let output = CommandOutput::new(String::new(), "zsh: Befehl nicht gefunden: sl".into());
+        assert!(!output.failed_with(Failure::CommandNotFound));
+        let output = output.with_exit_code(127);
+        assert!(output.failed_with(Failure::CommandNotFound));
+        assert!(!output.failed_with(Failure::NotExecutable));
+        let output = CommandOutput::new(String::new(), String::new()).with_exit_code(126);
+        assert!(output.failed_with(Failure::NotExecutable));
+    }
+
+    #[test]
+    fn test_failed_with_errno_names() {
+        let output = CommandOutput::new(
+            String::new(),
+            "npm ERR! code EACCES\nnpm ERR! syscall mkdir".into(),
+        );
+        assert!(output.failed_with(Failure::PermissionDenied));
+        assert!(!output.failed_with(Failure::NoSuchFile));
+        let output = CommandOutput::new("Error: ENOENT: open 'x'".into(), String::new());
+        assert!(output.failed_with(Failure::NoSuchFile));
+        let output = CommandOutput::new("EPERMISSIVE mode".into(), String::new());
+        assert!(!output.failed_with(Failure::PermissionDenied));
+    }
+
     #[test]
     fn raw_mode_guard_enables_raw_mode_on_creation() {
         let _guard = RawModeGuard::new();
@@ -110,6 +273,32 @@ mod tests {
         let output = CommandOutput::from(process_output);
         assert_eq!(output.stdout(), "test stdout");
         assert_eq!(output.stderr(), "test stderr");
+        assert_eq!(output.exit_code(), Some(0));
+    }
+
+    #[test]
+    fn test_command_output_truncated() {
+        let stderr = format!("error: first\n{}\nerror: last", "x".repeat(10_000));
+        let output = CommandOutput::new("short".to_string(), stderr).truncated(1024);
+        assert_eq!(output.stdout(), "short");
+        assert!(output.stderr().len() < 2 * 1024 + 64);
+        assert!(output.stderr().starts_with("error: first\n"));
+        assert!(output.stderr().ends_with("\nerror: last"));
+        assert!(output.stderr().contains("bytes truncated"));
+    }
+
+    #[test]
+    fn test_command_output_truncated_on_char_boundary() {
+        let output = CommandOutput::new("é".repeat(100), String::new()).truncated(3);
+        assert!(output.stdout().starts_with("é\n[..."));
+        assert!(output.stdout().ends_with("...]\né"));
+    }
+
+    #[test]
+    fn test_command_output_with_exit_code() {
+        let output = CommandOutput::new(String::new(), String::new());
+        assert_eq!(output.exit_code(), None);
+        assert_eq!(output.with_exit_code(127).exit_code(), Some(127));
     }
 
     #[test]
@@ -137,4 +326,28 @@ mod tests {
         assert_eq!(command.command(), "");
         assert!(command.parts().is_empty());
     }
+
+    #[test]
+    fn test_command_output_combined() {
+        let both = CommandOutput::new("out".to_string(), "err".to_string());
+        assert_eq!(both.combined(), "out\nerr");
+        let stdout_only = CommandOutput::new("out".to_string(), String::new());
+        assert_eq!(stdout_only.combined(), "out");
+        let stderr_only = CommandOutput::new(String::new(), "err".to_string());
+        assert_eq!(stderr_only.combined(), "err");
+    }
+
+    #[test]
+    fn test_command_cwd() {
+        let output = CommandOutput::new(String::new(), String::new());
+        let command = Command::new("ls".to_string(), output);
+        assert_eq!(
+            command.cwd(),
+            env::current_dir().expect("Current dir should be readable")
+        );
+
+        let output = CommandOutput::new(String::new(), String::new());
+        let command = Command::new("ls".to_string(), output).with_cwd(PathBuf::from("/srv"));
+        assert_eq!(command.cwd(), Path::new("/srv"));
+    }
 }
@@ -0,0 +1,264 @@
+//! Declarative rules read from `*.toml` files in the rules directory. Each `[section]` is one
+//! rule named after the section:
+//!
+//! ```toml
+//! [git_psuh]
+//! match_command = '^git psuh(.*)$'
+//! replace = 'git push{1}'
+//! ```
+//!
+//! `match_command` is checked against the command and `match_stderr` against its stderr; at
+//! least one is required and every one given has to match. `replace` may reference
+//! `{command}`, numbered groups (looked up in `match_command` first, then `match_stderr`) and
+//! named groups from either regex.
+use super::python::{partition_secure_rules, security_summary};
+use super::structs::{Command, rule_confidence};
+use crate::config::{self, Value};
+use crate::error::{AppError, AppResult};
+use crate::misc::log::{debug, diagnostic};
+use crate::misc::theme;
+use regex::{Captures, Regex};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug)]
+struct TomlRule {
+    name: String,
+    match_command: Option<Regex>,
+    match_stderr: Option<Regex>,
+    replace: String,
+    priority: Option<u8>,
+}
+
+impl TomlRule {
+    fn fix(&self, command: &Command) -> Option<String> {
+        let mut captures = vec![];
+        if let Some(regex) = &self.match_command {
+            captures.push((regex, regex.captures(command.command())?));
+        }
+        if let Some(regex) = &self.match_stderr {
+            captures.push((regex, regex.captures(command.output().stderr())?));
+        }
+        Some(expand_template(&self.replace, command.command(), &captures))
+    }
+}
+
+pub fn process_toml_rules(
+    command: &Command,
+    mut rule_paths: Vec<PathBuf>,
+    priorities: &HashMap<String, u8>,
+    verbose: bool,
+) -> AppResult<Vec<(String, u8)>> {
+    rule_paths.sort();
+    let (rule_paths, skipped) = partition_secure_rules(rule_paths, verbose);
+    let mut fixed_commands = vec![];
+    for rule_path in rule_paths {
+        let rules = match load_rules(&rule_path) {
+            Ok(rules) => rules,
+            Err(e) => {
+                diagnostic!(
+                    "{}{}{}",
+                    theme::warning("Failed to load TOML rules '"),
+                    rule_path.display(),
+                    theme::warning("': "),
+                );
+                diagnostic!("{e}");
+                continue;
+            }
+        };
+        for rule in rules {
+            let fixed = rule.fix(command);
+            debug!(
+                "toml rule {}: {}",
+                rule.name,
+                if fixed.is_some() {
+                    "matched"
+                } else {
+                    "no match"
+                }
+            );
+            if let Some(fixed) = fixed {
+                let confidence = rule_confidence(&rule.name, rule.priority, priorities);
+                fixed_commands.push((fixed, confidence));
+            }
+        }
+    }
+    if let Some(summary) = security_summary(&skipped, verbose) {
+        diagnostic!("{summary}");
+    }
+    Ok(fixed_commands)
+}
+
+fn load_rules(path: &Path) -> AppResult<Vec<TomlRule>> {
+    parse_rules(&fs::read_to_string(path)?)
+}
+
+fn parse_rules(raw: &str) -> AppResult<Vec<TomlRule>> {
+    let mut fields: Vec<(String, HashMap<String, Value>)> = vec![];
+    for (key, value) in config::parse_toml(raw)? {
+        let (name, field) = key
+            .rsplit_once('.')
+            .ok_or_else(|| AppError::Config(format!("'{key}' must be inside a [rule] section")))?;
+        match fields.iter_mut().find(|(rule, _)| rule == name) {
+            Some((_, rule_fields)) => {
+                rule_fields.insert(field.to_string(), value);
+            }
+            None => fields.push((
+                name.to_string(),
+                HashMap::from([(field.to_string(), value)]),
+            )),
+        }
+    }
+    fields
+        .into_iter()
+        .map(|(name, mut rule_fields)| {
+            let mut regex = |field: &str| -> AppResult<Option<Regex>> {
+                let key = format!("{name}.{field}");
+                rule_fields
+                    .remove(field)
+                    .map(|value| {
+                        let pattern = config::expect_string(&key, value)?;
+                        Regex::new(&pattern)
+                            .map_err(|e| AppError::Config(format!("'{key}' is invalid: {e}")))
+                    })
+                    .transpose()
+            };
+            let match_command = regex("match_command")?;
+            let match_stderr = regex("match_stderr")?;
+            if match_command.is_none() && match_stderr.is_none() {
+                return Err(AppError::Config(format!(
+                    "rule '{name}' needs match_command or match_stderr"
+                )));
+            }
+            let replace = rule_fields
+                .remove("replace")
+                .ok_or_else(|| AppError::Config(format!("rule '{name}' needs replace")))
+                .and_then(|value| config::expect_string(&format!("{name}.replace"), value))?;
+            let priority = rule_fields
+                .remove("priority")
+                .map(|value| config::expect_priority(&format!("{name}.priority"), value))
+                .transpose()?;
+            Ok(TomlRule {
+                name,
+                match_command,
+                match_stderr,
+                replace,
+                priority,
+            })
+        })
+        .collect()
+}
+
+/// Fills in `{command}` and `{group}` placeholders; unknown placeholders are kept as written
+/// and groups that didn't participate in the match become empty.
+fn expand_template(template: &str, command: &str, captures: &[(&Regex, Captures)]) -> String {
+    let mut expanded = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        expanded.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('}').map(|end| start + end) else {
+            rest = &rest[start..];
+            break;
+        };
+        match lookup(&rest[start + 1..end], command, captures) {
+            Some(value) => expanded.push_str(&value),
+            None => expanded.push_str(&rest[start..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+fn lookup(placeholder: &str, command: &str, captures: &[(&Regex, Captures)]) -> Option<String> {
+    if placeholder == "command" {
+        return Some(command.to_string());
+    }
+    let index = placeholder.parse::<usize>();
+    let (_, caps) = captures.iter().find(|(regex, _)| match index {
+        Ok(index) => index < regex.captures_len(),
+        Err(_) => regex
+            .capture_names()
+            .flatten()
+            .any(|name| name == placeholder),
+    })?;
+    let group = match index {
+        Ok(index) => caps.get(index),
+        Err(_) => caps.name(placeholder),
+    };
+    Some(group.map_or_else(String::new, |group| group.as_str().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rule_corrects_command_via_capture() {
+        let rules = parse_rules(
+            r#"
+[git_psuh]
+match_command = '^git psuh(.*)$'
+match_stderr = "'psuh' is not a git command"
+replace = 'git push{1}'
+"#,
+        )
+        .expect("Rules should parse");
+        let command = Command::failed(
+            "git psuh origin main",
+            "git: 'psuh' is not a git command. See 'git --help'.",
+        );
+        assert_eq!(
+            rules[0].fix(&command).as_deref(),
+            Some("git push origin main")
+        );
+        let command = Command::failed("git psuh", "Everything up-to-date");
+        assert_eq!(rules[0].fix(&command), None);
+    }
+
+    #[test]
+    fn test_rule_with_stderr_capture_and_command() {
+        let rules = parse_rules(
+            r#"
+[docker_daemon]
+match_stderr = 'Cannot connect to the Docker daemon at (?P<socket>\S+)\.'
+replace = 'sudo systemctl start docker && {command} # {socket} {unknown}'
+priority = 80
+"#,
+        )
+        .expect("Rules should parse");
+        let command = Command::failed(
+            "docker ps",
+            "Cannot connect to the Docker daemon at unix:///var/run/docker.sock. Is it running?",
+        );
+        assert_eq!(
+            rules[0].fix(&command).as_deref(),
+            Some(
+                "sudo systemctl start docker && docker ps # unix:///var/run/docker.sock {unknown}"
+            )
+        );
+        assert_eq!(rules[0].priority, Some(80));
+    }
+
+    #[test]
+    fn test_invalid_rules_are_rejected() {
+        assert!(parse_rules("[a]\nreplace = 'x'").is_err());
+        assert!(parse_rules("[a]\nmatch_command = 'x'").is_err());
+        assert!(parse_rules("[a]\nmatch_command = '('\nreplace = 'x'").is_err());
+        assert!(parse_rules("replace = 'x'").is_err());
+    }
+
+    #[test]
+    fn test_process_toml_rules_uses_configured_priority() {
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let path = dir.path().join("rules.toml");
+        fs::write(&path, "[ls_typo]\nmatch_command = '^sl$'\nreplace = 'ls'\n")
+            .expect("Failed to write rules");
+        let command = Command::failed("sl", "sl: command not found");
+        let priorities = HashMap::from([("ls_typo".to_string(), 90)]);
+        let fixes =
+            process_toml_rules(&command, vec![path], &priorities, false).expect("Rules should run");
+        assert_eq!(fixes, vec![("ls".to_string(), 90)]);
+    }
+}
@@ -2,30 +2,44 @@
 //!
 //! See [README](https://github.com/AsfhtgkDavid/theshit) for more details.
 mod cli;
+mod config;
 mod error;
 mod fix;
 mod misc;
 mod shells;
 
 use anyhow::{Context, Result};
-use clap::Parser;
-use cli::{Cli, Command};
-use crossterm::style::Stylize;
+use cli::{Cli, Command, RulesAction};
+use misc::log::debug;
+use misc::profile::Stage;
+use misc::theme;
 use std::env;
-use std::io::ErrorKind;
+use std::io::{self, ErrorKind};
+use std::process::ExitCode;
 use std::str::FromStr;
+use std::time::Duration;
 
-fn main() -> Result<()> {
+fn main() -> Result<ExitCode> {
     #[cfg(not(feature = "standard_panic"))]
     misc::set_panic_hook();
 
-    let args = Cli::parse();
+    let args = Cli::parse_with_build_info();
+    misc::log::set_quiet(
+        args.quiet || misc::log::flag_from_env(env::var("THESHIT_QUIET").ok().as_deref()),
+    );
+    misc::log::set_debug(misc::log::flag_from_env(
+        env::var("THESHIT_DEBUG").ok().as_deref(),
+    ));
 
-    let shell = args
-        .shell
-        .and_then(|shell| shells::Shell::from_str(&shell).ok())
-        .or_else(shells::get_current_shell)
-        .context("Could not determine the current shell.")?;
+    misc::profile::set_profile(matches!(args.command, Command::Fix { profile: true, .. }));
+
+    let shell = misc::profile::timed(Stage::ShellDetection, || {
+        args.shell
+            .and_then(|shell| shells::Shell::from_str(&shell).ok())
+            .or_else(shells::get_current_shell)
+    })
+    .context("Could not determine the current shell.")?;
+    debug!("shell: {shell:?}");
 
     match args.command {
         Command::Alias { name } => {
@@ -34,44 +48,220 @@ fn main() -> Result<()> {
             let alias = shell.get_shell_function(&name, program_path.as_path());
             println!("{alias}");
         }
-        Command::Fix => {
-            let command =
-                env::var("SH_PREV_CMD").context("SH_PREV_CMD environment variable is not set.")?;
-            let expand_command = misc::expand_aliases(&command, shell.get_aliases())
-                .context("Failed to expand aliases")?;
-            let fixed_command =
-                fix::fix_command(command, expand_command).context("Failed to fix command")?;
-            println!("{fixed_command}");
+        Command::Fix {
+            command,
+            stdout,
+            stderr,
+            exit_code,
+            history_depth,
+            verbose,
+            profile: _,
+            mode,
+            format,
+            current,
+        } => {
+            let config = config::Config::load().context("Failed to load config")?;
+            if current {
+                return fix_current(command.unwrap_or_default(), format, &config);
+            }
+            let mode = mode.or(config.mode).unwrap_or_default();
+            let command = previous_command(command)?;
+            let mut commands = vec![command.clone()];
+            if history_depth > 1 {
+                let history = misc::parse_history(&env::var("SH_HISTORY").unwrap_or_default());
+                commands.extend(history.into_iter().skip(1).take(history_depth as usize - 1));
+            }
+            let mut output =
+                (stdout.is_some() || stderr.is_some() || exit_code.is_some()).then(|| {
+                    let output = fix::CommandOutput::new(
+                        stdout.unwrap_or_default(),
+                        stderr.unwrap_or_default(),
+                    );
+                    match exit_code {
+                        Some(code) => output.with_exit_code(code),
+                        None => output,
+                    }
+                });
+            let aliases = misc::profile::timed(Stage::AliasExpansion, || shell.get_aliases());
+            let cwd = env::current_dir().context("Could not determine the current directory.")?;
+            let found = fix::find_first_fixable(
+                commands.into_iter().enumerate().collect(),
+                |(index, command)| -> Result<_> {
+                    let command = misc::normalize_command(command)
+                        .context("Failed to read previous command")?;
+                    if *index > 0 {
+                        // Older entries are never run again just to see their output (they may
+                        // well have been `rm` or `git push`), so only rules that need no output apply.
+                        return fix::get_current_fixes(command, cwd.clone(), &config)
+                            .context("Failed to fix command");
+                    }
+                    let expand_command = misc::profile::timed(Stage::AliasExpansion, || {
+                        misc::expand_aliases(&command, aliases.clone())
+                    })
+                    .context("Failed to expand aliases")?;
+                    debug!("command: {command}, expanded: {expand_command}");
+                    // Provided output only describes the most recent command.
+                    fix::get_fixed_commands(
+                        command,
+                        expand_command,
+                        output.take(),
+                        cwd.clone(),
+                        &config,
+                        verbose,
+                    )
+                    .context("Failed to fix command")
+                },
+            )?;
+            misc::profile::report();
+            let ((index, source), fixed_commands) = found.unwrap_or(((0, command), vec![]));
+            let mode = if index > 0 {
+                eprintln!(
+                    "{} {source}",
+                    theme::warning("No fix for the last command, this fixes an earlier one:")
+                );
+                // Running a fix for another command unasked would be a surprise.
+                match mode {
+                    config::Mode::Auto => config::Mode::Confirm,
+                    mode => mode,
+                }
+            } else {
+                mode
+            };
+            let emitted = fix::emit_fixed_commands(
+                mode,
+                format,
+                &source,
+                fixed_commands,
+                config.auto_confirm_timeout.map(Duration::from_secs),
+                &mut io::stdout(),
+                &mut io::stderr(),
+            )
+            .context("Failed to print fixed commands")?;
+            if !emitted {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        Command::Rules {
+            action: RulesAction::Explain { name },
+        } => {
+            let description = fix::describe_native_rule(&name)
+                .with_context(|| format!("'{name}' is not a native rule."))?;
+            println!("{name}: {description}");
+        }
+        Command::TestRule {
+            name,
+            command,
+            stdout,
+            stderr,
+            exit_code,
+        } => {
+            let output = fix::CommandOutput::new(stdout, stderr);
+            let output = match exit_code {
+                Some(code) => output.with_exit_code(code),
+                None => output,
+            };
+            let cwd = env::current_dir().context("Could not determine the current directory.")?;
+            let fixed_commands = fix::test_rule(&name, command, output, cwd, true)
+                .with_context(|| format!("Failed to run rule '{name}'"))?;
+            if fixed_commands.is_empty() {
+                println!("{name}: no match");
+                return Ok(ExitCode::FAILURE);
+            }
+            println!("{name}: matched");
+            for fixed_command in fixed_commands {
+                println!("  {fixed_command}");
+            }
+        }
+        Command::Shells => {
+            for supported in shells::Shell::all() {
+                println!(
+                    "{supported}: {}{}",
+                    if supported.is_set_up() {
+                        "set up"
+                    } else {
+                        "not set up"
+                    },
+                    if supported.as_str() == shell.as_str() {
+                        " (current)"
+                    } else {
+                        ""
+                    }
+                );
+            }
+        }
+        Command::Completions { shell } => {
+            print!("{}", shell.get_completions(&cli::completion_spec()));
+        }
+        Command::Sudo => {
+            let command = misc::normalize_command(&previous_command(None)?)
+                .context("Failed to read previous command")?;
+            fix::write_command(&mut io::stdout(), &misc::sudo_command(&command))?;
         }
-        Command::Setup { name } => {
+        Command::Setup { name, replace } => {
             let program_path =
                 env::current_exe().context("Could not determine the current executable path.")?;
-            match shell.setup_alias(&name, program_path.as_path()) {
+            match shell.setup_alias(&name, program_path.as_path(), replace) {
                 Ok(_) => println!(
                     "{}",
-                    format!("Alias setup successfully for {shell:?} as {name}").green()
+                    theme::success(format!("Alias setup successfully for {shell} as {name}"))
                 ),
                 Err(e) if e.kind() == ErrorKind::AlreadyExists => {
-                    println!("{}", "Alias already exists, skipping alias setup.".yellow());
+                    println!(
+                        "{}",
+                        theme::warning(
+                            "Alias already exists, skipping alias setup (use --replace to rewrite it)."
+                        )
+                    );
                 }
                 Err(e) => return Err(e).context("Failed to set up alias")?,
             }
-            match dirs::config_dir()
+            match misc::get_rules_dir()
                 .ok_or_else(|| {
                     std::io::Error::new(ErrorKind::NotFound, "Config directory not found")
                 })
-                .and_then(|dir| misc::create_default_fix_rules(dir.join("theshit/fix_rules")))
+                .and_then(misc::create_default_fix_rules)
             {
-                Ok(_) => println!("{}", "Default rules setup successfully".green()),
+                Ok(_) => println!("{}", theme::success("Default rules setup successfully")),
                 Err(e) if e.kind() == ErrorKind::AlreadyExists => {
                     println!(
                         "{}",
-                        "Default rules already exist, skipping rules setup.".yellow()
+                        theme::warning("Default rules already exist, skipping rules setup.")
                     );
                 }
                 Err(e) => return Err(e).context("Failed to set up default rules")?,
             }
         }
     }
-    Ok(())
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Prints the top fix for the command being typed, for the shell to put back in its buffer.
+fn fix_current(
+    command: String,
+    format: fix::OutputFormat,
+    config: &config::Config,
+) -> Result<ExitCode> {
+    let command =
+        misc::normalize_command(&command).context("Failed to read the current command")?;
+    let cwd = env::current_dir().context("Could not determine the current directory.")?;
+    let fixed_commands =
+        fix::get_current_fixes(command, cwd, config).context("Failed to fix command")?;
+    let fixed = fixed_commands
+        .iter()
+        .find(|fixed| format == fix::OutputFormat::Shell || misc::split_argv(fixed).is_some());
+    match fixed {
+        Some(fixed) => {
+            fix::write_fix(&mut io::stdout(), format, fixed)?;
+            Ok(ExitCode::SUCCESS)
+        }
+        None => Ok(ExitCode::FAILURE),
+    }
+}
+
+/// Returns the explicitly given command, falling back to the shell-provided `SH_PREV_CMD`.
+fn previous_command(command: Option<String>) -> Result<String> {
+    match command {
+        Some(command) => Ok(command),
+        None => env::var("SH_PREV_CMD").context("SH_PREV_CMD environment variable is not set."),
+    }
 }
@@ -1,30 +1,141 @@
+pub mod log;
+pub mod pipe;
+pub mod profile;
+pub mod theme;
+
 use crate::error::{AppError, AppResult};
-#[cfg(not(feature = "standard_panic"))]
-use crossterm::style::Stylize;
 use include_dir::{Dir, DirEntry, include_dir};
 use regex::Regex;
+use std::any::Any;
 use std::cmp::{max, min};
 use std::collections::HashMap;
+use std::ffi::OsString;
 use std::fs;
 use std::io::{self, ErrorKind, Result as IoResult};
 use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 static ASSETS_DIR: Dir = include_dir!("$CARGO_MANIFEST_DIR/assets");
 
+static SECRET_PATTERNS: &[&str] = &[
+    r#"(?i)((?:token|secret|password|passwd|api[_-]?key)[=:])[^\s'"]+"#,
+    r#"(?i)(--(?:token|secret|password|passwd|api-key)[ =])[^\s'"]+"#,
+    r#"(?i)(bearer )[^\s'"]+"#,
+    r"(gh[pousr]_)[A-Za-z0-9]+",
+    r"(AKIA)[0-9A-Z]{16}",
+];
+
 #[cfg(not(feature = "standard_panic"))]
 pub fn set_panic_hook() {
     std::panic::set_hook(Box::new(|info| {
-        let msg = info
-            .payload()
-            .downcast_ref::<&str>()
-            .map(|s| *s)
-            .or_else(|| info.payload().downcast_ref::<String>().map(|s| &**s))
-            .unwrap_or("Unknown panic");
-        eprintln!("Panic occurred: {}", msg.red());
+        let msg = panic_message(info.payload());
+        let backtrace = std::backtrace::Backtrace::capture();
+        let backtrace = (backtrace.status() == std::backtrace::BacktraceStatus::Captured)
+            .then(|| backtrace.to_string());
+        let report = build_crash_report(
+            msg,
+            info.location()
+                .map(|location| location.to_string())
+                .as_deref(),
+            backtrace.as_deref(),
+            std::env::var("SH_SHELL").ok().as_deref(),
+            std::env::var("SH_PREV_CMD").ok().as_deref(),
+        );
+        eprintln!("Panic occurred: {}", theme::error(msg));
+        match get_cache_dir().and_then(|dir| write_crash_report(&dir, &report)) {
+            Ok(path) => eprintln!(
+                "{} {}",
+                theme::warning("A crash report was saved to"),
+                path.display()
+            ),
+            Err(e) => eprintln!("{}: {}", theme::warning("Failed to write crash report"), e),
+        }
         std::process::exit(1);
     }));
 }
 
+#[cfg_attr(feature = "standard_panic", allow(dead_code))]
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(|s| s.as_str()))
+        .unwrap_or("Unknown panic")
+}
+
+#[cfg_attr(feature = "standard_panic", allow(dead_code))]
+fn redact_secrets(command: &str) -> String {
+    SECRET_PATTERNS
+        .iter()
+        .fold(command.to_string(), |redacted, pattern| {
+            Regex::new(pattern)
+                .expect("Hardcoded regex pattern should be valid")
+                .replace_all(&redacted, "${1}<redacted>")
+                .to_string()
+        })
+}
+
+#[cfg_attr(feature = "standard_panic", allow(dead_code))]
+fn build_crash_report(
+    message: &str,
+    location: Option<&str>,
+    backtrace: Option<&str>,
+    shell: Option<&str>,
+    command: Option<&str>,
+) -> String {
+    let mut report = format!(
+        "theshit {} crashed\n\nmessage: {}\nlocation: {}\nshell: {}\ncommand: {}\n",
+        env!("CARGO_PKG_VERSION"),
+        message,
+        location.unwrap_or("unknown"),
+        shell.unwrap_or("unknown"),
+        command.map(redact_secrets).unwrap_or_default()
+    );
+    if let Some(backtrace) = backtrace {
+        report.push_str("\nbacktrace:\n");
+        report.push_str(backtrace);
+    }
+    report
+}
+
+#[cfg_attr(feature = "standard_panic", allow(dead_code))]
+fn write_crash_report(dir: &Path, report: &str) -> IoResult<PathBuf> {
+    fs::create_dir_all(dir)?;
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    let path = dir.join(format!("crash-{timestamp}.log"));
+    fs::write(&path, report)?;
+    Ok(path)
+}
+
+/// Where caches go: `$XDG_CACHE_HOME` when set to an absolute path, otherwise the platform
+/// cache directory.
+fn resolve_cache_base(xdg_cache_home: Option<OsString>) -> Option<PathBuf> {
+    xdg_cache_home
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(dirs::cache_dir)
+}
+
+/// Creates `dir` (and missing parents) readable only by the owner, if it doesn't exist yet.
+fn create_private_dir(dir: PathBuf) -> IoResult<PathBuf> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(&dir)?;
+    Ok(dir)
+}
+
+/// The `theshit` cache directory, created on first use. Everything that caches goes here.
+pub fn get_cache_dir() -> IoResult<PathBuf> {
+    let base = resolve_cache_base(std::env::var_os("XDG_CACHE_HOME"))
+        .ok_or_else(|| io::Error::new(ErrorKind::NotFound, "Cache directory not found"))?;
+    create_private_dir(base.join("theshit"))
+}
+
 macro_rules! min_of {
     ($x:expr) => ($x);
     ($x:expr, $($rest:expr),+) => (
@@ -55,6 +166,26 @@ fn copy_dir_recursive(src: &Dir, dst: &Path) -> IoResult<()> {
     Ok(())
 }
 
+pub fn get_rules_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("theshit/fix_rules"))
+}
+
+/// Where packaged rules live, laid out like the user's rules directory: the system-wide
+/// directory first, then the user's data directory.
+pub fn get_data_rules_dirs() -> Vec<PathBuf> {
+    let mut rules_dirs = vec![PathBuf::from("/usr/share/theshit/rules")];
+    rules_dirs.extend(dirs::data_dir().map(|dir| dir.join("theshit/rules")));
+    rules_dirs
+}
+
+/// Every directory rules are read from, lowest precedence first; the user's config comes last
+/// so its rules override packaged ones with the same name.
+pub fn get_rules_dirs() -> Vec<PathBuf> {
+    let mut rules_dirs = get_data_rules_dirs();
+    rules_dirs.extend(get_rules_dir());
+    rules_dirs
+}
+
 pub fn create_default_fix_rules(rules_dir: PathBuf) -> IoResult<()> {
     if rules_dir.as_path().exists() {
         return Err(ErrorKind::AlreadyExists.into());
@@ -67,15 +198,149 @@ pub fn create_default_fix_rules(rules_dir: PathBuf) -> IoResult<()> {
     Ok(())
 }
 
+/// Folds a multi-line command (as returned by `fc -ln -1`) into a single line that evaluates the
+/// same way. Newlines inside quotes are part of an argument and are kept. Heredocs can't be
+/// expressed on one line, so they are refused.
+pub fn normalize_command(command: &str) -> AppResult<String> {
+    let command = command.trim_end_matches('\n');
+    if !command.contains('\n') {
+        return Ok(command.to_string());
+    }
+    let heredoc = Regex::new(r"(^|[^<])<<-?[^<]").expect("Hardcoded regex pattern should be valid");
+    if heredoc.is_match(command) {
+        return Err(AppError::Config(
+            "Multi-line commands with heredocs can't be fixed".into(),
+        ));
+    }
+
+    let mut normalized = String::new();
+    for line in split_unquoted_lines(command)
+        .iter()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+    {
+        if normalized.is_empty() {
+            normalized.push_str(line);
+            continue;
+        }
+        if let Some(continued) = normalized.strip_suffix('\\') {
+            normalized = continued.trim_end().to_string();
+            normalized.push(' ');
+        } else if opens_next_line(&normalized) {
+            normalized.push(' ');
+        } else {
+            normalized.push_str("; ");
+        }
+        normalized.push_str(line);
+    }
+    Ok(normalized)
+}
+
+/// Splits a command at the newlines that end a line, leaving the ones inside quotes in place.
+fn split_unquoted_lines(command: &str) -> Vec<String> {
+    let mut lines = vec![String::new()];
+    let mut quote = None;
+    let mut escaped = false;
+    for c in command.chars() {
+        if c == '\n' && quote.is_none() {
+            // An escaped newline is a line continuation, which the caller joins.
+            escaped = false;
+            lines.push(String::new());
+            continue;
+        }
+        if let Some(line) = lines.last_mut() {
+            line.push(c);
+        }
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match (quote, c) {
+            (Some('\''), '\'') | (Some('"'), '"') => quote = None,
+            (Some('\''), _) => {}
+            (_, '\\') => escaped = true,
+            (None, '\'' | '"') => quote = Some(c),
+            _ => {}
+        }
+    }
+    lines
+}
+
+/// Whether the next line continues the current command rather than starting a new one, as
+/// after a pipe or a keyword like `do` that a `;` can't follow.
+fn opens_next_line(line: &str) -> bool {
+    ["|", "&&", "||", ";", "{", "("]
+        .iter()
+        .any(|operator| line.ends_with(operator))
+        || line
+            .split_whitespace()
+            .last()
+            .is_some_and(|word| ["do", "then", "else"].contains(&word))
+}
+
+/// Parses `SH_HISTORY` (one command per line, newest first, as printed by `fc -lnr`).
+pub fn parse_history(raw_history: &str) -> Vec<String> {
+    raw_history
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Commands run before the one being fixed, newest first. The first `SH_HISTORY` entry is the
+/// failing command itself, so it is skipped.
+pub fn previous_commands() -> Vec<String> {
+    parse_history(&std::env::var("SH_HISTORY").unwrap_or_default())
+        .into_iter()
+        .skip(1)
+        .collect()
+}
+
+/// Builds the classic `sudo !!`: the previous command prefixed with `sudo`, unless it already is.
+pub fn sudo_command(command: &str) -> String {
+    let command = command.trim();
+    if command.split_whitespace().next() == Some("sudo") {
+        command.to_string()
+    } else {
+        format!("sudo {command}")
+    }
+}
+
+/// Expands a leading `~` and `$VAR`/`${VAR}` references the way the shell would before the path
+/// reaches the filesystem. Unset variables and `~user` are left as they are.
+pub fn expand_path(path: &str) -> String {
+    let path = match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => match dirs::home_dir() {
+            Some(home) => format!("{}{rest}", home.display()),
+            None => path.to_string(),
+        },
+        _ => path.to_string(),
+    };
+    let variable =
+        Regex::new(r"\$(?:\{(\w+)\}|(\w+))").expect("Hardcoded regex pattern should be valid");
+    variable
+        .replace_all(&path, |caps: &regex::Captures| {
+            let name = caps
+                .get(1)
+                .or_else(|| caps.get(2))
+                .map_or("", |name| name.as_str());
+            std::env::var(name).unwrap_or_else(|_| caps[0].to_string())
+        })
+        .into_owned()
+}
+
+/// Replaces the first word with the whole value of the alias it names, keeping the rest of the
+/// command as typed: with `ll='ls -l'`, `ll /tmp` becomes `ls -l /tmp`.
 pub fn expand_aliases(command: &str, aliases: HashMap<String, String>) -> AppResult<String> {
+    let command = command.trim_start();
     let binary = command
-        .split(' ')
+        .split_whitespace()
         .next()
         .ok_or_else(|| AppError::Config("Empty command provided".into()))?;
-    if aliases.contains_key(binary) {
-        Ok(command.replacen(binary, &aliases[binary], 1))
-    } else {
-        Ok(command.to_string())
+    match aliases.get(binary) {
+        Some(value) => Ok(format!("{value}{}", &command[binary.len()..])),
+        None => Ok(command.to_string()),
     }
 }
 
@@ -124,6 +389,42 @@ pub fn split_command(command: &str) -> Vec<String> {
         .unwrap_or(command.split_whitespace().map(|s| s.to_string()).collect())
 }
 
+/// Characters the shell interprets when they aren't quoted: operators, redirections, expansions,
+/// globs and comments.
+const SHELL_SYNTAX: &str = "|&;<>()$`*?[~#\n";
+
+fn has_shell_syntax(command: &str) -> bool {
+    let mut chars = command.chars();
+    let (mut in_single, mut in_double) = (false, false);
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' if !in_double => in_single = !in_single,
+            _ if in_single => {}
+            '\\' => {
+                chars.next();
+            }
+            '"' => in_double = !in_double,
+            '$' | '`' => return true,
+            _ if in_double => {}
+            c if SHELL_SYNTAX.contains(c) => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Splits `command` into the argv the shell would run it with, using the same quoting rules as
+/// [`split_command`]. `None` when running it takes more than word splitting and quote removal:
+/// operators, expansions, globs, variable assignments or unbalanced quotes.
+pub fn split_argv(command: &str) -> Option<Vec<String>> {
+    if has_shell_syntax(command) {
+        return None;
+    }
+    shell_words::split(command)
+        .ok()
+        .filter(|argv| argv.first().is_some_and(|program| !program.contains('=')))
+}
+
 pub fn replace_argument(script: &str, from: &str, to: &str) -> String {
     let end_pattern = format!(r" {}$", regex::escape(from));
     let end_regex = Regex::new(&end_pattern).expect("Hardcoded regex pattern should be valid");
@@ -132,7 +433,7 @@ pub fn replace_argument(script: &str, from: &str, to: &str) -> String {
         return end_regex.replace(script, format!(" {to}")).to_string();
     }
 
-    let middle_pattern = format!(" {} ", regex::escape(from));
+    let middle_pattern = format!(" {from} ");
     let replacement = format!(" {to} ");
 
     script.replacen(&middle_pattern, &replacement, 1)
@@ -142,6 +443,104 @@ pub fn replace_argument(script: &str, from: &str, to: &str) -> String {
 mod tests {
     use super::*;
 
+    fn aliases() -> HashMap<String, String> {
+        HashMap::from([
+            ("ll".to_string(), "ls -l".to_string()),
+            ("g".to_string(), "git".to_string()),
+        ])
+    }
+
+    #[test]
+    fn test_expand_aliases_bare_alias() {
+        assert_eq!(
+            expand_aliases("ll", aliases()).expect("Expansion should succeed"),
+            "ls -l"
+        );
+    }
+
+    #[test]
+    fn test_expand_aliases_keeps_arguments() {
+        assert_eq!(
+            expand_aliases("ll /tmp", aliases()).expect("Expansion should succeed"),
+            "ls -l /tmp"
+        );
+        assert_eq!(
+            expand_aliases("g log --grep g", aliases()).expect("Expansion should succeed"),
+            "git log --grep g"
+        );
+    }
+
+    #[test]
+    fn test_expand_aliases_leaves_other_commands() {
+        assert_eq!(
+            expand_aliases("lll /tmp", aliases()).expect("Expansion should succeed"),
+            "lll /tmp"
+        );
+        assert!(expand_aliases("   ", aliases()).is_err());
+    }
+
+    #[test]
+    fn test_expand_path_home() {
+        let home = dirs::home_dir().expect("Home directory should be known");
+        assert_eq!(expand_path("~/foo"), format!("{}/foo", home.display()));
+        assert_eq!(expand_path("~"), home.display().to_string());
+        assert_eq!(expand_path("~other/foo"), "~other/foo");
+        assert_eq!(expand_path("foo/~"), "foo/~");
+    }
+
+    #[test]
+    fn test_expand_path_variables() {
+        let home = std::env::var("HOME").expect("HOME should be set");
+        assert_eq!(expand_path("$HOME/bar"), format!("{home}/bar"));
+        assert_eq!(expand_path("${HOME}/bar"), format!("{home}/bar"));
+        assert_eq!(
+            expand_path("/tmp/$THESHIT_SURELY_UNSET_VAR/x"),
+            "/tmp/$THESHIT_SURELY_UNSET_VAR/x"
+        );
+    }
+
+    #[test]
+    fn test_split_argv() {
+        assert_eq!(
+            split_argv(r#"git commit -m "fix the 'bug'" --author='A \"B\" <a@b>'"#),
+            Some(vec![
+                "git".to_string(),
+                "commit".to_string(),
+                "-m".to_string(),
+                "fix the 'bug'".to_string(),
+                "--author=A \\\"B\\\" <a@b>".to_string(),
+            ])
+        );
+        assert_eq!(
+            split_argv(r"echo a\ b 'c;d' a=b"),
+            Some(vec![
+                "echo".to_string(),
+                "a b".to_string(),
+                "c;d".to_string(),
+                "a=b".to_string()
+            ])
+        );
+        assert_eq!(split_argv("echo 'unbalanced"), None);
+        assert_eq!(split_argv(""), None);
+    }
+
+    #[test]
+    fn test_split_argv_refuses_shell_syntax() {
+        for command in [
+            "git add . && git commit",
+            "ls | less",
+            "echo hi > out",
+            "echo $HOME",
+            "echo \"$(pwd)\"",
+            "rm *.tmp",
+            "cd ~",
+            "FOO=1 make",
+            "echo a # comment",
+        ] {
+            assert_eq!(split_argv(command), None, "{command}");
+        }
+    }
+
     #[test]
     fn test_split_command() {
         assert_eq!(
@@ -173,6 +572,10 @@ mod tests {
             "echo hello world"
         );
         assert_eq!(replace_argument(script, "notfound", "replacement"), script);
+        assert_eq!(
+            replace_argument("pip install a==1.0 b", "a==1.0", "a==2.0"),
+            "pip install a==2.0 b"
+        );
     }
 
     #[test]
@@ -323,6 +726,183 @@ mod tests {
         assert!(similarity < 1.0);
     }
 
+    #[test]
+    fn test_panic_message_from_payload() {
+        let str_payload: Box<dyn Any + Send> = Box::new("boom");
+        let string_payload: Box<dyn Any + Send> = Box::new("boom".to_string());
+        let other_payload: Box<dyn Any + Send> = Box::new(42);
+        assert_eq!(panic_message(str_payload.as_ref()), "boom");
+        assert_eq!(panic_message(string_payload.as_ref()), "boom");
+        assert_eq!(panic_message(other_payload.as_ref()), "Unknown panic");
+    }
+
+    #[test]
+    fn test_redact_secrets() {
+        assert_eq!(
+            redact_secrets("curl -H 'Authorization: Bearer abc123' https://x"),
+            "curl -H 'Authorization: Bearer <redacted>' https://x"
+        );
+        assert_eq!(
+            redact_secrets("deploy --token s3cr3t --verbose"),
+            "deploy --token <redacted> --verbose"
+        );
+        assert_eq!(
+            redact_secrets("env API_KEY=abc GITHUB=ghp_abcdef123 run"),
+            "env API_KEY=<redacted> GITHUB=ghp_<redacted> run"
+        );
+        assert_eq!(redact_secrets("ls -la"), "ls -la");
+    }
+
+    #[test]
+    fn test_resolve_cache_base_respects_xdg() {
+        assert_eq!(
+            resolve_cache_base(Some(OsString::from("/tmp/xdg-cache"))),
+            Some(PathBuf::from("/tmp/xdg-cache"))
+        );
+        assert_eq!(
+            resolve_cache_base(Some(OsString::from("relative/cache"))),
+            dirs::cache_dir()
+        );
+        assert_eq!(resolve_cache_base(None), dirs::cache_dir());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_create_private_dir() {
+        use std::os::unix::fs::PermissionsExt;
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let dir = create_private_dir(temp_dir.path().join("xdg/theshit"))
+            .expect("Failed to create cache dir");
+        let mode = fs::metadata(&dir)
+            .expect("Failed to get metadata")
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o700);
+        assert!(create_private_dir(dir).is_ok());
+    }
+
+    #[test]
+    fn test_crash_report_is_written() {
+        let payload: Box<dyn Any + Send> = Box::new("synthetic panic".to_string());
+        let report = build_crash_report(
+            panic_message(payload.as_ref()),
+            Some("src/main.rs:1:1"),
+            Some("0: main"),
+            Some("zsh"),
+            Some("git push --password hunter2"),
+        );
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let path = write_crash_report(&temp_dir.path().join("theshit"), &report)
+            .expect("Failed to write crash report");
+
+        let contents = fs::read_to_string(&path).expect("Failed to read crash report");
+        assert!(
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("crash-") && name.ends_with(".log"))
+        );
+        assert!(contents.contains(env!("CARGO_PKG_VERSION")));
+        assert!(contents.contains("message: synthetic panic"));
+        assert!(contents.contains("shell: zsh"));
+        assert!(contents.contains("command: git push --password <redacted>"));
+        assert!(contents.contains("backtrace:\n0: main"));
+        assert!(!contents.contains("hunter2"));
+    }
+
+    #[test]
+    fn test_normalize_single_line_command() {
+        assert_eq!(normalize_command("ls -la\n").unwrap(), "ls -la");
+    }
+
+    #[test]
+    fn test_normalize_two_line_pipeline() {
+        assert_eq!(
+            normalize_command("cat file.txt |\n  grep foo").unwrap(),
+            "cat file.txt | grep foo"
+        );
+    }
+
+    #[test]
+    fn test_normalize_line_continuation() {
+        assert_eq!(
+            normalize_command("ls \\\n  -la /tmp").unwrap(),
+            "ls -la /tmp"
+        );
+    }
+
+    #[test]
+    fn test_normalize_separate_commands() {
+        assert_eq!(normalize_command("cd /tmp\nls").unwrap(), "cd /tmp; ls");
+    }
+
+    #[test]
+    fn test_normalize_keeps_quoted_newlines() {
+        assert_eq!(
+            normalize_command("git commit -m \"a\nb\"").unwrap(),
+            "git commit -m \"a\nb\""
+        );
+        assert_eq!(
+            normalize_command("echo 'it\"s\n  here'\nls").unwrap(),
+            "echo 'it\"s\n  here'; ls"
+        );
+    }
+
+    #[test]
+    fn test_normalize_for_loop() {
+        assert_eq!(
+            normalize_command("for f in *; do\n  echo $f\ndone").unwrap(),
+            "for f in *; do echo $f; done"
+        );
+    }
+
+    #[test]
+    fn test_normalize_if_else() {
+        assert_eq!(
+            normalize_command("if true; then\n  echo a\nelse\n  echo b\nfi").unwrap(),
+            "if true; then echo a; else echo b; fi"
+        );
+        assert_eq!(normalize_command("{\n  ls\n}").unwrap(), "{ ls; }");
+    }
+
+    #[test]
+    fn test_normalize_refuses_heredoc() {
+        let result = normalize_command("cat <<EOF\nhello\nEOF");
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("heredoc"));
+    }
+
+    #[test]
+    fn test_normalize_allows_here_string() {
+        assert_eq!(
+            normalize_command("grep foo <<< \"$text\" |\nwc -l").unwrap(),
+            "grep foo <<< \"$text\" | wc -l"
+        );
+    }
+
+    #[test]
+    fn test_expand_alias_in_two_line_pipeline() {
+        let aliases = get_mock_alias();
+        let command = normalize_command("ll /tmp |\n  grep foo").unwrap();
+        let result = expand_aliases(&command, aliases).unwrap();
+        assert_eq!(result, "ls -l /tmp | grep foo");
+    }
+
+    #[test]
+    fn test_parse_history() {
+        assert_eq!(
+            parse_history("\tgit psuh\n\tcs /tmp\n\n\tls\n"),
+            vec!["git psuh", "cs /tmp", "ls"]
+        );
+        assert!(parse_history("").is_empty());
+    }
+
+    #[test]
+    fn test_sudo_command() {
+        assert_eq!(sudo_command("apt install vim"), "sudo apt install vim");
+        assert_eq!(sudo_command("  ls /root "), "sudo ls /root");
+        assert_eq!(sudo_command("sudo ls /root"), "sudo ls /root");
+    }
+
     #[test]
     fn test_single_word_command() {
         let aliases = get_mock_alias();
@@ -0,0 +1,66 @@
+//! Informational stderr output that `--quiet` (or `THESHIT_QUIET`) silences, and debug output
+//! that only `THESHIT_DEBUG` turns on. Hard errors and the interactive picker still use
+//! `eprintln!` directly.
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+static DEBUG: AtomicBool = AtomicBool::new(false);
+
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+pub fn set_debug(debug: bool) {
+    DEBUG.store(debug, Ordering::Relaxed);
+}
+
+pub fn is_debug() -> bool {
+    DEBUG.load(Ordering::Relaxed)
+}
+
+/// `THESHIT_QUIET` and `THESHIT_DEBUG` count as set unless they're empty or `0`.
+pub fn flag_from_env(value: Option<&str>) -> bool {
+    value.is_some_and(|value| !value.is_empty() && value != "0")
+}
+
+/// Like `eprintln!`, unless quiet mode is on.
+macro_rules! diagnostic {
+    ($($arg:tt)*) => {
+        if !$crate::misc::log::is_quiet() {
+            eprintln!($($arg)*);
+        }
+    };
+}
+pub(crate) use diagnostic;
+
+/// Explains what theshit is doing, when `THESHIT_DEBUG` is set. The arguments aren't evaluated
+/// otherwise.
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        if $crate::misc::log::is_debug() {
+            eprintln!(
+                "{}",
+                $crate::misc::theme::muted(format!("debug: {}", format_args!($($arg)*)))
+            );
+        }
+    };
+}
+pub(crate) use debug;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flag_from_env() {
+        assert!(!flag_from_env(None));
+        assert!(!flag_from_env(Some("")));
+        assert!(!flag_from_env(Some("0")));
+        assert!(flag_from_env(Some("1")));
+        assert!(flag_from_env(Some("yes")));
+    }
+}
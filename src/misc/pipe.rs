@@ -0,0 +1,156 @@
+//! Reading from child processes with a deadline, without helper threads: Python rules run in
+//! forked workers, and forking is only safe while theshit is single-threaded.
+use std::io::{self, ErrorKind, Read};
+use std::os::fd::AsRawFd;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often [`wait_by`] checks whether the child has exited.
+const WAIT_INTERVAL: Duration = Duration::from_millis(5);
+/// Past the deadline, a child that has finished can have left at most a pipe buffer behind
+/// (64 KiB on Linux); anything beyond that is still being written.
+const LATE_READ_LIMIT: usize = 64 * 1024;
+
+pub trait Pipe: Read + AsRawFd {}
+
+impl<T: Read + AsRawFd> Pipe for T {}
+
+/// Reads every pipe until it is closed, or returns `None` once `deadline` has passed.
+pub fn read_to_end_by(
+    pipes: &mut [&mut dyn Pipe],
+    deadline: Instant,
+) -> io::Result<Option<Vec<Vec<u8>>>> {
+    let mut buffers = vec![vec![]; pipes.len()];
+    let mut open = vec![true; pipes.len()];
+    let mut chunk = [0; 8192];
+    let mut late = 0;
+    while open.contains(&true) {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let indices = (0..pipes.len())
+            .filter(|&index| open[index])
+            .collect::<Vec<_>>();
+        let mut fds = indices
+            .iter()
+            .map(|&index| libc::pollfd {
+                fd: pipes[index].as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            })
+            .collect::<Vec<_>>();
+        // Rounded up, so the last partial millisecond doesn't turn into a busy loop. Once the
+        // deadline has passed, whatever is already written is still picked up.
+        let timeout = match remaining.as_millis() {
+            0 if remaining.is_zero() => 0,
+            millis => millis.saturating_add(1).min(i32::MAX as u128) as i32,
+        };
+        match unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, timeout) } {
+            0 if timeout == 0 => return Ok(None),
+            ready if ready < 0 => {
+                let error = io::Error::last_os_error();
+                if error.kind() == ErrorKind::Interrupted {
+                    continue;
+                }
+                return Err(error);
+            }
+            _ => {}
+        }
+        for (fd, &index) in fds.iter().zip(&indices) {
+            if fd.revents == 0 {
+                continue;
+            }
+            match pipes[index].read(&mut chunk) {
+                Ok(0) => open[index] = false,
+                Ok(read) => {
+                    if timeout == 0 {
+                        late += read;
+                        if late > LATE_READ_LIMIT {
+                            return Ok(None);
+                        }
+                    }
+                    buffers[index].extend_from_slice(&chunk[..read]);
+                }
+                Err(error) if error.kind() == ErrorKind::Interrupted => {}
+                Err(error) => return Err(error),
+            }
+        }
+    }
+    Ok(Some(buffers))
+}
+
+/// Polls `try_wait` until it reports an exit status, or returns `None` once `deadline` has
+/// passed.
+pub fn wait_by<T>(
+    deadline: Instant,
+    mut try_wait: impl FnMut() -> io::Result<Option<T>>,
+) -> io::Result<Option<T>> {
+    loop {
+        if let Some(status) = try_wait()? {
+            return Ok(Some(status));
+        }
+        if Instant::now() >= deadline {
+            return Ok(None);
+        }
+        thread::sleep(WAIT_INTERVAL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::{Command, Stdio};
+
+    #[test]
+    fn test_read_to_end_by_deadline() {
+        let mut child = Command::new("sh")
+            .args(["-c", "echo out; echo err >&2; sleep 5"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("Failed to spawn sh");
+        let mut stdout = child.stdout.take().expect("stdout is piped");
+        let mut stderr = child.stderr.take().expect("stderr is piped");
+        let started = Instant::now();
+        let read = read_to_end_by(
+            &mut [&mut stdout, &mut stderr],
+            started + Duration::from_millis(200),
+        )
+        .expect("Reading should not fail");
+        assert!(read.is_none());
+        assert!(started.elapsed() < Duration::from_secs(4));
+        let waited = wait_by(Instant::now(), || child.try_wait()).expect("Waiting should not fail");
+        assert!(waited.is_none());
+        child.kill().expect("Failed to kill sh");
+        child.wait().expect("Failed to reap sh");
+
+        let mut child = Command::new("sh")
+            .args(["-c", "echo out; echo err >&2"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("Failed to spawn sh");
+        let mut stdout = child.stdout.take().expect("stdout is piped");
+        let mut stderr = child.stderr.take().expect("stderr is piped");
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let read = read_to_end_by(&mut [&mut stdout, &mut stderr], deadline)
+            .expect("Reading should not fail");
+        assert_eq!(read, Some(vec![b"out\n".to_vec(), b"err\n".to_vec()]));
+        let status = wait_by(deadline, || child.try_wait()).expect("Waiting should not fail");
+        assert!(status.is_some_and(|status| status.success()));
+    }
+
+    #[test]
+    fn test_read_to_end_by_endless_output() {
+        let mut child = Command::new("yes")
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("Failed to spawn yes");
+        let mut stdout = child.stdout.take().expect("stdout is piped");
+        let started = Instant::now();
+        let read = read_to_end_by(&mut [&mut stdout], started + Duration::from_millis(100))
+            .expect("Reading should not fail");
+        assert!(read.is_none());
+        assert!(started.elapsed() < Duration::from_secs(4));
+        child.kill().expect("Failed to kill yes");
+        child.wait().expect("Failed to reap yes");
+    }
+}
@@ -0,0 +1,93 @@
+//! Timing of the stages of `theshit fix --profile`. Stages that run more than once (every
+//! native rule, every rules directory) add up, and the totals are printed together at the end.
+use crate::misc::theme;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use strum::{EnumCount, EnumIter, IntoEnumIterator, IntoStaticStr};
+
+#[derive(EnumIter, EnumCount, IntoStaticStr, Debug, Clone, Copy, PartialEq)]
+pub enum Stage {
+    #[strum(serialize = "shell detection")]
+    ShellDetection,
+    #[strum(serialize = "alias expansion")]
+    AliasExpansion,
+    #[strum(serialize = "native rules")]
+    NativeRules,
+    #[strum(serialize = "python init")]
+    PythonInit,
+    #[strum(serialize = "python rules")]
+    PythonRules,
+}
+
+static PROFILE: AtomicBool = AtomicBool::new(false);
+static TIMINGS: Mutex<[Option<Duration>; Stage::COUNT]> = Mutex::new([None; Stage::COUNT]);
+
+pub fn set_profile(profile: bool) {
+    PROFILE.store(profile, Ordering::Relaxed);
+}
+
+pub fn is_profile() -> bool {
+    PROFILE.load(Ordering::Relaxed)
+}
+
+/// Runs `work`, adding the time it took to `stage` when profiling.
+pub fn timed<T>(stage: Stage, work: impl FnOnce() -> T) -> T {
+    if !is_profile() {
+        return work();
+    }
+    let start = Instant::now();
+    let result = work();
+    let elapsed = start.elapsed();
+    if let Ok(mut timings) = TIMINGS.lock() {
+        let total = &mut timings[stage as usize];
+        *total = Some(total.unwrap_or_default() + elapsed);
+    }
+    result
+}
+
+/// One line per stage, in the order they run; `-` marks a stage that didn't run.
+fn format_report(timings: &[Option<Duration>; Stage::COUNT]) -> String {
+    Stage::iter()
+        .map(|stage| {
+            let label: &'static str = stage.into();
+            match timings[stage as usize] {
+                Some(elapsed) => {
+                    format!("profile: {label}: {:.3}ms", elapsed.as_secs_f64() * 1000.0)
+                }
+                None => format!("profile: {label}: -"),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Prints the collected timings to stderr, when profiling.
+pub fn report() {
+    if !is_profile() {
+        return;
+    }
+    if let Ok(timings) = TIMINGS.lock() {
+        eprintln!("{}", theme::muted(format_report(&timings)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_lists_every_stage() {
+        let mut timings = [None; Stage::COUNT];
+        timings[Stage::NativeRules as usize] = Some(Duration::from_micros(1500));
+        let report = format_report(&timings);
+        assert_eq!(
+            report,
+            "profile: shell detection: -\n\
+             profile: alias expansion: -\n\
+             profile: native rules: 1.500ms\n\
+             profile: python init: -\n\
+             profile: python rules: -"
+        );
+    }
+}
@@ -0,0 +1,163 @@
+use crossterm::style::{ContentStyle, StyledContent, Stylize};
+use std::env;
+use std::fmt::Display;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use strum::EnumString;
+
+#[derive(EnumString, Debug, Clone, Copy, PartialEq)]
+pub enum Theme {
+    #[strum(serialize = "default")]
+    Default,
+
+    #[strum(serialize = "high-contrast")]
+    HighContrast,
+
+    #[strum(serialize = "mono")]
+    Mono,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Role {
+    Error,
+    Critical,
+    Success,
+    Warning,
+    Hint,
+    Muted,
+}
+
+impl Theme {
+    pub fn current() -> Theme {
+        static THEME: OnceLock<Theme> = OnceLock::new();
+        *THEME.get_or_init(|| {
+            env::var("THESHIT_THEME")
+                .ok()
+                .and_then(|theme| Theme::from_str(&theme).ok())
+                .unwrap_or(Theme::Default)
+        })
+    }
+
+    pub fn style(self, role: Role) -> ContentStyle {
+        let style = ContentStyle::new();
+        match self {
+            Theme::Default => match role {
+                Role::Error => style.red(),
+                Role::Critical => style.red().bold(),
+                Role::Success => style.green(),
+                Role::Warning => style.yellow(),
+                Role::Hint => style.cyan(),
+                Role::Muted => style.dim(),
+            },
+            // Avoids the red/green pair and relies on brightness instead of hue alone.
+            Theme::HighContrast => match role {
+                Role::Error => style.magenta().bold(),
+                Role::Critical => style.white().on_dark_red().bold(),
+                Role::Success => style.blue().bold(),
+                Role::Warning => style.yellow().bold(),
+                Role::Hint => style.white().bold(),
+                // Dimmed text is hard to read, so muted is plain white rather than faint.
+                Role::Muted => style.white(),
+            },
+            Theme::Mono => style,
+        }
+    }
+
+    pub fn paint<D: Display>(self, role: Role, content: D) -> StyledContent<D> {
+        self.style(role).apply(content)
+    }
+}
+
+pub fn error<D: Display>(content: D) -> StyledContent<D> {
+    Theme::current().paint(Role::Error, content)
+}
+
+pub fn critical<D: Display>(content: D) -> StyledContent<D> {
+    Theme::current().paint(Role::Critical, content)
+}
+
+pub fn success<D: Display>(content: D) -> StyledContent<D> {
+    Theme::current().paint(Role::Success, content)
+}
+
+pub fn warning<D: Display>(content: D) -> StyledContent<D> {
+    Theme::current().paint(Role::Warning, content)
+}
+
+pub fn hint<D: Display>(content: D) -> StyledContent<D> {
+    Theme::current().paint(Role::Hint, content)
+}
+
+pub fn muted<D: Display>(content: D) -> StyledContent<D> {
+    Theme::current().paint(Role::Muted, content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROLES: [Role; 6] = [
+        Role::Error,
+        Role::Critical,
+        Role::Success,
+        Role::Warning,
+        Role::Hint,
+        Role::Muted,
+    ];
+
+    #[test]
+    fn test_theme_from_str() {
+        assert_eq!(Theme::from_str("default").ok(), Some(Theme::Default));
+        assert_eq!(
+            Theme::from_str("high-contrast").ok(),
+            Some(Theme::HighContrast)
+        );
+        assert_eq!(Theme::from_str("mono").ok(), Some(Theme::Mono));
+        assert!(Theme::from_str("rainbow").is_err());
+    }
+
+    #[test]
+    fn test_mono_theme_emits_no_ansi_codes() {
+        for role in ROLES {
+            let painted = Theme::Mono.paint(role, "message").to_string();
+            assert_eq!(painted, "message", "{role:?}");
+        }
+    }
+
+    #[test]
+    fn test_default_theme_keeps_original_colors() {
+        let theme = Theme::Default;
+        assert_eq!(
+            theme.paint(Role::Error, "x").to_string(),
+            "x".red().to_string()
+        );
+        assert_eq!(
+            theme.paint(Role::Critical, "x").to_string(),
+            "x".red().bold().to_string()
+        );
+        assert_eq!(
+            theme.paint(Role::Success, "x").to_string(),
+            "x".green().to_string()
+        );
+        assert_eq!(
+            theme.paint(Role::Warning, "x").to_string(),
+            "x".yellow().to_string()
+        );
+        assert_eq!(
+            theme.paint(Role::Hint, "x").to_string(),
+            "x".cyan().to_string()
+        );
+    }
+
+    #[test]
+    fn test_colored_themes_style_every_role() {
+        for theme in [Theme::Default, Theme::HighContrast] {
+            for role in ROLES {
+                assert!(
+                    theme.paint(role, "message").to_string().contains('\x1b'),
+                    "{theme:?} {role:?}"
+                );
+            }
+        }
+    }
+}
@@ -6,4 +6,5 @@ mod helpers;
 mod zsh;
 
 pub use enums::Shell;
+pub use generic::CompletionSpec;
 pub use helpers::get_current_shell;
@@ -1,64 +1,113 @@
-use crate::shells::generic;
+use crate::shells::{CompletionSpec, Shell, generic};
 use std::collections::HashMap;
 use std::io::ErrorKind;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 pub fn get_shell_function(name: &str, path: &Path) -> String {
     format!(
         "
 {name}() {{
-    export SH_SHELL=bash;
-    export SH_PREV_CMD=\"$(fc -ln -1)\";
+    [[ $- == *i* ]] || return 1;
+    export SH_SHELL={shell};
+    SH_PREV_CMD=\"$(fc -ln -1)\";
+    SH_PREV_CMD=${{SH_PREV_CMD#$'\\t '}};
+    export SH_PREV_CMD;
+    export SH_HISTORY=\"$(fc -lnr -10 -1)\";
     export SH_SHELL_ALIASES=\"$(alias)\";
     
     local SH_CMD;
     SH_CMD=$(
-      command {} fix \"$@\"
+      command {path} fix \"$@\"
     ) && eval \"$SH_CMD\";
 
     unset SH_SHELL_ALIASES;
+    unset SH_HISTORY;
     unset SH_PREV_CMD;
     unset SH_SHELL;
-}}
+}};
+
+_{name}_current() {{
+    local SH_CMD;
+    SH_CMD=$(SH_SHELL={shell} command {path} fix --current --command \"$READLINE_LINE\") || return;
+    READLINE_LINE=$SH_CMD;
+    READLINE_POINT=${{#READLINE_LINE}};
+}};
+if [[ $- == *i* ]]; then
+    bind -x '\"\\C-x\\C-f\": _{name}_current';
+fi
     ",
-        path.display()
+        shell = Shell::Bash,
+        path = generic::quote_path(path)
     )
 }
 
-pub fn setup_alias(name: &str, program_path: &Path) -> std::io::Result<()> {
-    let config_path = dirs::home_dir().ok_or(ErrorKind::NotFound)?.join(".bashrc");
+pub fn get_config_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".bashrc"))
+}
+
+pub fn setup_alias(name: &str, program_path: &Path, replace: bool) -> std::io::Result<()> {
+    let config_path = get_config_path().ok_or(ErrorKind::NotFound)?;
     generic::setup_alias(
-        format!("eval $( {} alias {})", program_path.display(), name),
+        get_setup_command(name, program_path),
         config_path.as_path(),
+        replace,
     )
 }
 
-pub fn get_aliases() -> HashMap<String, String> {
-    parse_alias(generic::get_raw_aliases_from_env())
+fn get_setup_command(name: &str, program_path: &Path) -> String {
+    format!(
+        "eval $( {} alias {})",
+        generic::quote_path(program_path),
+        name
+    )
 }
 
-fn parse_alias(raw_aliases: String) -> HashMap<String, String> {
+pub fn parse_alias(raw_aliases: &str) -> HashMap<String, String> {
     let mut aliases: HashMap<String, String> = HashMap::new();
     for raw_alias in raw_aliases.split('\n') {
         if !raw_alias.contains('=') || raw_alias.is_empty() {
             continue;
         }
-        if let Some((name, mut value)) = raw_alias.split_once('=') {
-            let value_bytes = value.as_bytes();
-            if (value_bytes[0] == b'"' && value_bytes[value.len() - 1] == b'"')
-                || (value_bytes[0] == b'\'' && value_bytes[value.len() - 1] == b'\'')
-            {
-                value = &value[1..value.len() - 1];
-            }
-            aliases.insert(
-                name.replacen("alias ", "", 1).to_string(),
-                value.to_string(),
-            );
+        if let Some((name, value)) = raw_alias.split_once('=') {
+            let value = generic::unquote_alias_value(value);
+            aliases.insert(name.replacen("alias ", "", 1).to_string(), value);
         }
     }
     aliases
 }
 
+pub fn get_completions(spec: &CompletionSpec) -> String {
+    let function = format!("_{}", spec.program.replace('-', "_"));
+    let subcommands = spec
+        .subcommands
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>();
+    let cases = spec
+        .subcommands
+        .iter()
+        .map(|(name, options)| {
+            format!(
+                "        {name}) COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")) ;;\n",
+                options.join(" ")
+            )
+        })
+        .collect::<String>();
+    format!(
+        "{function}() {{
+    local cur=\"${{COMP_WORDS[COMP_CWORD]}}\"
+    case \"${{COMP_WORDS[1]}}\" in
+{cases}        *) COMPREPLY=($(compgen -W \"{} {}\" -- \"$cur\")) ;;
+    esac
+}}
+complete -o default -F {function} {}
+",
+        subcommands.join(" "),
+        spec.options.join(" "),
+        spec.program
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -71,6 +120,22 @@ mod tests {
         assert!(result.contains("shit()"));
     }
 
+    #[test]
+    fn test_get_shell_function_guards_interactivity() {
+        let path = PathBuf::from("/usr/bin/theshit");
+        let result = get_shell_function("shit", &path);
+        assert!(result.contains("[[ $- == *i* ]] || return 1;"));
+    }
+
+    #[test]
+    fn test_get_shell_function_binds_current_buffer_widget() {
+        let path = PathBuf::from("/usr/bin/theshit");
+        let result = get_shell_function("shit", &path);
+        assert!(result.contains("fix --current --command \"$READLINE_LINE\""));
+        assert!(result.contains("bind -x '\"\\C-x\\C-f\": _shit_current';"));
+        assert!(result.trim_end().ends_with("fi"));
+    }
+
     #[test]
     fn test_get_shell_function_contains_path() {
         let path = PathBuf::from("/usr/bin/theshit");
@@ -78,6 +143,24 @@ mod tests {
         assert!(result.contains("/usr/bin/theshit"));
     }
 
+    #[test]
+    fn test_get_shell_function_quotes_path_with_spaces() {
+        let path = PathBuf::from("/Users/me/Library/Application Support/theshit");
+        let result = get_shell_function("shit", &path);
+        assert!(
+            result.contains("command '/Users/me/Library/Application Support/theshit' fix \"$@\"")
+        );
+    }
+
+    #[test]
+    fn test_get_setup_command_quotes_path_with_spaces() {
+        let path = PathBuf::from("/Users/me/Library/Application Support/theshit");
+        assert_eq!(
+            get_setup_command("shit", &path),
+            "eval $( '/Users/me/Library/Application Support/theshit' alias shit)"
+        );
+    }
+
     #[test]
     fn test_get_shell_function_exports_shell_type() {
         let path = PathBuf::from("/usr/bin/theshit");
@@ -85,57 +168,104 @@ mod tests {
         assert!(result.contains("export SH_SHELL=bash"));
     }
 
+    #[test]
+    fn test_get_shell_function_strips_only_fc_prefix() {
+        let path = PathBuf::from("/usr/bin/theshit");
+        let result = get_shell_function("shit", &path);
+        // `fc -l` puts a tab and a space before each entry; anything after is the command.
+        assert!(result.contains("SH_PREV_CMD=${SH_PREV_CMD#$'\\t '};"));
+    }
+
+    #[test]
+    fn test_get_shell_function_exports_history() {
+        let path = PathBuf::from("/usr/bin/theshit");
+        let result = get_shell_function("shit", &path);
+        assert!(result.contains("SH_HISTORY=\"$(fc -lnr -10 -1)\""));
+        assert!(result.contains("unset SH_HISTORY"));
+    }
+
     #[test]
     fn test_parse_alias_empty() {
-        let aliases = parse_alias("".to_string());
+        let aliases = parse_alias("");
         assert!(aliases.is_empty());
     }
 
     #[test]
     fn test_parse_alias_single_alias() {
-        let aliases = parse_alias("alias ll='ls -l'".to_string());
+        let aliases = parse_alias("alias ll='ls -l'");
         assert_eq!(aliases.get("ll"), Some(&"ls -l".to_string()));
     }
 
+    #[test]
+    fn test_parse_alias_value_with_equals_sign() {
+        let aliases = parse_alias("alias x='a=b'");
+        assert_eq!(aliases.get("x"), Some(&"a=b".to_string()));
+    }
+
     #[test]
     fn test_parse_alias_multiple_aliases() {
-        let aliases = parse_alias("alias ll='ls -l'\nalias la='ls -la'".to_string());
+        let aliases = parse_alias("alias ll='ls -l'\nalias la='ls -la'");
         assert_eq!(aliases.get("ll"), Some(&"ls -l".to_string()));
         assert_eq!(aliases.get("la"), Some(&"ls -la".to_string()));
     }
 
     #[test]
     fn test_parse_alias_with_double_quotes() {
-        let aliases = parse_alias("alias grep=\"grep --color=auto\"".to_string());
+        let aliases = parse_alias("alias grep=\"grep --color=auto\"");
         assert_eq!(aliases.get("grep"), Some(&"grep --color=auto".to_string()));
     }
 
     #[test]
     fn test_parse_alias_with_single_quotes() {
-        let aliases = parse_alias("alias cls='clear'".to_string());
+        let aliases = parse_alias("alias cls='clear'");
         assert_eq!(aliases.get("cls"), Some(&"clear".to_string()));
     }
 
     #[test]
     fn test_parse_alias_mixed_quotes() {
-        let aliases = parse_alias("alias ll='ls -l'\nalias grep=\"grep --color=auto\"".to_string());
+        let aliases = parse_alias("alias ll='ls -l'\nalias grep=\"grep --color=auto\"");
         assert_eq!(aliases.get("ll"), Some(&"ls -l".to_string()));
         assert_eq!(aliases.get("grep"), Some(&"grep --color=auto".to_string()));
     }
 
     #[test]
     fn test_parse_alias_ignores_invalid_format() {
-        let aliases = parse_alias("not_an_alias\nalias grep='grep --color=auto'".to_string());
+        let aliases = parse_alias("not_an_alias\nalias grep='grep --color=auto'");
         assert_eq!(aliases.get("grep"), Some(&"grep --color=auto".to_string()));
         assert_eq!(aliases.get("not_an_alias"), None);
     }
 
     #[test]
     fn test_parse_alias_with_spaces_in_value() {
-        let aliases = parse_alias("alias myalias='command with spaces'".to_string());
+        let aliases = parse_alias("alias myalias='command with spaces'");
         assert_eq!(
             aliases.get("myalias"),
             Some(&"command with spaces".to_string())
         );
     }
+
+    fn completion_spec() -> CompletionSpec {
+        CompletionSpec {
+            program: "theshit".to_string(),
+            options: vec!["--shell".to_string(), "--help".to_string()],
+            subcommands: vec![
+                ("alias".to_string(), vec!["--help".to_string()]),
+                (
+                    "fix".to_string(),
+                    vec!["--command".to_string(), "--help".to_string()],
+                ),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_get_completions() {
+        let result = get_completions(&completion_spec());
+        assert!(result.contains("_theshit() {"));
+        assert!(
+            result.contains("fix) COMPREPLY=($(compgen -W \"--command --help\" -- \"$cur\")) ;;")
+        );
+        assert!(result.contains("compgen -W \"alias fix --shell --help\""));
+        assert!(result.contains("complete -o default -F _theshit theshit"));
+    }
 }
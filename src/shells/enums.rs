@@ -1,10 +1,10 @@
-use super::{bash, fish, zsh};
+use super::{CompletionSpec, bash, fish, generic, zsh};
 use std::collections::HashMap;
 use std::io::Result;
-use std::path::Path;
-use strum::EnumString;
+use std::path::{Path, PathBuf};
+use strum::{Display, EnumIter, EnumString, IntoStaticStr, VariantArray};
 
-#[derive(EnumString, Debug)]
+#[derive(EnumString, Display, IntoStaticStr, EnumIter, VariantArray, Debug, Clone)]
 pub enum Shell {
     #[strum(serialize = "bash")]
     Bash,
@@ -17,6 +17,20 @@ pub enum Shell {
 }
 
 impl Shell {
+    /// The canonical lowercase name, as accepted by `--shell` and exported in `SH_SHELL`.
+    pub fn as_str(&self) -> &'static str {
+        self.into()
+    }
+    /// Every shell theshit knows, in declaration order.
+    pub fn all() -> &'static [Shell] {
+        Self::VARIANTS
+    }
+    /// Whether `theshit setup` has already added its block (or an older setup line) to this
+    /// shell's rc file.
+    pub fn is_set_up(&self) -> bool {
+        self.get_config_path()
+            .is_some_and(|path| generic::has_setup_block(&path))
+    }
     pub fn get_shell_function(&self, name: &str, path: &Path) -> String {
         match self {
             Shell::Bash => bash::get_shell_function(name, path),
@@ -24,18 +38,41 @@ impl Shell {
             Shell::Fish => fish::get_shell_function(name, path),
         }
     }
-    pub fn setup_alias(&self, name: &str, path: &Path) -> Result<()> {
+    pub fn setup_alias(&self, name: &str, path: &Path, replace: bool) -> Result<()> {
+        match self {
+            Shell::Bash => bash::setup_alias(name, path, replace),
+            Shell::Zsh => zsh::setup_alias(name, path, replace),
+            Shell::Fish => fish::setup_alias(name, path, replace),
+        }
+    }
+    pub fn get_config_path(&self) -> Option<PathBuf> {
         match self {
-            Shell::Bash => bash::setup_alias(name, path),
-            Shell::Zsh => zsh::setup_alias(name, path),
-            Shell::Fish => fish::setup_alias(name, path),
+            Shell::Bash => bash::get_config_path(),
+            Shell::Zsh => zsh::get_config_path(),
+            Shell::Fish => fish::get_config_path(),
         }
     }
     pub fn get_aliases(&self) -> HashMap<String, String> {
+        let raw_aliases = generic::get_raw_aliases_from_env();
+        if !raw_aliases.is_empty() {
+            return self.parse_aliases(&raw_aliases);
+        }
+        self.get_config_path()
+            .map(|path| self.parse_aliases(&generic::get_raw_aliases_from_rc(&path)))
+            .unwrap_or_default()
+    }
+    pub fn get_completions(&self, spec: &CompletionSpec) -> String {
         match self {
-            Shell::Bash => bash::get_aliases(),
-            Shell::Zsh => zsh::get_aliases(),
-            Shell::Fish => fish::get_aliases(),
+            Shell::Bash => bash::get_completions(spec),
+            Shell::Zsh => zsh::get_completions(spec),
+            Shell::Fish => fish::get_completions(spec),
+        }
+    }
+    pub fn parse_aliases(&self, raw: &str) -> HashMap<String, String> {
+        match self {
+            Shell::Bash => bash::parse_alias(raw),
+            Shell::Zsh => zsh::parse_alias(raw),
+            Shell::Fish => fish::parse_alias(raw),
         }
     }
 }
@@ -45,6 +82,27 @@ mod tests {
     use super::*;
     use std::path::PathBuf;
     use std::str::FromStr;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn test_shell_name_round_trips() {
+        for shell in Shell::iter() {
+            let parsed = Shell::from_str(shell.as_str()).expect("Name should parse");
+            assert_eq!(parsed.as_str(), shell.as_str());
+            assert_eq!(shell.to_string(), shell.as_str());
+        }
+        assert_eq!(Shell::Zsh.as_str(), "zsh");
+    }
+
+    #[test]
+    fn test_all_shells_have_a_shell_function() {
+        assert!(!Shell::all().is_empty());
+        assert_eq!(Shell::all().len(), Shell::iter().count());
+        for shell in Shell::all() {
+            let function = shell.get_shell_function("shit", &PathBuf::from("/usr/bin/theshit"));
+            assert!(function.contains("shit"), "{shell}");
+        }
+    }
 
     #[test]
     fn test_shell_from_str_bash() {
@@ -105,4 +163,54 @@ mod tests {
         assert!(result.contains("function shit"));
         assert!(result.contains("SH_SHELL fish"));
     }
+
+    #[test]
+    fn test_parse_aliases_matrix() {
+        let cases = [
+            (
+                Shell::Bash,
+                "alias ll='ls -l'\nalias grep=\"grep --color=auto\"\nalias cls='clear'",
+            ),
+            (
+                Shell::Zsh,
+                "ll='ls -l'\ngrep=\"grep --color=auto\"\ncls=clear",
+            ),
+            (
+                Shell::Fish,
+                "alias ll 'ls -l'\nalias grep 'grep --color=auto'\nalias cls clear",
+            ),
+        ];
+        for (shell, raw) in cases {
+            let aliases = shell.parse_aliases(raw);
+            assert_eq!(aliases.len(), 3, "{shell:?}");
+            assert_eq!(aliases.get("ll"), Some(&"ls -l".to_string()), "{shell:?}");
+            assert_eq!(
+                aliases.get("grep"),
+                Some(&"grep --color=auto".to_string()),
+                "{shell:?}"
+            );
+            assert_eq!(aliases.get("cls"), Some(&"clear".to_string()), "{shell:?}");
+        }
+    }
+
+    #[test]
+    fn test_parse_aliases_from_rc_lines() {
+        let rc_lines = "alias ll='ls -l'\nalias gs=\"git status\"";
+        for shell in [Shell::Bash, Shell::Zsh] {
+            let aliases = shell.parse_aliases(rc_lines);
+            assert_eq!(aliases.get("ll"), Some(&"ls -l".to_string()), "{shell:?}");
+            assert_eq!(
+                aliases.get("gs"),
+                Some(&"git status".to_string()),
+                "{shell:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_aliases_empty_for_every_shell() {
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish] {
+            assert!(shell.parse_aliases("").is_empty(), "{shell:?}");
+        }
+    }
 }
@@ -1,48 +1,66 @@
 use crate::misc;
-use crate::shells::generic;
+use crate::shells::{CompletionSpec, Shell, generic};
 use std::collections::HashMap;
 use std::io::ErrorKind;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 pub fn get_shell_function(name: &str, path: &Path) -> String {
     format!(
         "
 function {name} -d \"Correct your previous command\"
-    set -lx SH_SHELL fish
+    status is-interactive; or return 1
+    set -lx SH_SHELL {shell}
     set -lx SH_PREV_CMD \"$history[1]\"
+    set -lx SH_HISTORY (string join \\n -- $history[1..10])
     set -lx SH_SHELL_ALIASES (alias)
     
     set -l SH_CMD;
-    command {} fix $argv | read -l SH_CMD;
+    command {path} fix $argv | read -l SH_CMD;
 
 
     if test -n \"$SH_CMD\"
         eval \"$SH_CMD\";
     end
     set -e SH_SHELL_ALIASES;
+    set -e SH_HISTORY;
     set -e SH_PREV_CMD;
     set -e SH_SHELL;
 end
+
+function {name}_current -d \"Correct the command being typed\"
+    set -lx SH_SHELL {shell}
+    set -l SH_CMD (command {path} fix --current --command (commandline)); or return
+    commandline -r -- $SH_CMD
+end
+status is-interactive; and bind \\cx\\cf {name}_current
     ",
-        path.display()
+        shell = Shell::Fish,
+        path = generic::quote_path(path)
     )
 }
 
-pub fn setup_alias(name: &str, program_path: &Path) -> std::io::Result<()> {
-    let config_path = dirs::config_dir()
-        .ok_or(ErrorKind::NotFound)?
-        .join("fish/config.fish");
+pub fn get_config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("fish/config.fish"))
+}
+
+pub fn setup_alias(name: &str, program_path: &Path, replace: bool) -> std::io::Result<()> {
+    let config_path = get_config_path().ok_or(ErrorKind::NotFound)?;
     generic::setup_alias(
-        format!("{} alias {} | source", program_path.display(), name),
+        get_setup_command(name, program_path),
         config_path.as_path(),
+        replace,
     )
 }
 
-pub fn get_aliases() -> HashMap<String, String> {
-    parse_alias(generic::get_raw_aliases_from_env())
+fn get_setup_command(name: &str, program_path: &Path) -> String {
+    format!(
+        "{} alias {} | source",
+        generic::quote_path(program_path),
+        name
+    )
 }
 
-fn parse_alias(raw_aliases: String) -> HashMap<String, String> {
+pub fn parse_alias(raw_aliases: &str) -> HashMap<String, String> {
     let mut aliases: HashMap<String, String> = HashMap::new();
     for raw_alias in raw_aliases.split('\n') {
         if !raw_alias.contains("alias ") || raw_alias.is_empty() {
@@ -57,6 +75,33 @@ fn parse_alias(raw_aliases: String) -> HashMap<String, String> {
     aliases
 }
 
+pub fn get_completions(spec: &CompletionSpec) -> String {
+    let program = &spec.program;
+    let subcommands = spec
+        .subcommands
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let mut completions =
+        format!("complete -c {program} -n \"__fish_use_subcommand\" -f -a \"{subcommands}\"\n");
+    for option in &spec.options {
+        completions.push_str(&format!(
+            "complete -c {program} -n \"__fish_use_subcommand\" -l {}\n",
+            option.trim_start_matches("--")
+        ));
+    }
+    for (name, options) in &spec.subcommands {
+        for option in options {
+            completions.push_str(&format!(
+                "complete -c {program} -n \"__fish_seen_subcommand_from {name}\" -l {}\n",
+                option.trim_start_matches("--")
+            ));
+        }
+    }
+    completions
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -69,6 +114,21 @@ mod tests {
         assert!(result.contains("function shit"));
     }
 
+    #[test]
+    fn test_get_shell_function_guards_interactivity() {
+        let path = PathBuf::from("/usr/bin/theshit");
+        let result = get_shell_function("shit", &path);
+        assert!(result.contains("status is-interactive; or return 1"));
+    }
+
+    #[test]
+    fn test_get_shell_function_binds_current_buffer_widget() {
+        let path = PathBuf::from("/usr/bin/theshit");
+        let result = get_shell_function("shit", &path);
+        assert!(result.contains("fix --current --command (commandline)"));
+        assert!(result.contains("bind \\cx\\cf shit_current"));
+    }
+
     #[test]
     fn test_get_shell_function_contains_path() {
         let path = PathBuf::from("/usr/bin/theshit");
@@ -76,6 +136,24 @@ mod tests {
         assert!(result.contains("/usr/bin/theshit"));
     }
 
+    #[test]
+    fn test_get_shell_function_quotes_path_with_spaces() {
+        let path = PathBuf::from("/Users/me/Library/Application Support/theshit");
+        let result = get_shell_function("shit", &path);
+        assert!(
+            result.contains("command '/Users/me/Library/Application Support/theshit' fix $argv")
+        );
+    }
+
+    #[test]
+    fn test_get_setup_command_quotes_path_with_spaces() {
+        let path = PathBuf::from("/Users/me/Library/Application Support/theshit");
+        assert_eq!(
+            get_setup_command("shit", &path),
+            "'/Users/me/Library/Application Support/theshit' alias shit | source"
+        );
+    }
+
     #[test]
     fn test_get_shell_function_exports_shell_type() {
         let path = PathBuf::from("/usr/bin/theshit");
@@ -83,28 +161,36 @@ mod tests {
         assert!(result.contains("set -lx SH_SHELL fish"));
     }
 
+    #[test]
+    fn test_get_shell_function_exports_history() {
+        let path = PathBuf::from("/usr/bin/theshit");
+        let result = get_shell_function("shit", &path);
+        assert!(result.contains("set -lx SH_HISTORY (string join \\n -- $history[1..10])"));
+        assert!(result.contains("set -e SH_HISTORY"));
+    }
+
     #[test]
     fn test_parse_alias_empty() {
-        let aliases = parse_alias("".to_string());
+        let aliases = parse_alias("");
         assert!(aliases.is_empty());
     }
 
     #[test]
     fn test_parse_alias_single_alias() {
-        let aliases = parse_alias("alias ll 'ls -l'".to_string());
+        let aliases = parse_alias("alias ll 'ls -l'");
         assert_eq!(aliases.get("ll"), Some(&"ls -l".to_string()));
     }
 
     #[test]
     fn test_parse_alias_multiple_aliases() {
-        let aliases = parse_alias("alias ll 'ls -l'\nalias la 'ls -la'".to_string());
+        let aliases = parse_alias("alias ll 'ls -l'\nalias la 'ls -la'");
         assert_eq!(aliases.get("ll"), Some(&"ls -l".to_string()));
         assert_eq!(aliases.get("la"), Some(&"ls -la".to_string()));
     }
 
     #[test]
     fn test_parse_alias_with_spaces_in_value() {
-        let aliases = parse_alias("alias myalias 'command with spaces'".to_string());
+        let aliases = parse_alias("alias myalias 'command with spaces'");
         assert_eq!(
             aliases.get("myalias"),
             Some(&"command with spaces".to_string())
@@ -113,18 +199,44 @@ mod tests {
 
     #[test]
     fn test_parse_alias_ignores_invalid_format() {
-        let aliases = parse_alias("not_an_alias\nalias grep 'grep --color=auto'".to_string());
+        let aliases = parse_alias("not_an_alias\nalias grep 'grep --color=auto'");
         assert_eq!(aliases.get("grep"), Some(&"grep --color=auto".to_string()));
         assert_eq!(aliases.get("not_an_alias"), None);
     }
 
     #[test]
     fn test_parse_alias_mixed_aliases() {
-        let aliases = parse_alias(
-            "alias ll 'ls -l'\nalias grep 'grep --color=auto'\nalias cls 'clear'".to_string(),
-        );
+        let aliases =
+            parse_alias("alias ll 'ls -l'\nalias grep 'grep --color=auto'\nalias cls 'clear'");
         assert_eq!(aliases.get("ll"), Some(&"ls -l".to_string()));
         assert_eq!(aliases.get("grep"), Some(&"grep --color=auto".to_string()));
         assert_eq!(aliases.get("cls"), Some(&"clear".to_string()));
     }
+
+    fn completion_spec() -> CompletionSpec {
+        CompletionSpec {
+            program: "theshit".to_string(),
+            options: vec!["--shell".to_string(), "--help".to_string()],
+            subcommands: vec![
+                ("alias".to_string(), vec!["--help".to_string()]),
+                (
+                    "fix".to_string(),
+                    vec!["--command".to_string(), "--help".to_string()],
+                ),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_get_completions() {
+        let result = get_completions(&completion_spec());
+        assert!(
+            result.contains("complete -c theshit -n \"__fish_use_subcommand\" -f -a \"alias fix\"")
+        );
+        assert!(result.contains("complete -c theshit -n \"__fish_use_subcommand\" -l shell"));
+        assert!(
+            result
+                .contains("complete -c theshit -n \"__fish_seen_subcommand_from fix\" -l command")
+        );
+    }
 }
@@ -1,11 +1,21 @@
 use std::env;
-use std::fs::{File, OpenOptions};
-use std::io::{ErrorKind, Read, Result, Write, stdin};
+use std::fs;
+use std::io::{ErrorKind, Result, stdin};
 use std::path::Path;
 
-pub fn setup_alias(setup_command: String, config_path: &Path) -> Result<()> {
-    let mut config_file = match OpenOptions::new().read(true).append(true).open(config_path) {
-        Ok(file) => file,
+/// The shell-independent description of the CLI that completion scripts are generated from.
+pub struct CompletionSpec {
+    pub program: String,
+    pub options: Vec<String>,
+    pub subcommands: Vec<(String, Vec<String>)>,
+}
+
+const BLOCK_START: &str = "# >>> theshit >>>";
+const BLOCK_END: &str = "# <<< theshit <<<";
+
+pub fn setup_alias(setup_command: String, config_path: &Path, replace: bool) -> Result<()> {
+    let config_content = match fs::read_to_string(config_path) {
+        Ok(content) => content,
         Err(error) => match error.kind() {
             ErrorKind::NotFound => {
                 println!(
@@ -15,7 +25,7 @@ pub fn setup_alias(setup_command: String, config_path: &Path) -> Result<()> {
                 let mut input = String::new();
                 stdin().read_line(&mut input)?;
                 if input.trim().eq_ignore_ascii_case("y") || input.trim().is_empty() {
-                    File::create(config_path)?
+                    String::new()
                 } else {
                     return Err(ErrorKind::NotFound.into());
                 }
@@ -24,16 +34,250 @@ pub fn setup_alias(setup_command: String, config_path: &Path) -> Result<()> {
         },
     };
 
-    let mut config_content = String::new();
+    fs::write(
+        config_path,
+        update_setup_block(&config_content, &setup_command, replace)?,
+    )
+}
 
-    config_file.read_to_string(&mut config_content)?;
-    if config_content.contains(&setup_command) {
-        return Err(ErrorKind::AlreadyExists.into());
-    }
+/// Returns the rc content with the setup command in a marked block. An existing block (or a
+/// bare setup line written by older versions) is only rewritten when `replace` is set.
+fn update_setup_block(content: &str, setup_command: &str, replace: bool) -> Result<String> {
+    let block = format!("{BLOCK_START}\n{setup_command}\n{BLOCK_END}");
+    let lines = content.lines().collect::<Vec<_>>();
+    let existing = match lines.iter().position(|line| line.trim() == BLOCK_START) {
+        Some(start) => {
+            // Rewriting up to EOF could drop the user's own lines, so leave that to them.
+            let end = lines[start..]
+                .iter()
+                .position(|line| line.trim() == BLOCK_END)
+                .ok_or_else(|| {
+                    std::io::Error::new(
+                        ErrorKind::InvalidData,
+                        format!("Found \"{BLOCK_START}\" without a closing \"{BLOCK_END}\""),
+                    )
+                })?;
+            Some(start..start + end + 1)
+        }
+        None => lines
+            .iter()
+            .position(|line| line.contains(setup_command) || is_legacy_setup_line(line))
+            .map(|line| line..line + 1),
+    };
 
-    writeln!(config_file, "{setup_command}")
+    let mut updated = match existing {
+        Some(_) if !replace => return Err(ErrorKind::AlreadyExists.into()),
+        Some(range) => [
+            &lines[..range.start],
+            &[block.as_str()],
+            &lines[range.end..],
+        ]
+        .concat()
+        .join("\n"),
+        None if content.is_empty() || content.ends_with('\n') => format!("{content}{block}"),
+        None => format!("{content}\n{block}"),
+    };
+    updated.push('\n');
+    Ok(updated)
+}
+
+/// Whether the rc file at `config_path` already holds a setup block or a legacy setup line.
+pub fn has_setup_block(config_path: &Path) -> bool {
+    fs::read_to_string(config_path).is_ok_and(|content| {
+        content
+            .lines()
+            .any(|line| line.trim() == BLOCK_START || is_legacy_setup_line(line))
+    })
+}
+
+/// Matches the unmarked lines older versions appended for bash/zsh and fish.
+fn is_legacy_setup_line(line: &str) -> bool {
+    let line = line.trim();
+    (line.starts_with("eval $( ") && line.contains(" alias ") && line.ends_with(')'))
+        || (line.contains(" alias ") && line.ends_with(" | source") && !line.starts_with('#'))
 }
 
 pub fn get_raw_aliases_from_env() -> String {
     env::var("SH_SHELL_ALIASES").unwrap_or(String::from(""))
 }
+
+pub fn get_raw_aliases_from_rc(rc_path: &Path) -> String {
+    fs::read_to_string(rc_path)
+        .map(|content| filter_alias_lines(&content))
+        .unwrap_or_default()
+}
+
+fn filter_alias_lines(content: &str) -> String {
+    // Indented definitions live inside functions or conditionals, so only top-level lines count.
+    content
+        .lines()
+        .filter(|line| line.starts_with("alias "))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Strips the quotes around an alias value as printed by `alias` (or written in an rc file).
+/// Everything after the first `=` is the value, so `=` inside it is kept as is.
+pub fn unquote_alias_value(value: &str) -> String {
+    if let Some(inner) = value
+        .strip_prefix('\'')
+        .and_then(|value| value.strip_suffix('\''))
+    {
+        // Shells print an embedded single quote as `'\''`.
+        return inner.replace("'\\''", "'");
+    }
+    value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .unwrap_or(value)
+        .to_string()
+}
+
+/// Quotes the program path for the generated shell code, which would otherwise split a path
+/// with spaces (e.g. under `Application Support`) into several words.
+pub fn quote_path(path: &Path) -> String {
+    shell_words::quote(&path.to_string_lossy()).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RC_FIXTURE: &str = "\
+# alias old='should not be used'
+export PATH=\"$HOME/bin:$PATH\"
+alias ll='ls -l'
+  alias nested='inside a block'
+alias gs=\"git status\"
+#alias commented='ignored'
+";
+
+    #[test]
+    fn test_unquote_alias_value() {
+        assert_eq!(unquote_alias_value("'a=b'"), "a=b");
+        assert_eq!(unquote_alias_value("\"ls -l\""), "ls -l");
+        assert_eq!(unquote_alias_value("'it'\\''s'"), "it's");
+        assert_eq!(unquote_alias_value("plain=value"), "plain=value");
+        assert_eq!(unquote_alias_value(""), "");
+    }
+
+    #[test]
+    fn test_filter_alias_lines() {
+        assert_eq!(
+            filter_alias_lines(RC_FIXTURE),
+            "alias ll='ls -l'\nalias gs=\"git status\""
+        );
+    }
+
+    #[test]
+    fn test_get_raw_aliases_from_rc() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let rc_path = temp_dir.path().join(".bashrc");
+        fs::write(&rc_path, RC_FIXTURE).expect("Failed to write rc file");
+
+        let raw = get_raw_aliases_from_rc(&rc_path);
+        assert!(raw.contains("alias ll='ls -l'"));
+        assert!(raw.contains("alias gs=\"git status\""));
+        assert!(!raw.contains("old"));
+        assert!(!raw.contains("commented"));
+        assert!(!raw.contains("nested"));
+    }
+
+    #[test]
+    fn test_get_raw_aliases_from_missing_rc() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        assert!(get_raw_aliases_from_rc(&temp_dir.path().join("missing")).is_empty());
+    }
+
+    #[test]
+    fn test_update_setup_block_appends_block() {
+        let updated = update_setup_block("export A=1\n", "eval $( /bin/theshit alias shit)", false)
+            .expect("Block should be added");
+        assert_eq!(
+            updated,
+            "export A=1\n# >>> theshit >>>\neval $( /bin/theshit alias shit)\n# <<< theshit <<<\n"
+        );
+    }
+
+    #[test]
+    fn test_update_setup_block_existing_without_replace() {
+        let content = "# >>> theshit >>>\neval $( /bin/theshit alias shit)\n# <<< theshit <<<\n";
+        let error = update_setup_block(content, "eval $( /bin/theshit alias oops)", false)
+            .expect_err("Existing block should not be touched");
+        assert_eq!(error.kind(), ErrorKind::AlreadyExists);
+    }
+
+    #[test]
+    fn test_update_setup_block_unterminated_block() {
+        let content = "# >>> theshit >>>\neval $( /bin/theshit alias shit)\nexport B=2\n";
+        for replace in [false, true] {
+            let error = update_setup_block(content, "eval $( /bin/theshit alias oops)", replace)
+                .expect_err("Unterminated block should not be touched");
+            assert_eq!(error.kind(), ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn test_update_setup_block_replaces_name() {
+        let content = "export A=1\n# >>> theshit >>>\neval $( /bin/theshit alias shit)\n# <<< theshit <<<\nexport B=2\n";
+        let updated = update_setup_block(content, "eval $( /bin/theshit alias oops)", true)
+            .expect("Block should be replaced");
+        assert_eq!(
+            updated,
+            "export A=1\n# >>> theshit >>>\neval $( /bin/theshit alias oops)\n# <<< theshit <<<\nexport B=2\n"
+        );
+    }
+
+    #[test]
+    fn test_update_setup_block_replaces_legacy_line() {
+        let content = "/usr/bin/theshit alias shit | source\nset -x A 1\n";
+        let updated = update_setup_block(content, "/usr/bin/theshit alias oops | source", true)
+            .expect("Legacy line should be replaced");
+        assert_eq!(
+            updated,
+            "# >>> theshit >>>\n/usr/bin/theshit alias oops | source\n# <<< theshit <<<\nset -x A 1\n"
+        );
+    }
+
+    #[test]
+    fn test_has_setup_block() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let rc = temp_dir.path().join(".bashrc");
+        assert!(!has_setup_block(&rc));
+        fs::write(&rc, "export A=1\n").expect("Failed to write rc file");
+        assert!(!has_setup_block(&rc));
+        fs::write(
+            &rc,
+            "export A=1\n# >>> theshit >>>\neval $( /bin/theshit alias shit)\n# <<< theshit <<<\n",
+        )
+        .expect("Failed to write rc file");
+        assert!(has_setup_block(&rc));
+        fs::write(&rc, "/usr/bin/theshit alias shit | source\n").expect("Failed to write rc file");
+        assert!(has_setup_block(&rc));
+    }
+
+    #[test]
+    fn test_setup_alias_rewrites_rc_file() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let rc_path = temp_dir.path().join(".bashrc");
+        fs::write(&rc_path, "export A=1").expect("Failed to write rc file");
+
+        setup_alias(
+            "eval $( /bin/theshit alias shit)".to_string(),
+            &rc_path,
+            false,
+        )
+        .expect("Setup should succeed");
+        setup_alias(
+            "eval $( /bin/theshit alias oops)".to_string(),
+            &rc_path,
+            true,
+        )
+        .expect("Replacing should succeed");
+
+        let content = fs::read_to_string(&rc_path).expect("Failed to read rc file");
+        assert!(content.contains("alias oops"));
+        assert!(!content.contains("alias shit"));
+        assert_eq!(content.matches(BLOCK_START).count(), 1);
+    }
+}
@@ -1,8 +1,10 @@
 use super::enums::Shell;
+use std::collections::HashSet;
 use std::str::FromStr;
 use std::{env, process};
 use sysinfo::{Pid, ProcessRefreshKind, RefreshKind, System};
 
+/// Read access to the process table, so shell detection can run against a fake process tree.
 pub trait ProcessInspector {
     fn get_parent_pid(&self, pid: u32) -> Option<u32>;
     fn get_exe_name(&self, pid: u32) -> Option<String>;
@@ -39,13 +41,45 @@ fn get_current_shell_by_env() -> Option<Shell> {
         .and_then(|shell| Shell::from_str(shell.as_str()).ok())
 }
 
-fn find_shell_in_process_tree(inspector: &impl ProcessInspector, start_pid: u32) -> Option<Shell> {
+/// How many ancestors to inspect before giving up; real process trees are far shallower.
+const MAX_PROCESS_TREE_DEPTH: usize = 64;
+
+/// Terminal multiplexers sit between the pane's shell and the login session, so the processes
+/// above them say nothing about the shell in use.
+fn is_multiplexer(exe_name: &str) -> bool {
+    let name = exe_name.split(':').next().unwrap_or(exe_name).trim();
+    name.eq_ignore_ascii_case("tmux") || name.eq_ignore_ascii_case("screen")
+}
+
+fn get_shell_from_shell_var() -> Option<Shell> {
+    env::var("SHELL")
+        .ok()
+        .as_deref()
+        .and_then(|path| path.rsplit('/').next())
+        .and_then(|name| Shell::from_str(name).ok())
+}
+
+/// Walks up from `start_pid` to the first shell. Reaching a multiplexer returns
+/// `multiplexer_shell` (the user's `$SHELL`) when it is known, otherwise the walk continues.
+fn find_shell_in_process_tree(
+    inspector: &impl ProcessInspector,
+    start_pid: u32,
+    multiplexer_shell: Option<Shell>,
+) -> Option<Shell> {
+    // PID reuse can make a parent point back into the chain, so stop on cycles too.
+    let mut visited = HashSet::new();
     let mut current_process = start_pid;
-    loop {
-        if let Some(exe_name) = inspector.get_exe_name(current_process)
-            && let Ok(shell) = Shell::from_str(&exe_name)
-        {
-            return Some(shell);
+    for _ in 0..MAX_PROCESS_TREE_DEPTH {
+        if !visited.insert(current_process) {
+            return None;
+        }
+        if let Some(exe_name) = inspector.get_exe_name(current_process) {
+            if let Ok(shell) = Shell::from_str(&exe_name) {
+                return Some(shell);
+            }
+            if is_multiplexer(&exe_name) && multiplexer_shell.is_some() {
+                return multiplexer_shell;
+            }
         }
 
         match inspector.get_parent_pid(current_process) {
@@ -53,6 +87,35 @@ fn find_shell_in_process_tree(inspector: &impl ProcessInspector, start_pid: u32)
             _ => return None,
         }
     }
+    None
+}
+
+/// Finds the shell `start_pid` runs under by walking up the process tree `inspector` describes.
+/// A terminal multiplexer on the way stands for the user's `$SHELL`, as in
+/// [`get_current_shell`].
+///
+/// ```ignore
+/// // pid 20 (cargo) was started from pid 10 (zsh).
+/// struct FakeTree;
+///
+/// impl ProcessInspector for FakeTree {
+///     fn get_parent_pid(&self, pid: u32) -> Option<u32> {
+///         (pid == 20).then_some(10)
+///     }
+///
+///     fn get_exe_name(&self, pid: u32) -> Option<String> {
+///         match pid {
+///             10 => Some("zsh".to_string()),
+///             20 => Some("cargo".to_string()),
+///             _ => None,
+///         }
+///     }
+/// }
+///
+/// assert!(matches!(detect_shell_with(&FakeTree, 20), Some(Shell::Zsh)));
+/// ```
+pub fn detect_shell_with(inspector: &impl ProcessInspector, start_pid: u32) -> Option<Shell> {
+    find_shell_in_process_tree(inspector, start_pid, get_shell_from_shell_var())
 }
 
 fn get_current_shell_by_process() -> Option<Shell> {
@@ -60,8 +123,9 @@ fn get_current_shell_by_process() -> Option<Shell> {
     system
         .refresh_specifics(RefreshKind::nothing().with_processes(ProcessRefreshKind::everything()));
     let inspector = SysinfoInspector { system: &system };
-    find_shell_in_process_tree(&inspector, process::id())
+    detect_shell_with(&inspector, process::id())
 }
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -89,10 +153,21 @@ mod tests {
             names: HashMap::from([(100, "bash".to_string())]),
         };
 
-        let shell = find_shell_in_process_tree(&tree, 100);
+        let shell = find_shell_in_process_tree(&tree, 100, None);
         assert!(matches!(shell, Some(Shell::Bash)));
     }
 
+    #[test]
+    fn detect_shell_with_custom_inspector() {
+        let tree = MockProcessTree {
+            parents: HashMap::from([(20, 10)]),
+            names: HashMap::from([(10, "zsh".to_string()), (20, "cargo".to_string())]),
+        };
+
+        assert!(matches!(detect_shell_with(&tree, 20), Some(Shell::Zsh)));
+        assert!(detect_shell_with(&tree, 30).is_none());
+    }
+
     #[test]
     fn find_shell_deep_in_tree() {
         let tree = MockProcessTree {
@@ -104,7 +179,7 @@ mod tests {
             ]),
         };
 
-        let shell = find_shell_in_process_tree(&tree, 300);
+        let shell = find_shell_in_process_tree(&tree, 300, None);
         assert!(matches!(shell, Some(Shell::Bash)));
     }
 
@@ -120,7 +195,7 @@ mod tests {
             ]),
         };
 
-        let shell = find_shell_in_process_tree(&tree, 400);
+        let shell = find_shell_in_process_tree(&tree, 400, None);
         assert!(matches!(shell, Some(Shell::Zsh)));
     }
 
@@ -131,7 +206,7 @@ mod tests {
             names: HashMap::from([(100, "cargo".to_string())]),
         };
 
-        let shell = find_shell_in_process_tree(&tree, 100);
+        let shell = find_shell_in_process_tree(&tree, 100, None);
         assert!(shell.is_none());
     }
 
@@ -142,7 +217,7 @@ mod tests {
             names: HashMap::from([(100, "cargo".to_string()), (0, "init".to_string())]),
         };
 
-        let shell = find_shell_in_process_tree(&tree, 100);
+        let shell = find_shell_in_process_tree(&tree, 100, None);
         assert!(shell.is_none());
     }
 
@@ -159,7 +234,7 @@ mod tests {
             ]),
         };
 
-        let shell = find_shell_in_process_tree(&tree, 500);
+        let shell = find_shell_in_process_tree(&tree, 500, None);
         assert!(matches!(shell, Some(Shell::Zsh)));
     }
 
@@ -170,7 +245,7 @@ mod tests {
             names: HashMap::new(),
         };
 
-        let shell = find_shell_in_process_tree(&tree, 300);
+        let shell = find_shell_in_process_tree(&tree, 300, None);
         assert!(shell.is_none());
     }
 
@@ -185,7 +260,7 @@ mod tests {
             ]),
         };
 
-        let shell = find_shell_in_process_tree(&tree, 300);
+        let shell = find_shell_in_process_tree(&tree, 300, None);
         assert!(shell.is_none());
     }
 
@@ -196,7 +271,7 @@ mod tests {
             names: HashMap::from([(100, "fish".to_string()), (200, "cargo".to_string())]),
         };
 
-        let shell = find_shell_in_process_tree(&tree, 200);
+        let shell = find_shell_in_process_tree(&tree, 200, None);
         assert!(matches!(shell, Some(Shell::Fish)));
     }
 
@@ -207,7 +282,90 @@ mod tests {
             names: HashMap::from([(100, "zsh".to_string()), (200, "cargo".to_string())]),
         };
 
-        let shell = find_shell_in_process_tree(&tree, 200);
+        let shell = find_shell_in_process_tree(&tree, 200, None);
+        assert!(matches!(shell, Some(Shell::Zsh)));
+    }
+
+    #[test]
+    fn no_shell_found_in_cyclic_tree() {
+        let tree = MockProcessTree {
+            parents: HashMap::from([(300, 200), (200, 100), (100, 300)]),
+            names: HashMap::from([
+                (100, "init".to_string()),
+                (200, "cargo".to_string()),
+                (300, "my_app".to_string()),
+            ]),
+        };
+
+        let shell = find_shell_in_process_tree(&tree, 300, None);
+        assert!(shell.is_none());
+    }
+
+    #[test]
+    fn no_shell_found_beyond_max_depth() {
+        let depth = MAX_PROCESS_TREE_DEPTH as u32;
+        let tree = MockProcessTree {
+            parents: (1..=depth).map(|pid| (pid + 1, pid)).collect(),
+            names: HashMap::from([(1, "bash".to_string())]),
+        };
+
+        assert!(find_shell_in_process_tree(&tree, depth + 1, None).is_none());
+        assert!(matches!(
+            find_shell_in_process_tree(&tree, depth, None),
+            Some(Shell::Bash)
+        ));
+    }
+
+    #[test]
+    fn multiplexer_uses_shell_var() {
+        let tree = MockProcessTree {
+            parents: HashMap::from([(300, 200), (200, 100)]),
+            names: HashMap::from([
+                (100, "bash".to_string()),
+                (200, "tmux: server".to_string()),
+                (300, "my_app".to_string()),
+            ]),
+        };
+
+        let shell = find_shell_in_process_tree(&tree, 300, Some(Shell::Zsh));
         assert!(matches!(shell, Some(Shell::Zsh)));
     }
+
+    #[test]
+    fn multiplexer_without_shell_var_keeps_walking() {
+        let tree = MockProcessTree {
+            parents: HashMap::from([(300, 200), (200, 100)]),
+            names: HashMap::from([
+                (100, "fish".to_string()),
+                (200, "screen".to_string()),
+                (300, "my_app".to_string()),
+            ]),
+        };
+
+        let shell = find_shell_in_process_tree(&tree, 300, None);
+        assert!(matches!(shell, Some(Shell::Fish)));
+    }
+
+    #[test]
+    fn shell_below_multiplexer_wins() {
+        let tree = MockProcessTree {
+            parents: HashMap::from([(300, 200), (200, 100)]),
+            names: HashMap::from([
+                (100, "tmux".to_string()),
+                (200, "bash".to_string()),
+                (300, "my_app".to_string()),
+            ]),
+        };
+
+        let shell = find_shell_in_process_tree(&tree, 300, Some(Shell::Zsh));
+        assert!(matches!(shell, Some(Shell::Bash)));
+    }
+
+    #[test]
+    fn recognizes_multiplexer_names() {
+        assert!(is_multiplexer("tmux"));
+        assert!(is_multiplexer("tmux: server"));
+        assert!(is_multiplexer("SCREEN"));
+        assert!(!is_multiplexer("tmuxinator"));
+    }
 }
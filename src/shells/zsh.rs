@@ -1,64 +1,147 @@
-use crate::shells::generic;
+use crate::shells::{CompletionSpec, Shell, generic};
 use std::collections::HashMap;
 use std::io::{ErrorKind, Result};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 pub fn get_shell_function(name: &str, path: &Path) -> String {
     format!(
         "
 {name}() {{
-    export SH_SHELL=zsh;
+    [[ $- == *i* ]] || return 1;
+    export SH_SHELL={shell};
     SH_PREV_CMD=\"$(fc -ln -1)\";
     export SH_PREV_CMD;
+    SH_HISTORY=\"$(fc -lnr -10 -1)\";
+    export SH_HISTORY;
     SH_SHELL_ALIASES=$(alias);
     export SH_SHELL_ALIASES;
 
     SH_CMD=$(
-      {} fix $@
+      {path} fix $@
     ) && eval \"$SH_CMD\";
 
     unset SH_SHELL_ALIASES;
+    unset SH_HISTORY;
     unset SH_PREV_CMD;
     unset SH_SHELL;
 }}
+
+{name}-current() {{
+    local SH_CMD;
+    SH_CMD=$(SH_SHELL={shell} {path} fix --current --command \"$BUFFER\") || return;
+    BUFFER=$SH_CMD;
+    CURSOR=${{#BUFFER}};
+}}
+if [[ -o zle ]]; then
+    zle -N {name}-current;
+    bindkey '^X^F' {name}-current;
+fi
     ",
-        path.display()
+        shell = Shell::Zsh,
+        path = generic::quote_path(path)
     )
     .trim()
     .to_string()
 }
 
-pub fn setup_alias(name: &str, program_path: &Path) -> Result<()> {
-    let config_path = dirs::home_dir().ok_or(ErrorKind::NotFound)?.join(".zshrc");
+pub fn get_config_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".zshrc"))
+}
+
+pub fn setup_alias(name: &str, program_path: &Path, replace: bool) -> Result<()> {
+    let config_path = get_config_path().ok_or(ErrorKind::NotFound)?;
     generic::setup_alias(
-        format!("eval $( {} alias {})", program_path.display(), name),
+        get_setup_command(name, program_path),
         config_path.as_path(),
+        replace,
     )
 }
 
-pub fn get_aliases() -> HashMap<String, String> {
-    parse_alias(generic::get_raw_aliases_from_env())
+fn get_setup_command(name: &str, program_path: &Path) -> String {
+    format!(
+        "eval $( {} alias {})",
+        generic::quote_path(program_path),
+        name
+    )
 }
 
-fn parse_alias(raw_aliases: String) -> HashMap<String, String> {
+pub fn parse_alias(raw_aliases: &str) -> HashMap<String, String> {
     let mut aliases: HashMap<String, String> = HashMap::new();
     for raw_alias in raw_aliases.split('\n') {
         if !raw_alias.contains('=') || raw_alias.is_empty() {
             continue;
         }
-        if let Some((name, mut value)) = raw_alias.split_once('=') {
-            let value_bytes = value.as_bytes();
-            if (value_bytes[0] == b'"' && value_bytes[value.len() - 1] == b'"')
-                || (value_bytes[0] == b'\'' && value_bytes[value.len() - 1] == b'\'')
-            {
-                value = &value[1..value.len() - 1];
+        if let Some((name, value)) = raw_alias.split_once('=') {
+            let name = name.strip_prefix("alias ").unwrap_or(name);
+            // `f() { x=1; }` or `function f { x=1 }` has an `=` too but isn't an alias.
+            if name.is_empty() || name.contains(|c: char| c.is_whitespace() || "(){}".contains(c)) {
+                continue;
             }
-            aliases.insert(name.to_string(), value.to_string());
+            let value = generic::unquote_alias_value(strip_trailing_comment(value));
+            aliases.insert(name.to_string(), value);
         }
     }
     aliases
 }
 
+/// Cuts an unquoted ` # comment` off an alias value; a `#` inside quotes or within a word
+/// (`a#b`) is kept.
+fn strip_trailing_comment(value: &str) -> &str {
+    let mut quote = None;
+    let mut escaped = false;
+    let mut previous = ' ';
+    for (index, c) in value.char_indices() {
+        match (quote, c) {
+            _ if escaped => escaped = false,
+            (Some('"') | None, '\\') => escaped = true,
+            (Some(q), c) if c == q => quote = None,
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '#') if previous.is_whitespace() => return value[..index].trim_end(),
+            _ => {}
+        }
+        previous = c;
+    }
+    value
+}
+
+pub fn get_completions(spec: &CompletionSpec) -> String {
+    let function = format!("_{}", spec.program.replace('-', "_"));
+    let subcommands = spec
+        .subcommands
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>();
+    let cases = spec
+        .subcommands
+        .iter()
+        .map(|(name, options)| format!("            {name}) compadd -- {} ;;\n", options.join(" ")))
+        .collect::<String>();
+    format!(
+        "#compdef {program}
+
+{function}() {{
+    if (( CURRENT == 2 )); then
+        compadd -- {} {}
+    else
+        case ${{words[2]}} in
+{cases}            *) _files ;;
+        esac
+    fi
+}}
+
+# Works both autoloaded from $fpath and sourced directly.
+if [[ \"$funcstack[1]\" == \"{function}\" ]]; then
+    {function} \"$@\"
+else
+    compdef {function} {program}
+fi
+",
+        subcommands.join(" "),
+        spec.options.join(" "),
+        program = spec.program
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -71,6 +154,22 @@ mod tests {
         assert!(result.contains("shit()"));
     }
 
+    #[test]
+    fn test_get_shell_function_guards_interactivity() {
+        let path = PathBuf::from("/usr/bin/theshit");
+        let result = get_shell_function("shit", &path);
+        assert!(result.contains("[[ $- == *i* ]] || return 1;"));
+    }
+
+    #[test]
+    fn test_get_shell_function_binds_current_buffer_widget() {
+        let path = PathBuf::from("/usr/bin/theshit");
+        let result = get_shell_function("shit", &path);
+        assert!(result.contains("/usr/bin/theshit fix --current --command \"$BUFFER\""));
+        assert!(result.contains("zle -N shit-current;"));
+        assert!(result.contains("bindkey '^X^F' shit-current;"));
+    }
+
     #[test]
     fn test_get_shell_function_contains_path() {
         let path = PathBuf::from("/usr/bin/theshit");
@@ -78,6 +177,22 @@ mod tests {
         assert!(result.contains("/usr/bin/theshit"));
     }
 
+    #[test]
+    fn test_get_shell_function_quotes_path_with_spaces() {
+        let path = PathBuf::from("/Users/me/Library/Application Support/theshit");
+        let result = get_shell_function("shit", &path);
+        assert!(result.contains("'/Users/me/Library/Application Support/theshit' fix $@"));
+    }
+
+    #[test]
+    fn test_get_setup_command_quotes_path_with_spaces() {
+        let path = PathBuf::from("/Users/me/Library/Application Support/theshit");
+        assert_eq!(
+            get_setup_command("shit", &path),
+            "eval $( '/Users/me/Library/Application Support/theshit' alias shit)"
+        );
+    }
+
     #[test]
     fn test_get_shell_function_exports_shell_type() {
         let path = PathBuf::from("/usr/bin/theshit");
@@ -85,57 +200,128 @@ mod tests {
         assert!(result.contains("export SH_SHELL=zsh"));
     }
 
+    #[test]
+    fn test_get_shell_function_exports_history() {
+        let path = PathBuf::from("/usr/bin/theshit");
+        let result = get_shell_function("shit", &path);
+        assert!(result.contains("SH_HISTORY=\"$(fc -lnr -10 -1)\""));
+        assert!(result.contains("unset SH_HISTORY"));
+    }
+
     #[test]
     fn test_parse_alias_empty() {
-        let aliases = parse_alias("".to_string());
+        let aliases = parse_alias("");
         assert!(aliases.is_empty());
     }
 
     #[test]
     fn test_parse_alias_single_alias() {
-        let aliases = parse_alias("ll='ls -l'".to_string());
+        let aliases = parse_alias("ll='ls -l'");
         assert_eq!(aliases.get("ll"), Some(&"ls -l".to_string()));
     }
 
     #[test]
     fn test_parse_alias_multiple_aliases() {
-        let aliases = parse_alias("ll='ls -l'\nla='ls -la'".to_string());
+        let aliases = parse_alias("ll='ls -l'\nla='ls -la'");
         assert_eq!(aliases.get("ll"), Some(&"ls -l".to_string()));
         assert_eq!(aliases.get("la"), Some(&"ls -la".to_string()));
     }
 
     #[test]
     fn test_parse_alias_with_double_quotes() {
-        let aliases = parse_alias("grep=\"grep --color=auto\"".to_string());
+        let aliases = parse_alias("grep=\"grep --color=auto\"");
         assert_eq!(aliases.get("grep"), Some(&"grep --color=auto".to_string()));
     }
 
     #[test]
     fn test_parse_alias_with_single_quotes() {
-        let aliases = parse_alias("cls='clear'".to_string());
+        let aliases = parse_alias("cls='clear'");
         assert_eq!(aliases.get("cls"), Some(&"clear".to_string()));
     }
 
     #[test]
     fn test_parse_alias_mixed_quotes() {
-        let aliases = parse_alias("ll='ls -l'\ngrep=\"grep --color=auto\"".to_string());
+        let aliases = parse_alias("ll='ls -l'\ngrep=\"grep --color=auto\"");
         assert_eq!(aliases.get("ll"), Some(&"ls -l".to_string()));
         assert_eq!(aliases.get("grep"), Some(&"grep --color=auto".to_string()));
     }
 
     #[test]
     fn test_parse_alias_ignores_invalid_format() {
-        let aliases = parse_alias("not_an_alias\ngrep='grep --color=auto'".to_string());
+        let aliases = parse_alias("not_an_alias\ngrep='grep --color=auto'");
         assert_eq!(aliases.get("grep"), Some(&"grep --color=auto".to_string()));
         assert_eq!(aliases.get("not_an_alias"), None);
     }
 
+    #[test]
+    fn test_parse_alias_rc_file_form() {
+        let aliases = parse_alias("alias ll='ls -l'");
+        assert_eq!(aliases.get("ll"), Some(&"ls -l".to_string()));
+    }
+
     #[test]
     fn test_parse_alias_with_spaces_in_value() {
-        let aliases = parse_alias("myalias='command with spaces'".to_string());
+        let aliases = parse_alias("myalias='command with spaces'");
         assert_eq!(
             aliases.get("myalias"),
             Some(&"command with spaces".to_string())
         );
     }
+
+    #[test]
+    fn test_parse_alias_value_with_equals_sign() {
+        let aliases = parse_alias("x='a=b'\nenv=\"FOO=1 BAR=2 make\"");
+        assert_eq!(aliases.get("x"), Some(&"a=b".to_string()));
+        assert_eq!(aliases.get("env"), Some(&"FOO=1 BAR=2 make".to_string()));
+    }
+
+    #[test]
+    fn test_parse_alias_empty_or_unbalanced_value() {
+        let aliases = parse_alias("empty=\nquote='");
+        assert_eq!(aliases.get("empty"), Some(&String::new()));
+        assert_eq!(aliases.get("quote"), Some(&"'".to_string()));
+    }
+
+    #[test]
+    fn test_parse_alias_strips_trailing_comment() {
+        let aliases = parse_alias(
+            "alias ll='ls -l' # long listing\ngs=\"git status\"  #status\nhash='echo a # b'\ntag=echo#1",
+        );
+        assert_eq!(aliases.get("ll"), Some(&"ls -l".to_string()));
+        assert_eq!(aliases.get("gs"), Some(&"git status".to_string()));
+        assert_eq!(aliases.get("hash"), Some(&"echo a # b".to_string()));
+        assert_eq!(aliases.get("tag"), Some(&"echo#1".to_string()));
+    }
+
+    #[test]
+    fn test_parse_alias_ignores_function_definitions() {
+        let aliases = parse_alias(
+            "mkcd() { dir=$1; mkdir -p $dir && cd $dir; }\nfunction greet { name=world; }\nll='ls -l'",
+        );
+        assert_eq!(aliases.len(), 1);
+        assert_eq!(aliases.get("ll"), Some(&"ls -l".to_string()));
+    }
+
+    fn completion_spec() -> CompletionSpec {
+        CompletionSpec {
+            program: "theshit".to_string(),
+            options: vec!["--shell".to_string(), "--help".to_string()],
+            subcommands: vec![
+                ("alias".to_string(), vec!["--help".to_string()]),
+                (
+                    "fix".to_string(),
+                    vec!["--command".to_string(), "--help".to_string()],
+                ),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_get_completions() {
+        let result = get_completions(&completion_spec());
+        assert!(result.starts_with("#compdef theshit"));
+        assert!(result.contains("compadd -- alias fix --shell --help"));
+        assert!(result.contains("fix) compadd -- --command --help ;;"));
+        assert!(result.contains("compdef _theshit theshit"));
+    }
 }
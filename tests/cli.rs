@@ -0,0 +1,411 @@
+//! End-to-end tests running the built binary against an isolated config directory.
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::process::{Command, Output};
+use tempfile::TempDir;
+
+/// A home with its own config directory holding only the given native rules.
+fn isolated_home(native_rules: &[&str]) -> TempDir {
+    let home = tempfile::tempdir().expect("Failed to create temp dir");
+    let active = home.path().join(".config/theshit/fix_rules/active");
+    fs::create_dir_all(&active).expect("Failed to create rules dir");
+    for rule in native_rules {
+        fs::write(active.join(format!("{rule}.native")), "").expect("Failed to write rule");
+    }
+    home
+}
+
+fn theshit_command(home: &Path, args: &[&str]) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_theshit"));
+    command
+        .args(args)
+        .env("HOME", home)
+        .env("XDG_CONFIG_HOME", home.join(".config"))
+        .env("XDG_CACHE_HOME", home.join(".cache"))
+        .env_remove("SH_PREV_CMD")
+        .env_remove("SH_SHELL")
+        .env_remove("SH_SHELL_ALIASES")
+        .env_remove("SH_HISTORY")
+        .env_remove("SH_ALIASES")
+        .env_remove("THESHIT_QUIET")
+        .env_remove("THESHIT_DEBUG")
+        .current_dir(home);
+    command
+}
+
+fn theshit(home: &Path, args: &[&str]) -> Output {
+    theshit_command(home, args)
+        .output()
+        .expect("Failed to run theshit")
+}
+
+#[test]
+fn fix_exits_zero_when_a_fix_is_printed() {
+    let home = isolated_home(&["to_cd"]);
+    let output = theshit(
+        home.path(),
+        &[
+            "--shell",
+            "bash",
+            "fix",
+            "--mode",
+            "auto",
+            "--command",
+            "cs /tmp",
+            "--stderr",
+            "",
+        ],
+    );
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "cd /tmp\n");
+}
+
+#[test]
+fn fix_exits_non_zero_without_a_fix() {
+    let home = isolated_home(&["to_cd"]);
+    let output = theshit(
+        home.path(),
+        &[
+            "--shell",
+            "bash",
+            "fix",
+            "--mode",
+            "auto",
+            "--command",
+            "ls /tmp",
+            "--stderr",
+            "",
+        ],
+    );
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No fix found for: ls /tmp"));
+}
+
+/// Runs `theshit fix` the way the shell function does: the command comes from `SH_PREV_CMD` and
+/// the shell from `SH_SHELL`, with no `--command` or `--shell`.
+fn theshit_from_shell(home: &Path, prev_cmd: &str, args: &[&str]) -> Output {
+    theshit_command(home, &[&["fix", "--mode", "auto"], args].concat())
+        .env("SH_PREV_CMD", prev_cmd)
+        .env("SH_SHELL", "bash")
+        .output()
+        .expect("Failed to run theshit")
+}
+
+#[test]
+fn fix_reads_previous_command_from_env() {
+    let home = isolated_home(&["to_cd"]);
+    let output = theshit_from_shell(home.path(), "cs /tmp", &[]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "cd /tmp\n");
+}
+
+#[test]
+fn history_depth_never_reruns_older_commands() {
+    let home = isolated_home(&["mkdir_p", "to_cd"]);
+    let marker = home.path().join("ran");
+    let history = format!("true\nmkdir {}\ncs /tmp\n", marker.display());
+    let output = theshit_command(
+        home.path(),
+        &["fix", "--mode", "suggest", "--history-depth", "3"],
+    )
+    .env("SH_PREV_CMD", "true")
+    .env("SH_SHELL", "bash")
+    .env("SH_HISTORY", history)
+    .output()
+    .expect("Failed to run theshit");
+    assert_eq!(output.status.code(), Some(0));
+    assert!(!marker.exists());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("this fixes an earlier one:"));
+    assert!(stderr.contains(" cs /tmp\n"));
+    assert!(stderr.contains("cd /tmp"));
+}
+
+#[test]
+fn fix_adds_sudo_to_previous_command() {
+    let home = isolated_home(&["sudo", "to_cd"]);
+    let output = theshit_from_shell(
+        home.path(),
+        "cat /etc/shadow",
+        &[
+            "--stderr",
+            "cat: /etc/shadow: Permission denied",
+            "--exit-code",
+            "1",
+        ],
+    );
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "sudo cat /etc/shadow\n"
+    );
+}
+
+#[test]
+fn fix_reruns_previous_command_for_its_output() {
+    let home = isolated_home(&["mkdir_p", "sudo"]);
+    let output = theshit_from_shell(home.path(), "mkdir missing/child", &[]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "mkdir -p missing/child\n"
+    );
+    assert!(!home.path().join("missing").exists());
+}
+
+#[test]
+fn fix_without_previous_command_fails() {
+    let home = isolated_home(&["to_cd"]);
+    let output = theshit(home.path(), &["--shell", "bash", "fix"]);
+    assert_ne!(output.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("SH_PREV_CMD"));
+}
+
+/// A home with a Python rule that is skipped because others can write to it.
+fn home_with_insecure_rule() -> TempDir {
+    let home = isolated_home(&[]);
+    let rule = home
+        .path()
+        .join(".config/theshit/fix_rules/active/insecure.py");
+    fs::write(
+        &rule,
+        "def match(c, o, e): return True\ndef fix(c, o, e): return 'never'\n",
+    )
+    .expect("Failed to write rule");
+    fs::set_permissions(&rule, fs::Permissions::from_mode(0o666))
+        .expect("Failed to set permissions");
+    home
+}
+
+#[test]
+fn fix_reports_skipped_rules() {
+    let home = home_with_insecure_rule();
+    let output = theshit(
+        home.path(),
+        &[
+            "--shell",
+            "bash",
+            "fix",
+            "--mode",
+            "auto",
+            "--command",
+            "ls /tmp",
+            "--stderr",
+            "",
+        ],
+    );
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("skipped due to permissions"));
+}
+
+#[test]
+fn quiet_fix_prints_nothing_to_stderr() {
+    let home = home_with_insecure_rule();
+    let output = theshit(
+        home.path(),
+        &[
+            "--shell",
+            "bash",
+            "fix",
+            "--quiet",
+            "--mode",
+            "auto",
+            "--command",
+            "ls /tmp",
+            "--stderr",
+            "",
+        ],
+    );
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_theshit"))
+        .args([
+            "--shell",
+            "bash",
+            "fix",
+            "--mode",
+            "auto",
+            "--command",
+            "ls /tmp",
+            "--stderr",
+            "",
+        ])
+        .env("HOME", home.path())
+        .env("XDG_CONFIG_HOME", home.path().join(".config"))
+        .env("THESHIT_QUIET", "1")
+        .current_dir(home.path())
+        .output()
+        .expect("Failed to run theshit");
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+}
+
+#[test]
+fn debug_lists_every_evaluated_rule() {
+    let home = isolated_home(&["mkdir_p", "sudo", "to_cd"]);
+    let output = theshit_command(
+        home.path(),
+        &[
+            "--shell",
+            "bash",
+            "fix",
+            "--mode",
+            "auto",
+            "--command",
+            "cs /tmp",
+            "--stderr",
+            "",
+        ],
+    )
+    .env("THESHIT_DEBUG", "1")
+    .output()
+    .expect("Failed to run theshit");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "cd /tmp\n");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("shell: Bash"));
+    assert!(stderr.contains("expanded: cs /tmp"));
+    assert!(stderr.contains("native rule mkdir_p: no match"));
+    assert!(stderr.contains("native rule sudo: no match"));
+    assert!(stderr.contains("native rule to_cd: matched"));
+    assert!(stderr.contains("candidate 1: cd /tmp (confidence"));
+}
+
+#[test]
+fn shells_lists_every_supported_shell() {
+    let home = isolated_home(&[]);
+    fs::write(
+        home.path().join(".zshrc"),
+        "# >>> theshit >>>\neval $( /usr/bin/theshit alias shit)\n# <<< theshit <<<\n",
+    )
+    .expect("Failed to write .zshrc");
+    let output = theshit(home.path(), &["--shell", "zsh", "shells"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "bash: not set up\n\
+         zsh: set up (current)\n\
+         fish: not set up\n"
+    );
+}
+
+#[test]
+fn test_rule_reports_a_match() {
+    let home = isolated_home(&[]);
+    let output = theshit(
+        home.path(),
+        &[
+            "--shell",
+            "bash",
+            "test-rule",
+            "sudo",
+            "--command",
+            "mkdir /etc/app",
+            "--stderr",
+            "mkdir: cannot create directory '/etc/app': Permission denied",
+        ],
+    );
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "sudo: matched\n  sudo mkdir /etc/app\n"
+    );
+}
+
+#[test]
+fn test_rule_reports_no_match() {
+    let home = isolated_home(&[]);
+    let output = theshit(
+        home.path(),
+        &[
+            "--shell",
+            "bash",
+            "test-rule",
+            "to_cd",
+            "--command",
+            "ls /tmp",
+        ],
+    );
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "to_cd: no match\n");
+
+    let output = theshit(
+        home.path(),
+        &["--shell", "bash", "test-rule", "nope", "--command", "ls"],
+    );
+    assert_ne!(output.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("neither a native rule"));
+}
+
+#[test]
+fn fix_uses_previous_command_verbatim() {
+    let home = isolated_home(&[]);
+    fs::write(
+        home.path()
+            .join(".config/theshit/fix_rules/active/verbatim.toml"),
+        "[verbatim]\nmatch_command = '^  echo  \"a  b\"$'\nreplace = 'matched'\n",
+    )
+    .expect("Failed to write rule");
+    let output = theshit_from_shell(home.path(), "  echo  \"a  b\"\n", &["--stderr", ""]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "matched\n");
+}
+
+#[test]
+fn fix_profile_times_every_stage() {
+    let home = isolated_home(&["to_cd"]);
+    fs::write(
+        home.path().join(".config/theshit/fix_rules/active/never.py"),
+        "def match(command, stdout, stderr):\n    return False\n\n\ndef fix(command, stdout, stderr):\n    return command\n",
+    )
+    .expect("Failed to write rule");
+    let output = theshit(
+        home.path(),
+        &[
+            "--shell",
+            "bash",
+            "fix",
+            "--profile",
+            "--mode",
+            "auto",
+            "--command",
+            "cs /tmp",
+            "--stderr",
+            "",
+        ],
+    );
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "cd /tmp\n");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    for stage in [
+        "shell detection",
+        "alias expansion",
+        "native rules",
+        "python init",
+        "python rules",
+    ] {
+        assert!(
+            stderr.contains(&format!("profile: {stage}: ")),
+            "{stage} missing from {stderr}"
+        );
+    }
+    assert!(!stderr.contains(": -"), "a stage didn't run: {stderr}");
+
+    let output = theshit(
+        home.path(),
+        &[
+            "--shell",
+            "bash",
+            "fix",
+            "--mode",
+            "auto",
+            "--command",
+            "cs /tmp",
+            "--stderr",
+            "",
+        ],
+    );
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("profile:"));
+}